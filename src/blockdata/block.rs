@@ -18,13 +18,126 @@
 //! This module defines structures of blocks.
 //!
 
-use crate::blockdata::transaction::Transaction;
-use crate::consensus::encode::VarInt;
-use crate::cryptonote::hash;
+use crate::blockdata::coinbase::MINED_MONEY_UNLOCK_WINDOW;
+use crate::blockdata::transaction::{Transaction, TxIn};
+use crate::consensus::encode::{serialize, VarInt};
+use crate::consensus::hardfork::hard_fork_version;
+use crate::cryptonote::hash::{self, Hashable};
+use crate::network::Network;
+use crate::util::ringct::RctType;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt::{Display, Error, Formatter};
 
+/// Number of previous blocks used to compute the median timestamp
+///
+/// **Same as** `BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW` in `cryptonote_config.h`
+pub const TIMESTAMP_CHECK_WINDOW: usize = 60;
+
+/// Maximum number of seconds a block timestamp can be ahead of the current time
+///
+/// **Same as** `CRYPTONOTE_BLOCK_FUTURE_TIME_LIMIT` in `cryptonote_config.h`
+pub const BLOCK_FUTURE_TIME_LIMIT: u64 = 60 * 60 * 2;
+
+/// Maximum number of transactions in a block
+///
+/// **Same as** `CRYPTONOTE_MAX_TX_PER_BLOCK` in `cryptonote_config.h`
+pub const MAX_TX_PER_BLOCK: usize = 0x1000_0000;
+
+/// Hard fork version from which RingCT signatures are rejected in miner transactions
+const HF_VERSION_REJECT_SIGS_IN_COINBASE: u8 = 12;
+
+/// Possible errors when checking a block header
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum HeaderError {
+    /// Not enough previous timestamps are given to compute the median
+    #[error("{found} previous timestamps given, {expected} needed")]
+    NotEnoughTimestamps {
+        /// Number of timestamps needed
+        expected: usize,
+        /// Number of timestamps given
+        found: usize,
+    },
+    /// The timestamp is below the median of the previous blocks
+    #[error("timestamp {timestamp} is below the median {median} of the previous blocks")]
+    TimestampTooOld {
+        /// Block timestamp
+        timestamp: u64,
+        /// Median of the previous timestamps
+        median: u64,
+    },
+    /// The timestamp is too far in the future
+    #[error("timestamp {timestamp} is after the limit {limit}")]
+    TimestampTooFarInFuture {
+        /// Block timestamp
+        timestamp: u64,
+        /// Maximum accepted timestamp
+        limit: u64,
+    },
+    /// The major version does not match the hard fork version for the height
+    #[error("major version {found} does not match hard fork version {expected}")]
+    InvalidMajorVersion {
+        /// Hard fork version at the block height
+        expected: u8,
+        /// Block major version
+        found: u64,
+    },
+    /// The minor version votes for an older hard fork
+    #[error("vote {vote} is below hard fork version {hard_fork}")]
+    InvalidVote {
+        /// Hard fork version at the block height
+        hard_fork: u8,
+        /// Version the block votes for
+        vote: u64,
+    },
+    /// The miner transaction does not have a single generation input
+    #[error("miner transaction must have a single generation input")]
+    InvalidMinerInput,
+    /// The miner transaction input height does not match the block height
+    #[error("miner transaction height {found} does not match block height {expected}")]
+    InvalidMinerHeight {
+        /// Block height
+        expected: u64,
+        /// Height in the miner transaction input
+        found: u64,
+    },
+    /// The miner transaction unlock time is not the block height plus the unlock window
+    #[error("miner transaction unlock time {found} is not {expected}")]
+    InvalidMinerUnlockTime {
+        /// Expected unlock time
+        expected: u64,
+        /// Miner transaction unlock time
+        found: u64,
+    },
+    /// The miner transaction has RingCT signatures
+    #[error("RingCT signatures are not allowed in miner transaction")]
+    MinerRctSignatures,
+    /// The block contains more transactions than allowed
+    #[error("too many transactions: {0}")]
+    TooManyTransactions(usize),
+    /// A transaction hash is included more than once
+    #[error("duplicate transaction hash: {0}")]
+    DuplicateTransaction(hash::Hash),
+    /// The miner transaction hash is included in the transaction hashes
+    #[error("miner transaction is included in the transaction hashes")]
+    MinerTxIncluded,
+}
+
+/// Chain state needed to check a block header
+#[derive(Debug, Clone)]
+pub struct HeaderContext<'a> {
+    /// Network the block belongs to
+    pub network: Network,
+    /// Height of the block to check
+    pub height: u64,
+    /// Timestamps of the previous blocks ordered by height, only the last
+    /// [`TIMESTAMP_CHECK_WINDOW`] are used
+    pub previous_timestamps: &'a [u64],
+    /// Current time as a Unix timestamp in seconds
+    pub current_time: u64,
+}
+
 /// Monero block header
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
@@ -85,6 +198,196 @@ impl Display for Block {
 
 impl_consensus_encoding!(Block, header, miner_tx, tx_hashes);
 
+impl Block {
+    /// Return the Merkle root of the block transactions, the miner transaction hash is always the
+    /// first leaf of the tree
+    pub fn tree_root(&self) -> hash::Hash {
+        let mut hashes = Vec::with_capacity(1 + self.tx_hashes.len());
+        hashes.push(self.miner_tx.hash());
+        hashes.extend_from_slice(&self.tx_hashes);
+        hash::tree_hash(&hashes).expect("The miner transaction is always present. QED")
+    }
+
+    /// Return the Merkle branch of the miner transaction in the block transactions tree, used to
+    /// prove that the miner transaction, e.g. its merge mining tag, is committed in the block id
+    pub fn miner_tx_branch(&self) -> hash::MerkleBranch {
+        let mut hashes = Vec::with_capacity(1 + self.tx_hashes.len());
+        hashes.push(self.miner_tx.hash());
+        hashes.extend_from_slice(&self.tx_hashes);
+        hash::tree_branch(&hashes, 0).expect("The miner transaction is always present. QED")
+    }
+
+    /// Check the block header and miner transaction against the consensus rules that do not
+    /// require the full chain: timestamp, version and vote, miner transaction height and unlock
+    /// time, and the transaction hashes list
+    ///
+    /// The timestamp must not be below the median of the last [`TIMESTAMP_CHECK_WINDOW`] blocks,
+    /// the check is skipped for the first blocks of the chain, and must not be more than
+    /// [`BLOCK_FUTURE_TIME_LIMIT`] seconds ahead of the current time. The major version must be
+    /// the hard fork version at the block height and the minor version must vote for the same or
+    /// a newer version, a minor version of 0 votes for version 1.
+    ///
+    /// **Same as** [`check_block_timestamp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_core/blockchain.cpp),
+    /// [`HardFork::check`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/hardforks/hardfork.cpp)
+    /// and [`prevalidate_miner_transaction`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_core/blockchain.cpp)
+    pub fn check_header(&self, context: &HeaderContext<'_>) -> Result<(), HeaderError> {
+        self.check_timestamp(context)?;
+
+        let hf_version = hard_fork_version(context.network, context.height);
+        let major_version = *self.header.major_version;
+        if major_version != hf_version as u64 {
+            return Err(HeaderError::InvalidMajorVersion {
+                expected: hf_version,
+                found: major_version,
+            });
+        }
+        let vote = match *self.header.minor_version {
+            0 => 1,
+            vote => vote,
+        };
+        if vote < hf_version as u64 {
+            return Err(HeaderError::InvalidVote {
+                hard_fork: hf_version,
+                vote,
+            });
+        }
+
+        self.check_miner_tx(context.height, hf_version)?;
+        self.check_tx_hashes()
+    }
+
+    fn check_timestamp(&self, context: &HeaderContext<'_>) -> Result<(), HeaderError> {
+        let timestamp = *self.header.timestamp;
+        let limit = context.current_time.saturating_add(BLOCK_FUTURE_TIME_LIMIT);
+        if timestamp > limit {
+            return Err(HeaderError::TimestampTooFarInFuture { timestamp, limit });
+        }
+
+        if context.height < TIMESTAMP_CHECK_WINDOW as u64 {
+            return Ok(());
+        }
+        let previous = context.previous_timestamps;
+        if previous.len() < TIMESTAMP_CHECK_WINDOW {
+            return Err(HeaderError::NotEnoughTimestamps {
+                expected: TIMESTAMP_CHECK_WINDOW,
+                found: previous.len(),
+            });
+        }
+        let mut window = previous[previous.len() - TIMESTAMP_CHECK_WINDOW..].to_vec();
+        window.sort_unstable();
+        // Average of the two middle timestamps without overflowing, as `epee::misc_utils::median`
+        let (a, b) = (
+            window[TIMESTAMP_CHECK_WINDOW / 2 - 1],
+            window[TIMESTAMP_CHECK_WINDOW / 2],
+        );
+        let median = a / 2 + b / 2 + (a % 2 + b % 2) / 2;
+        if timestamp < median {
+            return Err(HeaderError::TimestampTooOld { timestamp, median });
+        }
+        Ok(())
+    }
+
+    fn check_miner_tx(&self, height: u64, hf_version: u8) -> Result<(), HeaderError> {
+        let prefix = &self.miner_tx.prefix;
+        let gen_height = match prefix.inputs.as_slice() {
+            [TxIn::Gen { height }] => **height,
+            _ => return Err(HeaderError::InvalidMinerInput),
+        };
+        if gen_height != height {
+            return Err(HeaderError::InvalidMinerHeight {
+                expected: height,
+                found: gen_height,
+            });
+        }
+        let unlock_time = height + MINED_MONEY_UNLOCK_WINDOW;
+        if *prefix.unlock_time != unlock_time {
+            return Err(HeaderError::InvalidMinerUnlockTime {
+                expected: unlock_time,
+                found: *prefix.unlock_time,
+            });
+        }
+        if hf_version >= HF_VERSION_REJECT_SIGS_IN_COINBASE {
+            let is_null = self
+                .miner_tx
+                .rct_signatures
+                .sig
+                .as_ref()
+                .is_none_or(|sig| sig.rct_type == RctType::Null);
+            if !is_null {
+                return Err(HeaderError::MinerRctSignatures);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_tx_hashes(&self) -> Result<(), HeaderError> {
+        if self.tx_hashes.len() > MAX_TX_PER_BLOCK {
+            return Err(HeaderError::TooManyTransactions(self.tx_hashes.len()));
+        }
+        let miner_tx_hash = self.miner_tx.hash();
+        let mut seen = HashSet::with_capacity(self.tx_hashes.len());
+        for tx_hash in &self.tx_hashes {
+            if *tx_hash == miner_tx_hash {
+                return Err(HeaderError::MinerTxIncluded);
+            }
+            if !seen.insert(tx_hash) {
+                return Err(HeaderError::DuplicateTransaction(*tx_hash));
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the block hashing blob, i.e. the serialized header followed by the transactions
+    /// Merkle root and the number of transactions, used for proof-of-work and block id
+    ///
+    /// **Same as** [`get_block_hashing_blob`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/cryptonote_format_utils.cpp#L1251-L1258)
+    pub fn hashing_blob(&self) -> Vec<u8> {
+        let mut blob = serialize(&self.header);
+        blob.extend_from_slice(self.tree_root().as_bytes());
+        blob.extend(serialize(&VarInt(1 + self.tx_hashes.len() as u64)));
+        blob
+    }
+
+    /// Return the height of the miner transaction input, if it is a single coinbase input
+    fn miner_tx_height(&self) -> Option<u64> {
+        match self.miner_tx.prefix.inputs.as_slice() {
+            [TxIn::Gen { height }] => Some(height.0),
+            _ => None,
+        }
+    }
+}
+
+// To get the block id
+impl hash::Hashable for Block {
+    /// **Same as** [`calculate_block_hash`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/cryptonote_format_utils.cpp)
+    fn hash(&self) -> hash::Hash {
+        // Block 202612 on mainnet has an id that does not match its content because of a bug in
+        // the original tree hash implementation, monerod recognizes it by the hash of the full
+        // serialized block and overrides its id
+        if self.miner_tx_height() == Some(202_612)
+            && hash::Hash::hash(&serialize(self)) == hash::Hash(BLOCK_202612_BLOB_HASH)
+        {
+            return hash::Hash(BLOCK_202612_ID);
+        }
+        let blob = self.hashing_blob();
+        let mut data = serialize(&VarInt(blob.len() as u64));
+        data.extend(blob);
+        hash::Hash::hash(&data)
+    }
+}
+
+/// Hash of the serialized block 202612
+const BLOCK_202612_BLOB_HASH: [u8; 32] = [
+    0x3a, 0x8a, 0x2b, 0x3a, 0x29, 0xb5, 0x0f, 0xc8, 0x6f, 0xf7, 0x3d, 0xd0, 0x87, 0xea, 0x43, 0xc6,
+    0xf0, 0xd6, 0xb8, 0xf9, 0x36, 0xc8, 0x49, 0x19, 0x4d, 0x5c, 0x84, 0xc7, 0x37, 0x90, 0x39, 0x66,
+];
+
+/// Id of the block 202612 in the blockchain
+const BLOCK_202612_ID: [u8; 32] = [
+    0xbb, 0xd6, 0x04, 0xd2, 0xba, 0x11, 0xba, 0x27, 0x93, 0x5e, 0x00, 0x6e, 0xd3, 0x9c, 0x9b, 0xfd,
+    0xd9, 0x9b, 0x76, 0xbf, 0x4a, 0x50, 0x65, 0x4b, 0xc1, 0xe1, 0xe6, 0x12, 0x17, 0x96, 0x26, 0x98,
+];
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -111,5 +414,190 @@ mod test {
         assert_eq!(bytes, bytes2);
         let hex2 = hex::encode(bytes2);
         assert_eq!(hex, hex2);
+        assert_eq!(hex_blockhash_blob, hex::encode(block.hashing_blob()));
+    }
+
+    #[test]
+    fn test_check_header() {
+        // stagenet block 558175, major version 12
+        let hex = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000";
+        let block = deserialize::<Block>(&hex::decode(hex).unwrap()).unwrap();
+        let timestamp = *block.header.timestamp;
+        let previous_timestamps: Vec<u64> = (1..=60).map(|i| timestamp - 120 * i).collect();
+        let context = HeaderContext {
+            network: Network::Stagenet,
+            height: 558_175,
+            previous_timestamps: &previous_timestamps,
+            current_time: timestamp,
+        };
+        assert_eq!(Ok(()), block.check_header(&context));
+
+        let context = HeaderContext {
+            current_time: timestamp - BLOCK_FUTURE_TIME_LIMIT - 1,
+            ..context
+        };
+        assert_eq!(
+            Err(HeaderError::TimestampTooFarInFuture {
+                timestamp,
+                limit: timestamp - 1
+            }),
+            block.check_header(&context)
+        );
+
+        let later_timestamps: Vec<u64> = (0..60).map(|i| timestamp + i).collect();
+        let context = HeaderContext {
+            previous_timestamps: &later_timestamps,
+            current_time: timestamp,
+            ..context
+        };
+        assert_eq!(
+            Err(HeaderError::TimestampTooOld {
+                timestamp,
+                median: timestamp + 29
+            }),
+            block.check_header(&context)
+        );
+
+        // Untrusted timestamps close to the maximum do not overflow the median
+        let max_timestamps: Vec<u64> = (0..60).map(|i| u64::MAX - i).collect();
+        let context = HeaderContext {
+            previous_timestamps: &max_timestamps,
+            ..context
+        };
+        assert_eq!(
+            Err(HeaderError::TimestampTooOld {
+                timestamp,
+                median: u64::MAX - 30
+            }),
+            block.check_header(&context)
+        );
+
+        let context = HeaderContext {
+            previous_timestamps: &previous_timestamps[..10],
+            ..context
+        };
+        assert_eq!(
+            Err(HeaderError::NotEnoughTimestamps {
+                expected: 60,
+                found: 10
+            }),
+            block.check_header(&context)
+        );
+
+        let context = HeaderContext {
+            network: Network::Mainnet,
+            previous_timestamps: &previous_timestamps,
+            ..context
+        };
+        assert_eq!(
+            Err(HeaderError::InvalidMajorVersion {
+                expected: 1,
+                found: 12
+            }),
+            block.check_header(&context)
+        );
+
+        let context = HeaderContext {
+            network: Network::Stagenet,
+            height: 558_176,
+            ..context
+        };
+        assert_eq!(
+            Err(HeaderError::InvalidMinerHeight {
+                expected: 558_176,
+                found: 558_175
+            }),
+            block.check_header(&context)
+        );
+    }
+
+    #[test]
+    fn test_check_header_votes_and_tx_hashes() {
+        let hex = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000";
+        let mut block = deserialize::<Block>(&hex::decode(hex).unwrap()).unwrap();
+        let previous_timestamps = vec![0; 60];
+        let context = HeaderContext {
+            network: Network::Stagenet,
+            height: 558_175,
+            previous_timestamps: &previous_timestamps,
+            current_time: *block.header.timestamp,
+        };
+
+        block.header.minor_version = VarInt(11);
+        assert_eq!(
+            Err(HeaderError::InvalidVote {
+                hard_fork: 12,
+                vote: 11
+            }),
+            block.check_header(&context)
+        );
+        block.header.minor_version = VarInt(13);
+        assert_eq!(Ok(()), block.check_header(&context));
+
+        let tx_hash = hash::Hash::hash(b"tx");
+        block.tx_hashes = vec![tx_hash, tx_hash];
+        assert_eq!(
+            Err(HeaderError::DuplicateTransaction(tx_hash)),
+            block.check_header(&context)
+        );
+        block.tx_hashes = vec![tx_hash, block.miner_tx.hash()];
+        assert_eq!(
+            Err(HeaderError::MinerTxIncluded),
+            block.check_header(&context)
+        );
+        block.tx_hashes = vec![];
+
+        block.miner_tx.prefix.unlock_time = VarInt(558_175);
+        assert_eq!(
+            Err(HeaderError::InvalidMinerUnlockTime {
+                expected: 558_235,
+                found: 558_175
+            }),
+            block.check_header(&context)
+        );
+        block.miner_tx.prefix.inputs = vec![];
+        assert_eq!(
+            Err(HeaderError::InvalidMinerInput),
+            block.check_header(&context)
+        );
+    }
+
+    #[test]
+    fn test_genesis_block_id() {
+        let genesis_tx = hex::decode("013c01ff0001ffffffffffff03029b2e4c0281c0b02e7c53291a94d1d0cbff8883f8024f5142ee494ffbbd08807121017767aafcde9be00dcfd098715ebcf7f410daebc582fda69d24a28e9d0bc890d1").unwrap();
+        let block = Block {
+            header: BlockHeader {
+                major_version: VarInt(1),
+                minor_version: VarInt(0),
+                timestamp: VarInt(0),
+                prev_id: hash::Hash::null_hash(),
+                nonce: 10000,
+            },
+            miner_tx: deserialize(&genesis_tx).unwrap(),
+            tx_hashes: vec![],
+        };
+        assert_eq!(
+            "418015bb9ae982a1975da7d79277c2705727a56894ba0fb246adaabb1f4632e3",
+            format!("{:02x}", block.hash())
+        );
+    }
+
+    #[test]
+    fn test_block_202612_exception() {
+        // Only the serialized block 202612 is overridden, any other block at that height keeps
+        // the id computed from its hashing blob
+        let mut block = Block::default();
+        block.miner_tx.prefix.inputs = vec![TxIn::Gen {
+            height: VarInt(202_612),
+        }];
+        assert_eq!(Some(202_612), block.miner_tx_height());
+        let blob = block.hashing_blob();
+        let mut data = serialize(&VarInt(blob.len() as u64));
+        data.extend(blob);
+        assert_eq!(hash::Hash::hash(&data), block.hash());
+        assert_ne!(hash::Hash(BLOCK_202612_ID), block.hash());
+
+        block.miner_tx.prefix.inputs = vec![];
+        assert_eq!(None, block.miner_tx_height());
     }
 }
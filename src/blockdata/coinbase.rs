@@ -0,0 +1,408 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Coinbase transaction
+//!
+//! Support for building miner (coinbase) transactions and block templates, as done by monerod
+//! when serving `get_block_template`.
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use monero::blockdata::block::BlockHeader;
+//! use monero::blockdata::coinbase::{BlockTemplate, MinerTxBuilder};
+//! use monero::{Address, PrivateKey};
+//!
+//! let address = Address::from_str("4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra").unwrap();
+//! let tx_key = PrivateKey::from_str("77916d0cd56ed1920aef6ca56d8a41bac915b68e4c46a589e0956e27a7b77404").unwrap();
+//!
+//! let miner_tx = MinerTxBuilder::new(2_200_000, 1_000_000_000_000, address, tx_key)
+//!     .reserve_size(8)
+//!     .build()
+//!     .unwrap();
+//! let template = BlockTemplate::new(BlockHeader::default(), miner_tx, vec![]);
+//!
+//! // Each worker writes its own extra nonce in the reserved space
+//! let hashing_blob = template.hashing_blob(&[0, 0, 0, 0, 0, 0, 0, 1]).unwrap();
+//! ```
+//!
+
+use std::ops::Range;
+
+use crate::blockdata::block::{Block, BlockHeader};
+use crate::blockdata::merge_mining::MergeMiningTag;
+use crate::blockdata::transaction::{
+    ExtraField, SubField, Transaction, TransactionPrefix, TxIn, TxOut, TxOutTarget,
+};
+use crate::consensus::encode::{serialize, VarInt};
+use crate::cryptonote::hash::{self, Hashable};
+use crate::cryptonote::onetime_key::KeyGenerator;
+use crate::util::address::{Address, AddressType};
+use crate::util::key::{PrivateKey, PublicKey};
+use crate::util::ringct::{RctSig, RctSigBase, RctType};
+
+use thiserror::Error;
+
+pub use crate::blockdata::transaction::TX_EXTRA_NONCE_MAX_COUNT;
+
+/// Number of blocks before a coinbase output can be spent
+///
+/// **Same as** `CRYPTONOTE_MINED_MONEY_UNLOCK_WINDOW` in `cryptonote_config.h`
+pub const MINED_MONEY_UNLOCK_WINDOW: u64 = 60;
+
+/// Possible errors when building a coinbase transaction
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// The extra nonce reserve does not fit in a nonce sub-field
+    #[error("extra nonce reserve of {0} bytes exceeds the nonce sub-field limit")]
+    ReserveTooLarge(usize),
+    /// Coinbase outputs cannot be sent to a subaddress
+    #[error("coinbase outputs cannot be sent to a subaddress")]
+    SubaddressNotSupported,
+    /// The extra nonce does not match the reserved size
+    #[error("extra nonce of {found} bytes does not match the {expected} reserved bytes")]
+    InvalidExtraNonceSize {
+        /// Number of reserved bytes
+        expected: usize,
+        /// Number of bytes given
+        found: usize,
+    },
+}
+
+/// Builder for a version 2 miner transaction paying the block reward to an address
+#[derive(Debug, Clone)]
+pub struct MinerTxBuilder {
+    height: u64,
+    reward: u64,
+    address: Address,
+    tx_key: PrivateKey,
+    reserve_size: usize,
+    merge_mining_tag: Option<MergeMiningTag>,
+}
+
+impl MinerTxBuilder {
+    /// Create a builder for the block at `height` paying `reward` to `address`, the `tx_key` is
+    /// the random transaction private key `r` used to derive the one-time output key
+    pub fn new(height: u64, reward: u64, address: Address, tx_key: PrivateKey) -> Self {
+        MinerTxBuilder {
+            height,
+            reward,
+            address,
+            tx_key,
+            reserve_size: 0,
+            merge_mining_tag: None,
+        }
+    }
+
+    /// Reserve `size` bytes in an extra nonce sub-field, the reserved bytes are zeroed and can be
+    /// overwritten by miners, no nonce sub-field is added if the size is 0
+    pub fn reserve_size(mut self, size: usize) -> Self {
+        self.reserve_size = size;
+        self
+    }
+
+    /// Commit to auxiliary chains with a merge mining tag in extra, the tag is placed before the
+    /// reserved extra nonce
+    pub fn merge_mining_tag(mut self, tag: MergeMiningTag) -> Self {
+        self.merge_mining_tag = Some(tag);
+        self
+    }
+
+    /// Build the miner transaction
+    pub fn build(&self) -> Result<MinerTx, Error> {
+        if self.reserve_size > TX_EXTRA_NONCE_MAX_COUNT {
+            return Err(Error::ReserveTooLarge(self.reserve_size));
+        }
+        if self.address.addr_type == AddressType::SubAddress {
+            return Err(Error::SubaddressNotSupported);
+        }
+
+        // Computes P = Hn(r*8*V || 0)*G + S
+        let generator = KeyGenerator::from_random(
+            self.address.public_view,
+            self.address.public_spend,
+            self.tx_key,
+        );
+        let output = TxOut {
+            amount: VarInt(self.reward),
+            target: TxOutTarget::ToKey {
+                key: generator.one_time_key(0),
+            },
+        };
+
+        let mut extra = vec![SubField::TxPublicKey(PublicKey::from_private_key(
+            &self.tx_key,
+        ))];
+        if let Some(tag) = self.merge_mining_tag {
+            extra.push(tag.into());
+        }
+        if self.reserve_size > 0 {
+            extra.push(SubField::Nonce(vec![0u8; self.reserve_size]));
+        }
+
+        let tx = Transaction {
+            prefix: TransactionPrefix {
+                version: VarInt(2),
+                unlock_time: VarInt(self.height + MINED_MONEY_UNLOCK_WINDOW),
+                inputs: vec![TxIn::Gen {
+                    height: VarInt(self.height),
+                }],
+                outputs: vec![output],
+                extra: ExtraField(extra),
+            },
+            signatures: vec![],
+            rct_signatures: RctSig {
+                sig: Some(RctSigBase {
+                    rct_type: RctType::Null,
+                    txn_fee: VarInt(0),
+                    pseudo_outs: vec![],
+                    ecdh_info: vec![],
+                    out_pk: vec![],
+                }),
+                p: None,
+            },
+        };
+
+        // The nonce is the last sub-field of extra, which is the last field of the prefix
+        let reserved_offset = if self.reserve_size > 0 {
+            serialize(&tx.prefix).len() - self.reserve_size
+        } else {
+            0
+        };
+
+        Ok(MinerTx {
+            tx,
+            tx_key: self.tx_key,
+            reserved_offset,
+            reserve_size: self.reserve_size,
+        })
+    }
+}
+
+/// A miner transaction with the position of its reserved extra nonce
+#[derive(Debug, Clone)]
+pub struct MinerTx {
+    /// The miner transaction
+    pub tx: Transaction,
+    /// The transaction private key `r`
+    pub tx_key: PrivateKey,
+    /// Offset of the reserved bytes in the serialized transaction, 0 if nothing is reserved
+    pub reserved_offset: usize,
+    /// Number of reserved bytes
+    pub reserve_size: usize,
+}
+
+/// A block template ready to be distributed to miners
+///
+/// The miner transaction is committed in the hashing blob through the transactions Merkle root,
+/// changing the extra nonce requires recomputing the miner transaction hash and the root. The
+/// template keeps the serialized miner transaction prefix to avoid re-serializing the whole block
+/// for each extra nonce.
+#[derive(Debug, Clone)]
+pub struct BlockTemplate {
+    /// The block with a zeroed extra nonce
+    pub block: Block,
+    /// Offset of the reserved bytes in the serialized block, same as monerod `reserved_offset`
+    pub reserved_offset: usize,
+    /// Number of reserved bytes
+    pub reserve_size: usize,
+    header: Vec<u8>,
+    prefix: Vec<u8>,
+    nonce_range: Range<usize>,
+    rct_base_hash: hash::Hash,
+}
+
+impl BlockTemplate {
+    /// Create a block template from its header, the miner transaction and the hashes of the
+    /// included transactions
+    pub fn new(header: BlockHeader, miner_tx: MinerTx, tx_hashes: Vec<hash::Hash>) -> Self {
+        let header_bytes = serialize(&header);
+        let prefix = serialize(&miner_tx.tx.prefix);
+        let rct_base_hash = match &miner_tx.tx.rct_signatures.sig {
+            Some(sig) => sig.hash(),
+            None => hash::Hash::null_hash(),
+        };
+        let reserved_offset = if miner_tx.reserve_size > 0 {
+            header_bytes.len() + miner_tx.reserved_offset
+        } else {
+            0
+        };
+        BlockTemplate {
+            block: Block {
+                header,
+                miner_tx: miner_tx.tx,
+                tx_hashes,
+            },
+            reserved_offset,
+            reserve_size: miner_tx.reserve_size,
+            header: header_bytes,
+            prefix,
+            nonce_range: miner_tx.reserved_offset..miner_tx.reserved_offset + miner_tx.reserve_size,
+            rct_base_hash,
+        }
+    }
+
+    /// Return the serialized block template, same as monerod `blocktemplate_blob`
+    pub fn blob(&self) -> Vec<u8> {
+        serialize(&self.block)
+    }
+
+    /// Return the hashing blob of the block with `extra_nonce` written in the reserved bytes
+    pub fn hashing_blob(&self, extra_nonce: &[u8]) -> Result<Vec<u8>, Error> {
+        if extra_nonce.len() != self.reserve_size {
+            return Err(Error::InvalidExtraNonceSize {
+                expected: self.reserve_size,
+                found: extra_nonce.len(),
+            });
+        }
+        let mut prefix = self.prefix.clone();
+        prefix[self.nonce_range.clone()].copy_from_slice(extra_nonce);
+
+        // Version 2 transaction hash: H(H(prefix) || H(rct base) || H(rct prunable)), the
+        // prunable part of a coinbase is empty and hashed as a null hash
+        let mut tx_hashes = Vec::with_capacity(1 + self.block.tx_hashes.len());
+        tx_hashes.push(hash::Hash::hash(
+            &[
+                hash::Hash::hash(&prefix).to_bytes(),
+                self.rct_base_hash.to_bytes(),
+                hash::Hash::null_hash().to_bytes(),
+            ]
+            .concat(),
+        ));
+        tx_hashes.extend_from_slice(&self.block.tx_hashes);
+        let root =
+            hash::tree_hash(&tx_hashes).expect("The miner transaction is always present. QED");
+
+        let mut blob = self.header.clone();
+        blob.extend_from_slice(root.as_bytes());
+        blob.extend(serialize(&VarInt(tx_hashes.len() as u64)));
+        Ok(blob)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockTemplate, Error, MinerTxBuilder, MINED_MONEY_UNLOCK_WINDOW};
+    use crate::blockdata::block::BlockHeader;
+    use crate::blockdata::transaction::{SubField, TxIn};
+    use crate::cryptonote::hash::Hash;
+    use crate::network::Network;
+    use crate::test_utils::keys;
+    use crate::util::address::Address;
+    use crate::util::key::{PublicKey, ViewPair};
+    use crate::util::ringct::RctType;
+
+    #[test]
+    fn build_miner_tx() {
+        let viewpair = ViewPair::from(&keys());
+        let address = Address::from_viewpair(Network::Mainnet, &viewpair);
+        let tx_key = Hash::hash(b"tx key").as_scalar();
+
+        let miner_tx = MinerTxBuilder::new(2_000_000, 600_000_000_000, address, tx_key)
+            .reserve_size(16)
+            .build()
+            .unwrap();
+        let tx = &miner_tx.tx;
+
+        assert_eq!(2, *tx.prefix.version);
+        assert_eq!(
+            2_000_000 + MINED_MONEY_UNLOCK_WINDOW,
+            *tx.prefix.unlock_time
+        );
+        match tx.prefix.inputs[..] {
+            [TxIn::Gen { ref height }] => assert_eq!(2_000_000, **height),
+            _ => panic!("Miner tx must have a single gen input"),
+        }
+        assert_eq!(
+            RctType::Null,
+            tx.rct_signatures.sig.as_ref().unwrap().rct_type
+        );
+        assert_eq!(
+            Some(PublicKey::from_private_key(&tx_key)),
+            tx.prefix.tx_pubkey()
+        );
+        assert_eq!(
+            Some(&SubField::Nonce(vec![0; 16])),
+            tx.prefix.extra.0.last()
+        );
+
+        // The output is owned by the address
+        let owned = tx.prefix.check_outputs(&viewpair, 0..1, 0..1).unwrap();
+        assert_eq!(1, owned.len());
+        assert_eq!(600_000_000_000, *owned[0].out.amount);
+    }
+
+    #[test]
+    fn reserved_offset_in_template() {
+        let viewpair = ViewPair::from(&keys());
+        let address = Address::from_viewpair(Network::Mainnet, &viewpair);
+        let tx_key = Hash::hash(b"tx key").as_scalar();
+
+        let miner_tx = MinerTxBuilder::new(2_000_000, 600_000_000_000, address, tx_key)
+            .reserve_size(8)
+            .build()
+            .unwrap();
+        let tx_hashes = vec![Hash::hash(b"tx1"), Hash::hash(b"tx2")];
+        let template = BlockTemplate::new(BlockHeader::default(), miner_tx, tx_hashes);
+
+        let blob = template.blob();
+        assert_eq!(
+            &[0u8; 8],
+            &blob[template.reserved_offset..template.reserved_offset + 8]
+        );
+        assert_eq!(
+            template.block.hashing_blob(),
+            template.hashing_blob(&[0u8; 8]).unwrap()
+        );
+
+        // Patching the reserved bytes gives the same hashing blob as the modified block
+        let extra_nonce = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut block = template.block.clone();
+        block.miner_tx.prefix.extra.0[1] = SubField::Nonce(extra_nonce.to_vec());
+        assert_eq!(
+            block.hashing_blob(),
+            template.hashing_blob(&extra_nonce).unwrap()
+        );
+        assert_eq!(
+            Err(Error::InvalidExtraNonceSize {
+                expected: 8,
+                found: 4
+            }),
+            template.hashing_blob(&[0u8; 4])
+        );
+    }
+
+    #[test]
+    fn reject_invalid_builder() {
+        let viewpair = ViewPair::from(&keys());
+        let address = Address::from_viewpair(Network::Mainnet, &viewpair);
+        let tx_key = Hash::hash(b"tx key").as_scalar();
+        assert_eq!(
+            Err(Error::ReserveTooLarge(256)),
+            MinerTxBuilder::new(1, 1, address, tx_key)
+                .reserve_size(256)
+                .build()
+                .map(|_| ())
+        );
+
+        let subaddress =
+            Address::subaddress(Network::Mainnet, address.public_spend, address.public_view);
+        assert_eq!(
+            Err(Error::SubaddressNotSupported),
+            MinerTxBuilder::new(1, 1, subaddress, tx_key)
+                .build()
+                .map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,240 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Merge mining
+//!
+//! A Monero block commits to the blocks of auxiliary chains with a merge mining tag in the extra
+//! field of its miner transaction. The tag holds the Merkle root, computed with the CryptoNote
+//! tree hash, of the auxiliary hashes and the depth of that tree.
+//!
+//! ```rust
+//! use monero::blockdata::merge_mining::{aux_branch, verify_aux_hash, MergeMiningTag};
+//! use monero::blockdata::Block;
+//! use monero::cryptonote::hash::Hash;
+//!
+//! let aux_hashes = vec![Hash::hash(b"aux chain 1"), Hash::hash(b"aux chain 2")];
+//! let tag = MergeMiningTag::new(&aux_hashes).unwrap();
+//!
+//! let mut block = Block::default();
+//! block.miner_tx.prefix.extra.0.push(tag.into());
+//!
+//! let branch = aux_branch(&aux_hashes, 1).unwrap();
+//! assert!(verify_aux_hash(&block, &aux_hashes[1], &branch).is_ok());
+//! ```
+//!
+
+use crate::blockdata::block::Block;
+use crate::blockdata::transaction::{ExtraField, SubField};
+use crate::consensus::encode::VarInt;
+use crate::cryptonote::hash::{self, Hash, MerkleBranch, MAX_BRANCH_DEPTH};
+
+use thiserror::Error;
+
+/// Possible errors when verifying a merge mining commitment
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// The miner transaction has no merge mining tag
+    #[error("no merge mining tag in the miner transaction")]
+    NoMergeMiningTag,
+    /// The branch is deeper than the tree of the tag
+    #[error("branch of depth {found} exceeds the merge mining tree depth {depth}")]
+    InvalidBranchDepth {
+        /// Depth of the tree in the tag
+        depth: u64,
+        /// Length of the branch
+        found: usize,
+    },
+    /// The branch is longer than the bits of its path
+    #[error("branch of depth {0} exceeds the maximum depth {}", MAX_BRANCH_DEPTH)]
+    BranchTooLong(usize),
+    /// The auxiliary hash and branch do not lead to the Merkle root of the tag
+    #[error("auxiliary hash is not committed in the merge mining tag")]
+    NotCommitted,
+}
+
+/// Merge mining tag committing to the hashes of auxiliary chains
+///
+/// **Same as** `tx_extra_merge_mining_tag` in [`tx_extra.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/tx_extra.h)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeMiningTag {
+    /// Depth of the auxiliary Merkle tree
+    pub depth: u64,
+    /// Merkle root of the auxiliary hashes
+    pub merkle_root: Hash,
+}
+
+impl MergeMiningTag {
+    /// Create the tag of the auxiliary hashes, return `None` if there is no hash
+    pub fn new(aux_hashes: &[Hash]) -> Option<MergeMiningTag> {
+        Some(MergeMiningTag {
+            depth: tree_depth(aux_hashes.len()),
+            merkle_root: hash::tree_hash(aux_hashes)?,
+        })
+    }
+
+    /// Return the first merge mining tag, if any, present in the extra field
+    pub fn from_extra(extra: &ExtraField) -> Option<MergeMiningTag> {
+        extra.0.iter().find_map(|x| match x {
+            SubField::MergeMining(depth, merkle_root) => Some(MergeMiningTag {
+                depth: **depth,
+                merkle_root: *merkle_root,
+            }),
+            _ => None,
+        })
+    }
+}
+
+impl From<MergeMiningTag> for SubField {
+    fn from(tag: MergeMiningTag) -> SubField {
+        SubField::MergeMining(VarInt(tag.depth), tag.merkle_root)
+    }
+}
+
+/// Return the depth of a tree built with the tree hash over `count` leaves, i.e. the length of
+/// its longest branch
+fn tree_depth(count: usize) -> u64 {
+    match count {
+        0 | 1 => 0,
+        count => (usize::BITS - (count - 1).leading_zeros()) as u64,
+    }
+}
+
+/// Compute the Merkle branch of the auxiliary hash at `index`, return `None` if the index is out
+/// of bounds
+pub fn aux_branch(aux_hashes: &[Hash], index: usize) -> Option<MerkleBranch> {
+    hash::tree_branch(aux_hashes, index)
+}
+
+/// Verify that the auxiliary hash is committed, through its branch, in the merge mining tag of
+/// the block miner transaction
+pub fn verify_aux_hash(block: &Block, aux_hash: &Hash, branch: &MerkleBranch) -> Result<(), Error> {
+    let tag =
+        MergeMiningTag::from_extra(&block.miner_tx.prefix.extra).ok_or(Error::NoMergeMiningTag)?;
+    if branch.hashes.len() > MAX_BRANCH_DEPTH {
+        return Err(Error::BranchTooLong(branch.hashes.len()));
+    }
+    if branch.hashes.len() as u64 > tag.depth {
+        return Err(Error::InvalidBranchDepth {
+            depth: tag.depth,
+            found: branch.hashes.len(),
+        });
+    }
+    if branch.root(aux_hash) != Some(tag.merkle_root) {
+        return Err(Error::NotCommitted);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{aux_branch, tree_depth, verify_aux_hash, Error, MergeMiningTag};
+    use crate::blockdata::block::{Block, BlockHeader};
+    use crate::blockdata::coinbase::{BlockTemplate, MinerTxBuilder};
+    use crate::blockdata::transaction::SubField;
+    use crate::consensus::encode::{deserialize, serialize, VarInt};
+    use crate::cryptonote::hash::{Hash, Hashable, MerkleBranch};
+    use crate::util::address::Address;
+    use crate::util::key::PrivateKey;
+
+    fn merge_mined_block(aux_hashes: &[Hash]) -> Block {
+        let address = Address::from_str("4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra").unwrap();
+        let tx_key = PrivateKey::from_str(
+            "bcfdda53205318e1c14fa0ddca1a45df363bb427972981d0249d0f4652a7df07",
+        )
+        .unwrap();
+        let miner_tx = MinerTxBuilder::new(2_000_000, 600_000_000_000, address, tx_key)
+            .merge_mining_tag(MergeMiningTag::new(aux_hashes).unwrap())
+            .reserve_size(8)
+            .build()
+            .unwrap();
+        let tx_hashes = vec![Hash::hash(b"tx 1"), Hash::hash(b"tx 2")];
+        BlockTemplate::new(BlockHeader::default(), miner_tx, tx_hashes).block
+    }
+
+    #[test]
+    fn tag_depth() {
+        assert_eq!(0, tree_depth(1));
+        assert_eq!(1, tree_depth(2));
+        assert_eq!(2, tree_depth(3));
+        assert_eq!(2, tree_depth(4));
+        assert_eq!(3, tree_depth(5));
+        assert_eq!(None, MergeMiningTag::new(&[]));
+
+        let hash = Hash::hash(b"aux chain");
+        let tag = MergeMiningTag::new(&[hash]).unwrap();
+        assert_eq!(0, tag.depth);
+        assert_eq!(hash, tag.merkle_root);
+        assert_eq!(
+            [&[0x03, 0x21, 0x00][..], hash.as_bytes()].concat(),
+            serialize(&SubField::from(tag))
+        );
+    }
+
+    #[test]
+    fn verify_merge_mined_block() {
+        let aux_hashes: Vec<Hash> = (0u8..5).map(|i| Hash::hash(&[i])).collect();
+        let block = merge_mined_block(&aux_hashes);
+        // The tag goes through the block encoding
+        let block: Block = deserialize(&serialize(&block)).unwrap();
+        let tag = MergeMiningTag::from_extra(&block.miner_tx.prefix.extra).unwrap();
+        assert_eq!(3, tag.depth);
+        assert_eq!(Some(tag), MergeMiningTag::new(&aux_hashes));
+
+        for (index, aux_hash) in aux_hashes.iter().enumerate() {
+            let branch = aux_branch(&aux_hashes, index).unwrap();
+            assert_eq!(Ok(()), verify_aux_hash(&block, aux_hash, &branch));
+            assert_eq!(
+                Err(Error::NotCommitted),
+                verify_aux_hash(&block, &Hash::hash(b"other"), &branch)
+            );
+        }
+        assert_eq!(None, aux_branch(&aux_hashes, 5));
+
+        let mut branch = aux_branch(&aux_hashes, 0).unwrap();
+        branch.hashes.extend_from_slice(&aux_hashes[..2]);
+        assert_eq!(
+            Err(Error::InvalidBranchDepth { depth: 3, found: 4 }),
+            verify_aux_hash(&block, &aux_hashes[0], &branch)
+        );
+
+        // The miner transaction, holding the tag, is committed in the block id
+        let miner_tx_branch = block.miner_tx_branch();
+        assert_eq!(
+            Some(block.tree_root()),
+            miner_tx_branch.root(&block.miner_tx.hash())
+        );
+
+        // A tag deeper than the path bits does not allow a longer branch
+        let mut block = block;
+        block.miner_tx.prefix.extra.0 =
+            vec![SubField::MergeMining(VarInt(64), Hash::hash(b"deep tree"))];
+        let branch = MerkleBranch {
+            hashes: vec![aux_hashes[1]; 40],
+            path: 0,
+        };
+        assert_eq!(
+            Err(Error::BranchTooLong(40)),
+            verify_aux_hash(&block, &aux_hashes[0], &branch)
+        );
+
+        let block = Block::default();
+        assert_eq!(
+            Err(Error::NoMergeMiningTag),
+            verify_aux_hash(&block, &aux_hashes[0], &aux_branch(&aux_hashes, 0).unwrap())
+        );
+    }
+}
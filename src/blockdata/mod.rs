@@ -21,6 +21,8 @@
 //!
 
 pub mod block;
+pub mod coinbase;
+pub mod merge_mining;
 #[cfg(test)]
 mod tests;
 pub mod transaction;
@@ -15,8 +15,8 @@
 
 #![allow(unused_imports)]
 
-use crate::blockdata::transaction::{ExtraField, Transaction};
-use crate::consensus::encode::{deserialize, deserialize_partial, serialize};
+use crate::blockdata::transaction::{ExtraField, Transaction, TransactionDecoder};
+use crate::consensus::encode::{deserialize, deserialize_partial, serialize, DecodeLimits};
 use crate::cryptonote::hash::Hashable;
 
 #[test]
@@ -121,3 +121,65 @@ fn deserialize_transaction() {
         format!("{:02x}", tx.hash())
     );
 }
+
+#[test]
+fn decode_transaction_stream() {
+    let blobs = [
+        hex::decode("01f18d0601ffb58d0605efefead70202eb72f82bd8bdda51e0bdc25f04e99ffb90c6214e11b455abca7b116c7857738880e497d01202e87c65a22b78f4b7686ef3a30113674659a4fe769a7ded73d60e6f7c556a19858090dfc04a022ee52dca8845438995eb6d7af985ca07186cc34a7eb696937f78fc0fd9008e2280c0f9decfae0102cec392ffdcae05a370dc3c447465798d3688677f4a5937f1fef9661df99ac2fb80c0caf384a30202e2b6ce11475c2312d2de5c9f26fbd88b7fcac0dbbb7b31f49abe9bd631ed49e42b0104d46cf1a204ae727c14473d67ea95da3e97b250f3c63e0997198bfc812d7a81020800000000d8111b25").unwrap(),
+        hex::decode("0100020280d0acf30e018fe30113c516f7d0a0edd45ff450cdfd523d4083c4d2156b012b699cc495d93ac0d18b02808095e789c6040190137b9a219e36370321eddac567acbe26828493d9c41e8f66af51ee91fab8f6cfee058088aca3cf02021b2c657602daf8d06a2e6e06796f8394a48e90c99aa7a4f9da7937264f96577480d0b8e1981a02f4a3c5bee35ead69c91a5ae2733089c5f85420012e77e0e814a1ee23870982168080d194b57402280e7f49fbadbbe72ccca09652a0626b4ce4bf5fcadb13d683b596db589c50b580a0e5b9c291010269dcd7ee8ddcf9b1364d4f2a30fc12b5db417403851578fc44bc044e342bb50880c0caf384a3020288ed376d2529ac72bb1c499b88ff6d95c5c90fc038b1c0beb61d6959ff82591c2101492f5e1eccec89f7474d6ce920d6a8dfe4c82f29dca2412e86e35b01e2d95459b5aeb66856af919a5dbf591f2ee6c4abd2dbf145504fc0723f84051fe73f9202434d8c57b2396d8fb33a760b72f20968072aa6c72f9c3c2b646496055b127c0e7862fa25b84ae6edd3f9834060bb3a5b9129138244238a8247c76490e6070b0981503145a127ee79b24c100f21cde3c4bc48cbb064757ae32243ab909b4f3007").unwrap(),
+        hex::decode("01000202deaf160100d9c30184e3ca2c46f5b7ef4928f82c389cc06f307bc9ef65e8fc65b07df4112e0280d0dbc3f40201979309af29c1ad0690e081ec9e1fd59181f9b4ed948e2f54eb7615d14e091f22c02e4a03efa792fa0e021e276f10e488b1312d903e5a2223f0d052604158b387d269ece90189ec2fa65f8090dfc04a0235c3c90eb03851f9a7cf10c1c9346ea69948d2c9fd6eae43c56f85874a09b23c80f8cce2840202877a68cc2fb5abbb8cf9ac97dbf9a6db2f7d29d4ace251b36588b4f6f501c4bc2101e7c2cc36e6116f21d8f160ca5456a575f94d60fa11077ce815b3faf11783eee65e3a8edaab495310ef11fb6468ef4c9b4b89bd528c14e04946fc85a7accb7a0650b92851e19883126e70a3a43ce8d21933c34c42623b17610676dba78a07f90723fab15bf31ca33eb2450c3af7c1668b6bec9d596be2eba1993cfc12c24ac20925b12c476ce4c57ea614faf70b8b138433c9c0ea88845c25a63b38e41b130c0b").unwrap(),
+    ];
+    let stream = blobs.concat();
+
+    let mut decoder = TransactionDecoder::new(&stream[..]);
+    for blob in blobs.iter() {
+        let (tx, consumed) = decoder.next_transaction().unwrap().unwrap();
+        assert_eq!(blob.len(), consumed);
+        assert_eq!(deserialize::<Transaction>(blob).unwrap().hash(), tx.hash());
+    }
+    assert!(decoder.next_transaction().unwrap().is_none());
+    assert_eq!(stream.len() as u64, decoder.position());
+
+    // The last transaction is truncated
+    let decoder = TransactionDecoder::new(&stream[..stream.len() - 1]);
+    let results: Vec<_> = decoder.collect();
+    assert_eq!(blobs.len(), results.len());
+    assert!(results[blobs.len() - 1].is_err());
+
+    // The second transaction has two inputs
+    let limits = DecodeLimits {
+        max_elements: 1,
+        ..Default::default()
+    };
+    let mut decoder = TransactionDecoder::with_limits(&stream[blobs[0].len()..], limits);
+    assert!(decoder.next_transaction().is_err());
+
+    // The number of inputs is checked before allocating them
+    let hostile = hex::decode("0200ffffffff0f").unwrap();
+    assert!(TransactionDecoder::new(&hostile[..])
+        .next_transaction()
+        .is_err());
+
+    // A number of inputs just under the limit does not allocate them before decoding them
+    let hostile = hex::decode("0200ffffff07").unwrap();
+    assert!(TransactionDecoder::new(&hostile[..])
+        .next_transaction()
+        .is_err());
+
+    // The decoder stops after an error
+    let mut decoder = TransactionDecoder::new(&stream[blobs[0].len() + 1..]);
+    assert!(decoder.next().unwrap().is_err());
+    assert!(decoder.next().is_none());
+    assert!(decoder.next_transaction().unwrap().is_none());
+
+    // A reader failing on every read
+    struct Failing;
+
+    impl std::io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::Other.into())
+        }
+    }
+
+    assert_eq!(1, TransactionDecoder::new(Failing).count());
+}
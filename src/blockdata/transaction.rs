@@ -19,19 +19,27 @@
 //! view key.
 //!
 
-use crate::consensus::encode::{self, serialize, Decodable, Decoder, Encodable, Encoder, VarInt};
+use crate::consensus::encode::{
+    self, serialize, BoundedDecoder, Decodable, DecodeLimits, Decoder, Encodable, Encoder, VarInt,
+};
 use crate::cryptonote::hash;
 use crate::cryptonote::onetime_key::{KeyRecoverer, SubKeyChecker};
 use crate::cryptonote::subaddress::Index;
+use crate::util::address::PaymentId;
 use crate::util::key::{KeyPair, PrivateKey, PublicKey, ViewPair};
 use crate::util::ringct::{RctSig, RctSigBase, RctSigPrunable, RctType, Signature};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::traits::IsIdentity;
 use hex::encode as hex_encode;
+use std::collections::HashSet;
 use std::fmt::{Display, Error as FmtError, Formatter};
-use std::io::Cursor;
+use std::io::{self, Cursor, Read};
+use std::iter::FusedIterator;
 use std::ops::Range;
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 /// Transaction error
 #[derive(Debug)]
@@ -40,10 +48,123 @@ pub enum Error {
     NoTxPublicKey,
     /// Scripts input/output are not supported
     ScriptNotSupported,
+    /// The prunable part does not match the prunable hash of the pruned transaction
+    PrunableHashMismatch,
 }
 
+/// Possible errors when checking the semantics of a transaction
+#[derive(Error, Debug, PartialEq)]
+pub enum SemanticError {
+    /// The transaction version is not allowed at this hard fork
+    #[error("transaction version {version} is not allowed at hard fork {hf_version}")]
+    InvalidVersion {
+        /// Transaction version
+        version: u64,
+        /// Hard fork version
+        hf_version: u8,
+    },
+    /// The transaction has no input
+    #[error("transaction has no input")]
+    NoInputs,
+    /// An input is not a key input
+    #[error("input {0} is not a key input")]
+    InvalidInputType(usize),
+    /// An input of a RingCT transaction has a non-zero amount
+    #[error("input {0} has a non-zero amount")]
+    NonZeroInputAmount(usize),
+    /// An input has an empty ring
+    #[error("input {0} has no key offsets")]
+    EmptyKeyOffsets(usize),
+    /// An input references the same ring member more than once
+    #[error("input {0} has duplicate ring members")]
+    DuplicateRingMember(usize),
+    /// An input ring is smaller than the minimum allowed at this hard fork
+    #[error("input {input} has a ring size of {found}, minimum is {minimum}")]
+    RingSizeTooSmall {
+        /// Input index
+        input: usize,
+        /// Minimum ring size
+        minimum: usize,
+        /// Ring size of the input
+        found: usize,
+    },
+    /// An input ring size is not the one enforced at this hard fork
+    #[error("input {input} has a ring size of {found}, expected {expected}")]
+    InvalidRingSize {
+        /// Input index
+        input: usize,
+        /// Enforced ring size
+        expected: usize,
+        /// Ring size of the input
+        found: usize,
+    },
+    /// A key image is spent more than once in the transaction
+    #[error("input {0} has a duplicate key image")]
+    DuplicateKeyImage(usize),
+    /// Inputs are not sorted by descending key images
+    #[error("input {0} is not sorted by key image")]
+    UnsortedKeyImages(usize),
+    /// A key image is not a valid point in the prime order subgroup
+    #[error("input {0} has an invalid key image")]
+    InvalidKeyImage(usize),
+    /// The transaction has no output
+    #[error("transaction has no output")]
+    NoOutputs,
+    /// The transaction has less outputs than required
+    #[error("transaction has {found} outputs, minimum is {minimum}")]
+    TooFewOutputs {
+        /// Minimum number of outputs
+        minimum: usize,
+        /// Number of outputs
+        found: usize,
+    },
+    /// The transaction has more outputs than allowed
+    #[error("transaction has {found} outputs, maximum is {maximum}")]
+    TooManyOutputs {
+        /// Maximum number of outputs
+        maximum: usize,
+        /// Number of outputs
+        found: usize,
+    },
+    /// An output is not a key output
+    #[error("output {0} is not a key output")]
+    InvalidOutputType(usize),
+    /// An output of a RingCT transaction has a non-zero amount
+    #[error("output {0} has a non-zero amount")]
+    NonZeroOutputAmount(usize),
+    /// The RingCT signatures are missing
+    #[error("RingCT signatures are missing")]
+    MissingRctSignatures,
+    /// The prunable part of the RingCT signatures is missing
+    #[error("prunable RingCT signatures are missing")]
+    MissingPrunable,
+    /// The RingCT type is not allowed at this hard fork
+    #[error("RingCT type {rct_type} is not allowed at hard fork {hf_version}")]
+    RctTypeNotAllowed {
+        /// RingCT type
+        rct_type: RctType,
+        /// Hard fork version
+        hf_version: u8,
+    },
+    /// A signature field does not have the expected number of elements
+    #[error("{field} has {found} elements, expected {expected}")]
+    LengthMismatch {
+        /// Name of the field
+        field: &'static str,
+        /// Expected number of elements
+        expected: usize,
+        /// Number of elements
+        found: usize,
+    },
+}
+
+/// Maximum number of outputs covered by bulletproofs
+///
+/// **Same as** `BULLETPROOF_MAX_OUTPUTS` in `cryptonote_config.h`
+pub const BULLETPROOF_MAX_OUTPUTS: usize = 16;
+
 /// Input key image
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct KeyImage {
     /// The actual key image
@@ -52,6 +173,52 @@ pub struct KeyImage {
 
 impl_consensus_encoding!(KeyImage, image);
 
+impl KeyImage {
+    /// Compute the key image `I = x*Hp(P)` of the one-time secret key `x`, with `P = x*G` the
+    /// one-time public key
+    ///
+    /// **Same as** `generate_key_image` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+    pub fn new(secret: &PrivateKey) -> KeyImage {
+        let pubkey = PublicKey::from_private_key(secret);
+        let point = secret.scalar * hash::Hash::hash_to_point(pubkey.as_bytes());
+        KeyImage {
+            image: hash::Hash(point.compress().to_bytes()),
+        }
+    }
+
+    /// Decompress the key image into an Edwards point, return `None` if the bytes are not a
+    /// valid point encoding
+    pub fn decompress(&self) -> Option<EdwardsPoint> {
+        CompressedEdwardsY(self.image.to_bytes()).decompress()
+    }
+
+    /// Return whether the key image is a valid point in the prime order subgroup, i.e.
+    /// `l * key_image` is the identity
+    ///
+    /// **Same as** [`check_tx_inputs_keyimages_domain`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_core/blockchain.cpp)
+    pub fn is_torsion_free(&self) -> bool {
+        self.decompress()
+            .is_some_and(|point| point.is_torsion_free())
+    }
+
+    /// Return whether the key image is the identity point
+    pub fn is_identity(&self) -> bool {
+        self.decompress().is_some_and(|point| point.is_identity())
+    }
+}
+
+/// Return the first key image that appears more than once, i.e. a double spend, or `None` if all
+/// key images are unique
+pub fn find_double_spend<'a, I>(key_images: I) -> Option<&'a KeyImage>
+where
+    I: IntoIterator<Item = &'a KeyImage>,
+{
+    let mut seen = HashSet::new();
+    key_images
+        .into_iter()
+        .find(|key_image| !seen.insert(*key_image))
+}
+
 /// A transaction input, which defines the ring size and the key image to avoid
 /// double spend.
 #[derive(Debug, Clone)]
@@ -195,9 +362,38 @@ impl<'a> OwnedTxOut<'a> {
     }
 }
 
+/// Maximum size of the nonce sub-field in transaction extra
+///
+/// **Same as** `TX_EXTRA_NONCE_MAX_COUNT` in `tx_extra.h`
+pub const TX_EXTRA_NONCE_MAX_COUNT: usize = 255;
+
+/// Maximum size of the padding sub-field in transaction extra, including its tag
+///
+/// **Same as** `TX_EXTRA_PADDING_MAX_COUNT` in `tx_extra.h`
+pub const TX_EXTRA_PADDING_MAX_COUNT: usize = 255;
+
+/// Possible errors when building an extra field
+#[derive(Error, Debug, PartialEq)]
+pub enum ExtraFieldError {
+    /// More than one transaction public key
+    #[error("duplicate transaction public key")]
+    DuplicateTxPublicKey,
+    /// A nonce is larger than [`TX_EXTRA_NONCE_MAX_COUNT`]
+    #[error("nonce of {0} bytes exceeds the nonce sub-field limit")]
+    NonceTooLarge(usize),
+    /// The padding, including its tag, is larger than [`TX_EXTRA_PADDING_MAX_COUNT`]
+    #[error("padding of {0} bytes exceeds the padding sub-field limit")]
+    PaddingTooLarge(usize),
+    /// The padding is not the last sub-field
+    #[error("padding is not the last sub-field")]
+    PaddingNotLast,
+}
+
 /// Every transaction contains an Extra field, which is a part of transaction prefix
 ///
-/// Extra field is composed of typed sub fields of variable or fixed length.
+/// Extra field is composed of typed sub fields of variable or fixed length. Consensus rules do
+/// not require the extra field to be well formed, when decoded with a transaction the bytes after
+/// the first unparsable sub-field are kept in a [`SubField::Unknown`].
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct ExtraField(pub Vec<SubField>);
@@ -227,6 +423,145 @@ impl ExtraField {
             _ => None,
         })
     }
+
+    /// Sort the sub-fields in the canonical order: transaction public key, additional public keys,
+    /// nonce, merge mining, MinerGate, padding and finally unknown bytes. The sort is stable so
+    /// sub-fields of the same type keep their relative order.
+    ///
+    /// **Same as** `sort_tx_extra` with `allow_partial` in [`cryptonote_format_utils.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/cryptonote_format_utils.cpp)
+    pub fn sort(&mut self) {
+        self.0.sort_by_key(SubField::order);
+    }
+
+    /// Return the raw bytes of the extra field, without the length prefix
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(serialize).collect()
+    }
+
+    /// Return the payment id, if any, present in the nonces of extra field
+    pub fn extra_nonce(&self) -> Option<ExtraNonce> {
+        self.0.iter().find_map(|x| match x {
+            SubField::Nonce(nonce) => ExtraNonce::from_nonce(nonce),
+            _ => None,
+        })
+    }
+
+    /// Return the decrypted payment id, if an encrypted payment id and a transaction public key
+    /// are present in extra field, with the view secret key of the recipient
+    pub fn decrypted_payment_id(&self, view: &PrivateKey) -> Option<PaymentId> {
+        match self.extra_nonce()? {
+            ExtraNonce::EncryptedPaymentId(payment_id) => {
+                Some(payment_id.decrypt(&self.tx_pubkey()?, view))
+            }
+            ExtraNonce::PaymentId(_) => None,
+        }
+    }
+}
+
+/// Builder for an extra field with sub-fields in the canonical order
+///
+/// ```rust
+/// use monero::blockdata::transaction::{ExtraFieldBuilder, SubField};
+/// use monero::util::key::{PrivateKey, PublicKey};
+///
+/// let tx_key = PrivateKey::from_slice(&[1; 32]).unwrap();
+/// let extra = ExtraFieldBuilder::new()
+///     .field(SubField::Nonce(vec![0; 8]))
+///     .field(SubField::TxPublicKey(PublicKey::from_private_key(&tx_key)))
+///     .build()
+///     .unwrap();
+/// assert_eq!(Some(PublicKey::from_private_key(&tx_key)), extra.tx_pubkey());
+/// assert!(matches!(extra.0[1], SubField::Nonce(_)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtraFieldBuilder {
+    fields: Vec<SubField>,
+}
+
+impl ExtraFieldBuilder {
+    /// Create an empty extra field builder
+    pub fn new() -> Self {
+        ExtraFieldBuilder::default()
+    }
+
+    /// Add a sub-field to the extra field
+    pub fn field(mut self, field: SubField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Sort the sub-fields canonically, see [`ExtraField::sort`], and validate them: at most one
+    /// transaction public key, nonces of at most [`TX_EXTRA_NONCE_MAX_COUNT`] bytes and a padding
+    /// of at most [`TX_EXTRA_PADDING_MAX_COUNT`] bytes in the last position
+    pub fn build(&self) -> Result<ExtraField, ExtraFieldError> {
+        let mut extra = ExtraField(self.fields.clone());
+        extra.sort();
+        let mut has_pubkey = false;
+        for (i, field) in extra.0.iter().enumerate() {
+            match field {
+                SubField::TxPublicKey(_) if has_pubkey => {
+                    return Err(ExtraFieldError::DuplicateTxPublicKey)
+                }
+                SubField::TxPublicKey(_) => has_pubkey = true,
+                SubField::Nonce(nonce) if nonce.len() > TX_EXTRA_NONCE_MAX_COUNT => {
+                    return Err(ExtraFieldError::NonceTooLarge(nonce.len()))
+                }
+                SubField::Padding(_) if i + 1 != extra.0.len() => {
+                    return Err(ExtraFieldError::PaddingNotLast)
+                }
+                SubField::Padding(size) if *size as usize + 1 > TX_EXTRA_PADDING_MAX_COUNT => {
+                    return Err(ExtraFieldError::PaddingTooLarge(*size as usize + 1))
+                }
+                _ => (),
+            }
+        }
+        Ok(extra)
+    }
+
+    /// Build the extra field and return its raw bytes, see [`ExtraField::to_bytes`]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ExtraFieldError> {
+        Ok(self.build()?.to_bytes())
+    }
+}
+
+/// Payment id stored in a nonce sub-field, the first byte of the nonce is a tag identifying the
+/// payment id type.
+///
+/// **Same as** `set_payment_id_to_tx_extra_nonce` and `get_payment_id_from_tx_extra_nonce` in [`cryptonote_format_utils.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/cryptonote_format_utils.cpp)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum ExtraNonce {
+    /// Unencrypted 32 bytes payment id, tag `0x00`
+    PaymentId(hash::Hash),
+    /// Encrypted 8 bytes payment id, tag `0x01`
+    EncryptedPaymentId(PaymentId),
+}
+
+impl ExtraNonce {
+    /// Parse a nonce sub-field content, return `None` if the nonce does not hold a payment id
+    pub fn from_nonce(nonce: &[u8]) -> Option<ExtraNonce> {
+        match nonce.split_first()? {
+            (0x0, id) if id.len() == 32 => Some(ExtraNonce::PaymentId(hash::Hash::from_slice(id))),
+            (0x1, id) if id.len() == 8 => {
+                Some(ExtraNonce::EncryptedPaymentId(PaymentId::from_slice(id)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the nonce sub-field content
+    pub fn to_nonce(&self) -> Vec<u8> {
+        match self {
+            ExtraNonce::PaymentId(id) => [&[0x0], id.as_bytes()].concat(),
+            ExtraNonce::EncryptedPaymentId(id) => [&[0x1], id.as_bytes()].concat(),
+        }
+    }
+}
+
+impl From<ExtraNonce> for SubField {
+    fn from(nonce: ExtraNonce) -> SubField {
+        SubField::Nonce(nonce.to_nonce())
+    }
 }
 
 /// Each sub-field contains a sub-field tag followed by sub-field content of fixed or variable
@@ -241,12 +576,31 @@ pub enum SubField {
     Nonce(Vec<u8>),
     /// Padding size is limited to 255 null bytes, variable length
     Padding(u8),
-    /// Merge mining infos: `depth` and `merkle_root`, fixed length of one VarInt and 32 bytes hash
+    /// Merge mining infos: `depth` and `merkle_root`, variable length of one VarInt and 32 bytes
+    /// hash, see [`MergeMiningTag`](crate::blockdata::merge_mining::MergeMiningTag)
     MergeMining(VarInt, hash::Hash),
     /// Additional public keys for Subaddresses outputs, variable length of `n` additional public keys
     AdditionalPublickKey(Vec<PublicKey>),
     /// Mysterious MinerGate, variable length
     MysteriousMinerGate(String),
+    /// Unparsable bytes at the end of the extra field, without tag, kept as is to preserve the
+    /// encoding of the transaction
+    Unknown(Vec<u8>),
+}
+
+impl SubField {
+    /// Position of the sub-field in a sorted extra field
+    fn order(&self) -> u8 {
+        match self {
+            SubField::TxPublicKey(_) => 0,
+            SubField::AdditionalPublickKey(_) => 1,
+            SubField::Nonce(_) => 2,
+            SubField::MergeMining(_, _) => 3,
+            SubField::MysteriousMinerGate(_) => 4,
+            SubField::Padding(_) => 5,
+            SubField::Unknown(_) => 6,
+        }
+    }
 }
 
 impl Display for SubField {
@@ -269,6 +623,7 @@ impl Display for SubField {
             SubField::MysteriousMinerGate(miner_gate) => {
                 writeln!(fmt, "Mysterious miner gate: {}", miner_gate)
             }
+            SubField::Unknown(bytes) => writeln!(fmt, "Unknown: {}", hex_encode(bytes)),
         }
     }
 }
@@ -319,6 +674,14 @@ impl TransactionPrefix {
         self.outputs.len()
     }
 
+    /// Return an iterator over the key images of the transaction's key inputs
+    pub fn key_images(&self) -> impl Iterator<Item = &KeyImage> {
+        self.inputs.iter().filter_map(|input| match input {
+            TxIn::ToKey { k_image, .. } => Some(k_image),
+            _ => None,
+        })
+    }
+
     /// Return the transaction public key present in extra field
     pub fn tx_pubkey(&self) -> Option<PublicKey> {
         self.extra.tx_pubkey()
@@ -420,75 +783,728 @@ impl Display for Transaction {
     }
 }
 
+impl Transaction {
+    /// Return the amount of the output at `index`, decrypted with the output shared key
+    /// `Hs(8*a*R || n)` for RingCT transactions, `None` if the output does not exist or the
+    /// decrypted amount does not open the output commitment
+    ///
+    /// **Same as** the amount recovery of `check_tx_key_helper` in [`wallet2.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet2.cpp)
+    pub fn output_amount(&self, index: usize, shared_key: &PrivateKey) -> Option<u64> {
+        let output = self.prefix.outputs.get(index)?;
+        match &self.rct_signatures.sig {
+            Some(sig) if sig.rct_type != RctType::Null => sig
+                .open_output(index, shared_key)
+                .map(|opening| opening.amount),
+            _ => Some(*output.amount),
+        }
+    }
+
+    /// Check the transaction against the consensus rules that do not require the chain state,
+    /// the hard fork version is the one of the block including the transaction
+    ///
+    /// Inputs must all be key inputs with distinct ring members and key images in the prime order
+    /// subgroup, sorted by descending key images from hard fork 7. The ring size must be valid
+    /// for the hard fork, the exception for unmixable outputs before hard fork 6 is not handled.
+    /// Outputs must be key outputs with zero amounts under RingCT, at least 2 from hard fork 12
+    /// and at most [`BULLETPROOF_MAX_OUTPUTS`] with bulletproofs. The RingCT type must be
+    /// allowed at the hard fork and all signatures must match the number of inputs and outputs.
+    /// Miner transactions must not be checked with this function.
+    ///
+    /// **Same as** `check_tx_semantic`, `check_tx_outputs` and the stateless part of
+    /// `check_tx_inputs` in [`monero/src/cryptonote_core`](https://github.com/monero-project/monero/tree/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_core)
+    pub fn check_semantics(&self, hf_version: u8) -> Result<(), SemanticError> {
+        let version = *self.prefix.version;
+        let version_allowed = match version {
+            1 => hf_version < 6,
+            2 => hf_version >= 4,
+            _ => false,
+        };
+        if !version_allowed {
+            return Err(SemanticError::InvalidVersion {
+                version,
+                hf_version,
+            });
+        }
+
+        let ring_sizes = self.check_inputs(hf_version)?;
+        self.check_outputs(hf_version)?;
+
+        if version == 1 {
+            if self.signatures.len() != ring_sizes.len() {
+                return Err(SemanticError::LengthMismatch {
+                    field: "signatures",
+                    expected: ring_sizes.len(),
+                    found: self.signatures.len(),
+                });
+            }
+            for (sigs, ring_size) in self.signatures.iter().zip(ring_sizes) {
+                if sigs.len() != ring_size {
+                    return Err(SemanticError::LengthMismatch {
+                        field: "signatures",
+                        expected: ring_size,
+                        found: sigs.len(),
+                    });
+                }
+            }
+            Ok(())
+        } else {
+            if !self.signatures.is_empty() {
+                return Err(SemanticError::LengthMismatch {
+                    field: "signatures",
+                    expected: 0,
+                    found: self.signatures.len(),
+                });
+            }
+            self.check_rct_signatures(hf_version, &ring_sizes)
+        }
+    }
+
+    /// Check the inputs and return the ring size of each input
+    fn check_inputs(&self, hf_version: u8) -> Result<Vec<usize>, SemanticError> {
+        let inputs = &self.prefix.inputs;
+        if inputs.is_empty() {
+            return Err(SemanticError::NoInputs);
+        }
+
+        let mut ring_sizes = Vec::with_capacity(inputs.len());
+        let mut key_images = HashSet::with_capacity(inputs.len());
+        let mut last_key_image: Option<&hash::Hash> = None;
+        for (i, input) in inputs.iter().enumerate() {
+            let (amount, key_offsets, k_image) = match input {
+                TxIn::ToKey {
+                    amount,
+                    key_offsets,
+                    k_image,
+                } => (amount, key_offsets, k_image),
+                _ => return Err(SemanticError::InvalidInputType(i)),
+            };
+            if *self.prefix.version >= 2 && **amount != 0 {
+                return Err(SemanticError::NonZeroInputAmount(i));
+            }
+
+            if key_offsets.is_empty() {
+                return Err(SemanticError::EmptyKeyOffsets(i));
+            }
+            // Offsets are relative, a zero offset after the first one references the same output
+            if hf_version >= 6 && key_offsets[1..].iter().any(|offset| **offset == 0) {
+                return Err(SemanticError::DuplicateRingMember(i));
+            }
+            check_ring_size(i, key_offsets.len(), hf_version)?;
+            ring_sizes.push(key_offsets.len());
+
+            if !key_images.insert(k_image) {
+                return Err(SemanticError::DuplicateKeyImage(i));
+            }
+            let image = &k_image.image;
+            if let Some(last) = last_key_image {
+                if hf_version >= 7 && image.as_bytes() > last.as_bytes() {
+                    return Err(SemanticError::UnsortedKeyImages(i));
+                }
+            }
+            if !k_image.is_torsion_free() {
+                return Err(SemanticError::InvalidKeyImage(i));
+            }
+            last_key_image = Some(image);
+        }
+        Ok(ring_sizes)
+    }
+
+    fn check_outputs(&self, hf_version: u8) -> Result<(), SemanticError> {
+        let outputs = &self.prefix.outputs;
+        if outputs.is_empty() {
+            return Err(SemanticError::NoOutputs);
+        }
+        let is_rct = *self.prefix.version >= 2;
+        if is_rct && hf_version >= 12 && outputs.len() < 2 {
+            return Err(SemanticError::TooFewOutputs {
+                minimum: 2,
+                found: outputs.len(),
+            });
+        }
+        let is_bp = self
+            .rct_signatures
+            .sig
+            .as_ref()
+            .is_some_and(|sig| sig.rct_type.is_rct_bp());
+        if is_bp && outputs.len() > BULLETPROOF_MAX_OUTPUTS {
+            return Err(SemanticError::TooManyOutputs {
+                maximum: BULLETPROOF_MAX_OUTPUTS,
+                found: outputs.len(),
+            });
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            match output.target {
+                TxOutTarget::ToKey { .. } => (),
+                _ => return Err(SemanticError::InvalidOutputType(i)),
+            }
+            if is_rct && *output.amount != 0 {
+                return Err(SemanticError::NonZeroOutputAmount(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rct_signatures(
+        &self,
+        hf_version: u8,
+        ring_sizes: &[usize],
+    ) -> Result<(), SemanticError> {
+        let sig = self
+            .rct_signatures
+            .sig
+            .as_ref()
+            .ok_or(SemanticError::MissingRctSignatures)?;
+        let rct_type = sig.rct_type;
+        if !is_rct_type_allowed(rct_type, hf_version) {
+            return Err(SemanticError::RctTypeNotAllowed {
+                rct_type,
+                hf_version,
+            });
+        }
+        let p = self
+            .rct_signatures
+            .p
+            .as_ref()
+            .ok_or(SemanticError::MissingPrunable)?;
+
+        let inputs = ring_sizes.len();
+        let outputs = self.prefix.outputs.len();
+        let (base_pseudo_outs, prunable_pseudo_outs) = match rct_type {
+            RctType::Simple => (inputs, 0),
+            RctType::Full => (0, 0),
+            _ => (0, inputs),
+        };
+        let (range_sigs, min_bulletproofs, max_bulletproofs) = match rct_type {
+            RctType::Full | RctType::Simple => (outputs, 0, 0),
+            RctType::Bulletproof => (0, 1, outputs),
+            _ => (0, 1, 1),
+        };
+        let (mgs, clsags) = match rct_type {
+            RctType::Full => (1, 0),
+            RctType::CLSAG => (0, inputs),
+            _ => (inputs, 0),
+        };
+        check_length("ecdh_info", outputs, sig.ecdh_info.len())?;
+        check_length("out_pk", outputs, sig.out_pk.len())?;
+        check_length("pseudo_outs", base_pseudo_outs, sig.pseudo_outs.len())?;
+        check_length(
+            "prunable pseudo_outs",
+            prunable_pseudo_outs,
+            p.pseudo_outs.len(),
+        )?;
+        check_length("range_sigs", range_sigs, p.range_sigs.len())?;
+        if p.bulletproofs.len() < min_bulletproofs || p.bulletproofs.len() > max_bulletproofs {
+            return Err(SemanticError::LengthMismatch {
+                field: "bulletproofs",
+                expected: min_bulletproofs,
+                found: p.bulletproofs.len(),
+            });
+        }
+        check_length("MGs", mgs, p.MGs.len())?;
+        check_length("CLSAGs", clsags, p.CLSAGs.len())?;
+
+        if rct_type == RctType::Full {
+            for mg in &p.MGs {
+                check_length("MG ss", ring_sizes[0], mg.ss.len())?;
+            }
+        } else {
+            for (mg, ring_size) in p.MGs.iter().zip(ring_sizes) {
+                check_length("MG ss", *ring_size, mg.ss.len())?;
+            }
+            for (clsag, ring_size) in p.CLSAGs.iter().zip(ring_sizes) {
+                check_length("CLSAG s", *ring_size, clsag.s.len())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the ring size of an input against the rules of the hard fork, hard fork 15 still
+/// accepts the ring size 11 of the previous hard forks
+fn check_ring_size(input: usize, ring_size: usize, hf_version: u8) -> Result<(), SemanticError> {
+    let mixin = ring_size - 1;
+    if hf_version == 15 && mixin == 10 {
+        return Ok(());
+    }
+    let exact = match hf_version {
+        15..=u8::MAX => Some(15),
+        8..=14 => Some(10),
+        _ => None,
+    };
+    if let Some(exact) = exact {
+        if mixin != exact {
+            return Err(SemanticError::InvalidRingSize {
+                input,
+                expected: exact + 1,
+                found: ring_size,
+            });
+        }
+    }
+    let minimum = match hf_version {
+        7 => 6,
+        6 => 4,
+        2..=5 => 2,
+        _ => 0,
+    };
+    if mixin < minimum {
+        return Err(SemanticError::RingSizeTooSmall {
+            input,
+            minimum: minimum + 1,
+            found: ring_size,
+        });
+    }
+    Ok(())
+}
+
+/// Return whether the RingCT type can be used by a non-coinbase transaction at the hard fork
+fn is_rct_type_allowed(rct_type: RctType, hf_version: u8) -> bool {
+    match rct_type {
+        RctType::Null => false,
+        RctType::Full | RctType::Simple => hf_version <= 8,
+        RctType::Bulletproof => (8..=10).contains(&hf_version),
+        RctType::Bulletproof2 => (10..=13).contains(&hf_version),
+        RctType::CLSAG => (13..=15).contains(&hf_version),
+    }
+}
+
+fn check_length(field: &'static str, expected: usize, found: usize) -> Result<(), SemanticError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(SemanticError::LengthMismatch {
+            field,
+            expected,
+            found,
+        })
+    }
+}
+
+impl Transaction {
+    /// Return the hash of the prunable part of the RingCT signatures, as used in the transaction
+    /// hash, `None` for version 1 transactions and transactions without RingCT signatures
+    pub fn prunable_hash(&self) -> Option<hash::Hash> {
+        if *self.prefix.version == 1 {
+            return None;
+        }
+        let sig_base = self.rct_signatures.sig.as_ref()?;
+        if sig_base.rct_type == RctType::Null {
+            return Some(hash::Hash::null_hash());
+        }
+        match &self.rct_signatures.p {
+            Some(_) => Some(hash::Hash::hash(&self.prunable_blob())),
+            None => Some(hash::Hash::from_slice(&[
+                0x70, 0xa4, 0x85, 0x5d, 0x04, 0xd8, 0xfa, 0x7b, 0x3b, 0x27, 0x82, 0xca, 0x53, 0xb6,
+                0x00, 0xe5, 0xc0, 0x03, 0xc7, 0xdc, 0xb2, 0x7d, 0x7e, 0x92, 0x3c, 0x23, 0xf7, 0x86,
+                0x01, 0x46, 0xd2, 0xc5,
+            ])),
+        }
+    }
+
+    /// Return the consensus encoding of the prunable part of the transaction: the signatures of
+    /// version 1 transactions or the prunable part of the RingCT signatures
+    pub fn prunable_blob(&self) -> Vec<u8> {
+        let mut encoder = Cursor::new(vec![]);
+        match *self.prefix.version {
+            1 => {
+                for sigs in self.signatures.iter() {
+                    for sig in sigs {
+                        sig.consensus_encode(&mut encoder).unwrap();
+                    }
+                }
+            }
+            _ => {
+                if let (Some(sig), Some(p)) = (&self.rct_signatures.sig, &self.rct_signatures.p) {
+                    p.consensus_encode(&mut encoder, sig.rct_type).unwrap();
+                }
+            }
+        }
+        encoder.into_inner()
+    }
+
+    /// Split the transaction into its pruned transaction and the consensus encoding of its
+    /// prunable part, the encoding of the pruned transaction followed by the prunable part is the
+    /// encoding of the transaction
+    pub fn prune(&self) -> (PrunedTransaction, Vec<u8>) {
+        let pruned = PrunedTransaction {
+            prefix: self.prefix.clone(),
+            rct_signatures: match *self.prefix.version {
+                1 => None,
+                _ => self.rct_signatures.sig.clone(),
+            },
+            prunable_hash: self.prunable_hash().unwrap_or_else(hash::Hash::null_hash),
+        };
+        (pruned, self.prunable_blob())
+    }
+}
+
+/// Hash of a version 2 transaction, from the hash of its prefix, of its RingCT signatures base
+/// and of its prunable part
+fn rct_transaction_hash(
+    prefix: &TransactionPrefix,
+    sig_base: Option<&RctSigBase>,
+    prunable_hash: hash::Hash,
+) -> hash::Hash {
+    let mut hashes: Vec<hash::Hash> = vec![hash::Hashable::hash(prefix)];
+    if let Some(sig_base) = sig_base {
+        hashes.push(hash::Hashable::hash(sig_base));
+        hashes.push(prunable_hash);
+    }
+    let bytes: Vec<u8> = hashes
+        .into_iter()
+        .flat_map(|h| Vec::from(&h.to_bytes()[..]))
+        .collect();
+    hash::Hash::hash(&bytes)
+}
+
 impl hash::Hashable for Transaction {
     fn hash(&self) -> hash::Hash {
         match *self.prefix.version {
             1 => hash::Hash::hash(&serialize(self)),
+            _ => rct_transaction_hash(
+                &self.prefix,
+                self.rct_signatures.sig.as_ref(),
+                self.prunable_hash().unwrap_or_else(hash::Hash::null_hash),
+            ),
+        }
+    }
+}
+
+/// A transaction without its prunable part, as stored and relayed by pruned nodes
+///
+/// The pruned transaction is encoded as its prefix followed by the base of its RingCT
+/// signatures. The signatures of version 1 transactions and the prunable part of RingCT
+/// signatures are replaced by the hash of the prunable part, which is enough to compute the
+/// hash of version 2 transactions.
+///
+/// ```rust
+/// use monero::blockdata::transaction::{PrunedTransaction, Transaction};
+/// use monero::consensus::encode::{deserialize, serialize};
+/// use monero::cryptonote::hash::Hashable;
+///
+/// # let raw_tx = hex::decode("02000102000bb2e38c0189ea01a9bc02a533fe02a90705fd0540745f59f49374365304f8b4d5da63b444b2d74a40f8007ea44940c15cbbc80c9d106802000267f0f669ead579c1067cbffdf67c4af80b0287c549a10463122b4860fe215f490002b6a2e2f35a93d637ff7d25e20da326cee8e92005d3b18b3c425dabe8336568992c01d6c75cf8c76ac458123f2a498512eb65bb3cecba346c8fcfc516dc0c88518bb90209016f82359eb1fe71d604f0dce9470ed5fd4624bb9fce349a0e8317eabf4172f78a8b27dec6ea1a46da10ed8620fa8367c6391eaa8aabf4ebf660d9fe0eb7e9dfa08365a089ad2df7bce7ef776467898d5ca8947152923c54a1c5030e0c2f01035c555ff4285dcc44dfadd6bc37ec8b9354c045c6590446a81c7f53d8f199cace3faa7f17b3b8302a7cbb3881e8fdc23cca0275c9245fdc2a394b8d3ae73911e3541b10e7725cdeef5e0307bc218caefaafe97c102f39c8ce78f62cccf23c69baf0af55933c9d384ceaf07488f2f1ac7343a593449afd54d1065f6a1a4658845817e4b0e810afc4ca249096e463f9f368625fa37d5bbcbe87af68ce3c4d630f93a66defa4205b178f4e9fa04107bd535c7a4b2251df2dad255e470b611ffe00078c2916fc1eb2af1273e0df30dd1c74b6987b9885e7916b6ca711cbd4b7b50576e51af1439e9ed9e33eb97d8faba4e3bd46066a5026a1940b852d965c1db455d1401687ccaccc524e000b05966763564b7deb8fd64c7fb3d649897c94583dca1558893b071f5e6700dad139f3c6f973c7a43b207ee3e67dc7f7f18b52df442258200c7fe6d16685127da1df9b0d93d764c2659599bc6d300ae33bf8b7c2a504317da90ea2f0bb2af09bd531feae57cb4a0273d8add62fadfc6d43402372e5caf854e112b88417936f1a9c4045d48b5b0b7703d96801b35ff66c716cddbee1b92407aa069a162c163071710e28ccddf6fb560feea32485f2c54a477ae23fd8210427eabe4288cbe0ecbef4ed19ca049ceded424d9f839da957f56ffeb73060ea15498fcbc2d73606e85e963a667dafdb2641fb91862c07b98c1fdae8fadf514600225036dd63c22cdadb57d2125ebf30bc77f7ea0bc0dafb484bf01434954c5053b9c8a143f06972f80fa66788ea1e3425dc0104a9e3674729967b9819552ebb172418da0e4b3778ad4b3d6acd8f354ba09e54bbc8604540010e1e1e4d3066515aed457bd3399c0ce787236dbcd3923de4fb8faded10199b33c1251191612ab5526c1cf0cd55a0aeaed3f7a955ceced16dabdbeb0a2a19a9fdb5aa8c4fc8767cf70e4ad1838518bc6b9de7c420c1f57636579a14a5a8bdacd24e61a68adede8a2e07416c25409dd91ab78905bc99bab4ab4fb9e4ea628e09a271837769c4e67e580dcd5485e12e4e308cb4509686a7484a71f7dfe334499808c7122f07d45d89230b1f19ed86f675b7fec44ef5f3b178ae0af92ff114bd96baa264604fea5a762307bdce6cb483b7bc780d32ed5343fcc3aa306997f211dc075f6dfd66035c1db10bef8656fefbb45645264d401682e42fe3e05906f79d65481b87508f1a4c434e0d1dfc247d4276306f801a6b57e4e4a525177bae24e0bd88a216597d9db44f2604c29d8a5f74e7b934f55048690b5dcefd6489a81aa64c1edb49b320faab94130e603d99e455cfd828bca782176192ece95e9b967fe3dd698574cf0c0b6926970b156e1134658de657de42c4930e72b49c0d94da66c330ab188c10f0d2f578590f31bcac6fcff7e21f9ff67ae1a40d5a03b19301dcbbadc1aa9392795cf81f1401ec16d986a7f96fbb9e8e12ce04a2226e26b78117a4dfb757c6a44481ff68bb0909e7010988cd37146fb45d4cca4ba490aae323bb51a12b6864f88ea6897aa700ee9142eaf0880844083026f044a5e3dba4aae08578cb057976001beb27b5110c41fe336bf7879733739ce22fb31a1a6ac2c900d6d6c6facdbc60085e5c93d502542cfea90dbc62d4e061b7106f09f9c4f6c1b5506dd0550eb8b2bf17678b140de33a10ba676829092e6a13445d1857d06c715eea4492ff864f0b34d178a75a0f1353078f83cfee1440b0a20e64abbd0cab5c6e7083486002970a4904f8371805d1a0ee4aea8524168f0f39d2dfc55f545a98a031841a740e8422a62e123c8303021fb81afbb76d1120c0fbc4d3d97ba69f4e2fe086822ece2047c9ccea507008654c199238a5d17f009aa2dd081f7901d0688aa15311865a319ccba8de4023027235b5725353561c5f1185f6a063fb32fc65ef6e90339d406a6884d66be49d03daaf116ee4b65ef80dd3052a13157b929f98640c0bbe99c8323ce3419a136403dc3f7a95178c3966d2d7bdecf516a28eb2cf8cddb3a0463dc7a6248883f7be0a10aae1bb50728ec9b8880d6011b366a850798f6d7fe07103695dded3f371ca097c1d3596967320071d7f548938afe287cb9b8fae761fa592425623dcbf653028").unwrap();
+/// let tx = deserialize::<Transaction>(&raw_tx).unwrap();
+///
+/// // A pruned node stores the pruned blob and the prunable hash
+/// let (pruned, _prunable) = tx.prune();
+/// let blob = serialize(&pruned);
+///
+/// let pruned = PrunedTransaction::from_pruned_blob(&blob, pruned.prunable_hash).unwrap();
+/// assert_eq!(Some(tx.hash()), pruned.hash());
+/// ```
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct PrunedTransaction {
+    /// The transaction prefix
+    pub prefix: TransactionPrefix,
+    /// The base of the RingCT signatures, `None` for version 1 transactions
+    pub rct_signatures: Option<RctSigBase>,
+    /// Hash of the prunable part, the null hash for version 1 transactions
+    pub prunable_hash: hash::Hash,
+}
+
+impl PrunedTransaction {
+    /// Decode a pruned transaction given the hash of its prunable part
+    pub fn consensus_decode<D: Decoder>(
+        d: &mut D,
+        prunable_hash: hash::Hash,
+    ) -> Result<PrunedTransaction, encode::Error> {
+        let prefix: TransactionPrefix = Decodable::consensus_decode(d)?;
+        let rct_signatures = match *prefix.version {
+            1 => None,
+            _ if prefix.inputs.is_empty() => None,
+            _ => RctSigBase::consensus_decode(d, prefix.inputs.len(), prefix.outputs.len())?,
+        };
+        Ok(PrunedTransaction {
+            prefix,
+            rct_signatures,
+            prunable_hash,
+        })
+    }
+
+    /// Deserialize a pruned blob given the hash of its prunable part, will error if the
+    /// deserialization doesn't consume the entire blob
+    pub fn from_pruned_blob(
+        blob: &[u8],
+        prunable_hash: hash::Hash,
+    ) -> Result<PrunedTransaction, encode::Error> {
+        let mut decoder = BoundedDecoder::from_slice(blob);
+        let pruned = PrunedTransaction::consensus_decode(&mut decoder, prunable_hash)?;
+        if decoder.bytes_read() as usize == blob.len() {
+            Ok(pruned)
+        } else {
+            Err(encode::Error::ParseFailed(
+                "data not consumed entirely when explicitly deserializing",
+            ))
+        }
+    }
+
+    /// Return the transaction hash, `None` for version 1 transactions as their hash covers their
+    /// signatures
+    pub fn hash(&self) -> Option<hash::Hash> {
+        match *self.prefix.version {
+            1 => None,
+            _ => Some(rct_transaction_hash(
+                &self.prefix,
+                self.rct_signatures.as_ref(),
+                self.prunable_hash,
+            )),
+        }
+    }
+
+    /// Rebuild the full transaction from the consensus encoding of its prunable part, the
+    /// prunable part of version 2 transactions must match the prunable hash
+    pub fn with_prunable(self, prunable: &[u8]) -> Result<Transaction, encode::Error> {
+        let mut decoder = BoundedDecoder::from_slice(prunable);
+        let mut tx = Transaction {
+            prefix: self.prefix,
+            signatures: vec![],
+            rct_signatures: RctSig { sig: None, p: None },
+        };
+        match *tx.prefix.version {
+            1 => tx.signatures = decode_signatures(&mut decoder, &tx.prefix)?,
             _ => {
-                let mut hashes: Vec<hash::Hash> = vec![];
-                hashes.push(self.prefix.hash());
-                if let Some(sig_base) = &self.rct_signatures.sig {
-                    hashes.push(sig_base.hash());
-                    if sig_base.rct_type == RctType::Null {
-                        hashes.push(hash::Hash::null_hash());
-                    } else {
-                        match &self.rct_signatures.p {
-                            Some(p) => {
-                                let mut encoder = Cursor::new(vec![]);
-                                p.consensus_encode(&mut encoder, sig_base.rct_type).unwrap();
-                                hashes.push(hash::Hash::hash(&encoder.into_inner()));
-                            }
-                            None => {
-                                let empty_hash = hash::Hash::from_slice(&[
-                                    0x70, 0xa4, 0x85, 0x5d, 0x04, 0xd8, 0xfa, 0x7b, 0x3b, 0x27,
-                                    0x82, 0xca, 0x53, 0xb6, 0x00, 0xe5, 0xc0, 0x03, 0xc7, 0xdc,
-                                    0xb2, 0x7d, 0x7e, 0x92, 0x3c, 0x23, 0xf7, 0x86, 0x01, 0x46,
-                                    0xd2, 0xc5,
-                                ]);
-                                hashes.push(empty_hash);
-                            }
-                        }
-                    }
+                if let Some(sig) = self.rct_signatures {
+                    tx.rct_signatures.p = decode_prunable(&mut decoder, &tx.prefix, &sig)?;
+                    tx.rct_signatures.sig = Some(sig);
                 }
-                let bytes: Vec<u8> = hashes
-                    .into_iter()
-                    .flat_map(|h| Vec::from(&h.to_bytes()[..]))
-                    .collect();
-                hash::Hash::hash(&bytes)
             }
         }
+        if decoder.bytes_read() as usize != prunable.len() {
+            return Err(encode::Error::ParseFailed(
+                "data not consumed entirely when explicitly deserializing",
+            ));
+        }
+        match tx.prunable_hash() {
+            Some(prunable_hash) if prunable_hash != self.prunable_hash => {
+                Err(Error::PrunableHashMismatch.into())
+            }
+            _ => Ok(tx),
+        }
+    }
+}
+
+impl<S: Encoder> Encodable<S> for PrunedTransaction {
+    fn consensus_encode(&self, s: &mut S) -> Result<(), encode::Error> {
+        self.prefix.consensus_encode(s)?;
+        if let Some(sig) = &self.rct_signatures {
+            sig.consensus_encode(s)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reader counting the consumed bytes, with a look-ahead of one byte to detect the end of the
+/// stream between two transactions
+#[derive(Debug)]
+struct CountingReader<R> {
+    inner: R,
+    peeked: Option<u8>,
+    count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    fn is_at_end(&mut self) -> io::Result<bool> {
+        if self.peeked.is_some() {
+            return Ok(false);
+        }
+        let mut byte = [0u8; 1];
+        loop {
+            match self.inner.read(&mut byte) {
+                Ok(0) => return Ok(true),
+                Ok(_) => {
+                    self.peeked = Some(byte[0]);
+                    return Ok(false);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let read = match self.peeked.take() {
+            Some(byte) => {
+                buf[0] = byte;
+                1
+            }
+            None => self.inner.read(buf)?,
+        };
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+/// Streaming decoder of consensus encoded transactions
+///
+/// Transactions are decoded one after the other from any reader, e.g. a TCP stream or a file of
+/// concatenated transactions, and the number of bytes consumed by each transaction is reported.
+/// Each transaction is decoded by a [`BoundedDecoder`] with the [`DecodeLimits`] of the decoder,
+/// so hostile input cannot request huge allocations.
+///
+/// After an error the position of the reader is unspecified, the decoder stops and returns no
+/// more transactions.
+///
+/// ```rust
+/// use monero::blockdata::transaction::TransactionDecoder;
+/// use monero::consensus::encode::serialize;
+/// use monero::cryptonote::hash::Hashable;
+/// use monero::blockdata::block::Block;
+/// # use monero::consensus::encode::deserialize;
+/// # let hex = hex::decode("0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000").unwrap();
+/// # let block = deserialize::<Block>(&hex).unwrap();
+///
+/// let blob = serialize(&block.miner_tx);
+/// let stream = [&blob[..], &blob[..]].concat();
+///
+/// let mut decoder = TransactionDecoder::new(&stream[..]);
+/// for decoded in &mut decoder {
+///     let (tx, consumed) = decoded.unwrap();
+///     assert_eq!(block.miner_tx.hash(), tx.hash());
+///     assert_eq!(blob.len(), consumed);
+/// }
+/// assert_eq!(stream.len() as u64, decoder.position());
+/// ```
+///
+#[derive(Debug)]
+pub struct TransactionDecoder<R> {
+    reader: CountingReader<R>,
+    limits: DecodeLimits,
+    failed: bool,
+}
+
+impl<R: Read> TransactionDecoder<R> {
+    /// Create a decoder reading from the reader with the default limits
+    pub fn new(reader: R) -> TransactionDecoder<R> {
+        TransactionDecoder::with_limits(reader, DecodeLimits::default())
+    }
+
+    /// Create a decoder reading from the reader with the given limits, applied to each
+    /// transaction
+    pub fn with_limits(reader: R, limits: DecodeLimits) -> TransactionDecoder<R> {
+        TransactionDecoder {
+            reader: CountingReader {
+                inner: reader,
+                peeked: None,
+                count: 0,
+            },
+            limits,
+            failed: false,
+        }
+    }
+
+    /// Return the limits of the decoder
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// Return the number of bytes consumed since the creation of the decoder
+    pub fn position(&self) -> u64 {
+        self.reader.count
+    }
+
+    /// Return the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader.inner
+    }
+
+    /// Decode the next transaction and return it with the number of bytes it consumed, `None`
+    /// if the reader is at its end or after an error
+    pub fn next_transaction(&mut self) -> Result<Option<(Transaction, usize)>, encode::Error> {
+        if self.failed {
+            return Ok(None);
+        }
+        let res = self.decode_next();
+        self.failed = res.is_err();
+        res
+    }
+
+    fn decode_next(&mut self) -> Result<Option<(Transaction, usize)>, encode::Error> {
+        if self.reader.is_at_end().map_err(encode::Error::Io)? {
+            return Ok(None);
+        }
+        let start = self.reader.count;
+        let mut decoder = BoundedDecoder::new(&mut self.reader, self.limits);
+        let tx = Transaction::consensus_decode(&mut decoder)?;
+        Ok(Some((tx, (self.reader.count - start) as usize)))
     }
 }
 
+impl<R: Read> Iterator for TransactionDecoder<R> {
+    type Item = Result<(Transaction, usize), encode::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_transaction().transpose()
+    }
+}
+
+impl<R: Read> FusedIterator for TransactionDecoder<R> {}
+
 // ----------------------------------------------------------------------------------------------------------------
 
 impl<D: Decoder> Decodable<D> for ExtraField {
     fn consensus_decode(d: &mut D) -> Result<ExtraField, encode::Error> {
-        let mut fields: Vec<SubField> = vec![];
         let bytes: Vec<u8> = Decodable::consensus_decode(d)?;
-        let mut decoder = Cursor::new(&bytes[..]);
+        Ok(ExtraField::parse_lenient(&bytes))
+    }
+}
+
+impl ExtraField {
+    /// Parse the sub-fields of the raw bytes of an extra field, fail on the first unparsable
+    /// sub-field
+    pub fn parse(bytes: &[u8]) -> Result<ExtraField, encode::Error> {
+        let mut fields: Vec<SubField> = vec![];
+        let mut decoder = BoundedDecoder::from_slice(bytes);
         // Decode each extra field
-        while decoder.position() < bytes.len() as u64 {
-            fields.push(Decodable::consensus_decode(&mut decoder)?);
+        while decoder.bytes_read() < bytes.len() as u64 {
+            fields.push(decode_sub_field(&mut decoder, bytes)?);
         }
-        // Fail if data are not consumed entirely.
-        if decoder.position() as usize == bytes.len() {
-            Ok(ExtraField(fields))
-        } else {
-            Err(encode::Error::ParseFailed(
-                "data not consumed entirely when explicitly deserializing",
-            ))
+        Ok(ExtraField(fields))
+    }
+
+    /// Parse the sub-fields of the raw bytes of an extra field, stop at the first unparsable
+    /// sub-field and keep the remaining bytes in a [`SubField::Unknown`]
+    ///
+    /// The extra field always serializes back to `bytes`.
+    pub fn parse_lenient(bytes: &[u8]) -> ExtraField {
+        let mut fields: Vec<SubField> = vec![];
+        let mut decoder = BoundedDecoder::from_slice(bytes);
+        while decoder.bytes_read() < bytes.len() as u64 {
+            let position = decoder.bytes_read() as usize;
+            match decode_sub_field(&mut decoder, bytes) {
+                Ok(field) => fields.push(field),
+                Err(_) => {
+                    fields.push(SubField::Unknown(bytes[position..].to_vec()));
+                    break;
+                }
+            }
         }
+        ExtraField(fields)
     }
 }
 
+/// Decode the next sub-field of the extra field `bytes`, the sub-field must encode back to the
+/// exact same bytes
+fn decode_sub_field(
+    decoder: &mut BoundedDecoder<Cursor<&[u8]>>,
+    bytes: &[u8],
+) -> Result<SubField, encode::Error> {
+    let start = decoder.bytes_read() as usize;
+    let field: SubField = Decodable::consensus_decode(decoder)?;
+    if serialize(&field)[..] != bytes[start..decoder.bytes_read() as usize] {
+        return Err(encode::Error::ParseFailed("Non canonical sub-field"));
+    }
+    Ok(field)
+}
+
 impl<S: Encoder> Encodable<S> for ExtraField {
     fn consensus_encode(&self, s: &mut S) -> Result<(), encode::Error> {
-        let mut encoder = Cursor::new(vec![]);
-        for field in self.0.iter() {
-            field.consensus_encode(&mut encoder)?;
-        }
-        encoder.into_inner().consensus_encode(s)
+        self.to_bytes().consensus_encode(s)
     }
 }
 
@@ -498,7 +1514,7 @@ impl<D: Decoder> Decodable<D> for SubField {
 
         match tag {
             0x0 => {
-                let mut i = 0;
+                let mut i = 0u8;
                 loop {
                     // Consume all bytes until the end of cursor
                     // A new cursor must be created when parsing extra bytes otherwise
@@ -507,20 +1523,35 @@ impl<D: Decoder> Decodable<D> for SubField {
                     // This works because extra padding must be the last one
                     let byte: Result<u8, encode::Error> = Decodable::consensus_decode(d);
                     match byte {
-                        Ok(_) => {
+                        Ok(0) if i as usize + 1 < TX_EXTRA_PADDING_MAX_COUNT => {
                             i += 1;
                         }
+                        Ok(0) => return Err(encode::Error::ParseFailed("Padding too large")),
+                        Ok(_) => return Err(encode::Error::ParseFailed("Non-zero padding")),
                         Err(_) => break,
                     }
                 }
                 Ok(SubField::Padding(i))
             }
             0x1 => Ok(SubField::TxPublicKey(Decodable::consensus_decode(d)?)),
-            0x2 => Ok(SubField::Nonce(Decodable::consensus_decode(d)?)),
-            0x3 => Ok(SubField::MergeMining(
-                Decodable::consensus_decode(d)?,
-                Decodable::consensus_decode(d)?,
-            )),
+            0x2 => {
+                let nonce: Vec<u8> = Decodable::consensus_decode(d)?;
+                if nonce.len() > TX_EXTRA_NONCE_MAX_COUNT {
+                    return Err(encode::Error::ParseFailed("Nonce too large"));
+                }
+                Ok(SubField::Nonce(nonce))
+            }
+            0x3 => {
+                // The tag is stored as a string, like in monerod
+                let bytes: Vec<u8> = Decodable::consensus_decode(d)?;
+                let mut decoder = BoundedDecoder::from_slice(&bytes);
+                let depth = Decodable::consensus_decode(&mut decoder)?;
+                let merkle_root = Decodable::consensus_decode(&mut decoder)?;
+                if decoder.bytes_read() as usize != bytes.len() {
+                    return Err(encode::Error::ParseFailed("Invalid merge mining tag"));
+                }
+                Ok(SubField::MergeMining(depth, merkle_root))
+            }
             0x4 => Ok(SubField::AdditionalPublickKey(Decodable::consensus_decode(
                 d,
             )?)),
@@ -552,8 +1583,9 @@ impl<S: Encoder> Encodable<S> for SubField {
             }
             SubField::MergeMining(ref depth, ref merkle_root) => {
                 0x3u8.consensus_encode(s)?;
-                depth.consensus_encode(s)?;
-                merkle_root.consensus_encode(s)
+                let mut bytes = serialize(depth);
+                bytes.extend_from_slice(merkle_root.as_bytes());
+                bytes.consensus_encode(s)
             }
             SubField::AdditionalPublickKey(ref pubkeys) => {
                 0x4u8.consensus_encode(s)?;
@@ -563,6 +1595,12 @@ impl<S: Encoder> Encodable<S> for SubField {
                 0xdeu8.consensus_encode(s)?;
                 string.consensus_encode(s)
             }
+            SubField::Unknown(ref bytes) => {
+                for byte in bytes {
+                    byte.consensus_encode(s)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -632,68 +1670,85 @@ impl<S: Encoder> Encodable<S> for TxOutTarget {
 }
 
 #[allow(non_snake_case)]
+/// Decode the ring signatures of a version 1 transaction, one signature per ring member of
+/// each key input
+fn decode_signatures<D: Decoder>(
+    d: &mut D,
+    prefix: &TransactionPrefix,
+) -> Result<Vec<Vec<Signature>>, encode::Error> {
+    prefix
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            TxIn::ToKey { key_offsets, .. } => {
+                let sigs: Result<Vec<Signature>, encode::Error> = key_offsets
+                    .iter()
+                    .map(|_| Decodable::consensus_decode(d))
+                    .collect();
+                Some(sigs)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decode the prunable part of the RingCT signatures of a version 2 transaction
+fn decode_prunable<D: Decoder>(
+    d: &mut D,
+    prefix: &TransactionPrefix,
+    sig: &RctSigBase,
+) -> Result<Option<RctSigPrunable>, encode::Error> {
+    if sig.rct_type == RctType::Null {
+        return Ok(None);
+    }
+    RctSigPrunable::consensus_decode(
+        d,
+        sig.rct_type,
+        prefix.inputs.len(),
+        prefix.outputs.len(),
+        mixin_size(prefix)?,
+    )
+}
+
+/// Number of mixins of the RingCT signatures, given by the ring size of the first input
+fn mixin_size(prefix: &TransactionPrefix) -> Result<usize, encode::Error> {
+    match prefix.inputs.first() {
+        Some(TxIn::ToKey { key_offsets, .. }) => key_offsets
+            .len()
+            .checked_sub(1)
+            .ok_or(encode::Error::ParseFailed("Empty ring")),
+        _ => Ok(0),
+    }
+}
+
 impl<D: Decoder> Decodable<D> for Transaction {
     fn consensus_decode(d: &mut D) -> Result<Transaction, encode::Error> {
         let prefix: TransactionPrefix = Decodable::consensus_decode(d)?;
 
-        let inputs = prefix.inputs.len();
-        let outputs = prefix.outputs.len();
-
         match *prefix.version {
             1 => {
-                let signatures: Result<Vec<Vec<Signature>>, encode::Error> = prefix
-                    .inputs
-                    .iter()
-                    .filter_map(|input| match input {
-                        TxIn::ToKey { key_offsets, .. } => {
-                            let sigs: Result<Vec<Signature>, encode::Error> = key_offsets
-                                .iter()
-                                .map(|_| Decodable::consensus_decode(d))
-                                .collect();
-                            Some(sigs)
-                        }
-                        _ => None,
-                    })
-                    .collect();
+                let signatures = decode_signatures(d, &prefix)?;
                 Ok(Transaction {
                     prefix,
-                    signatures: signatures?,
+                    signatures,
                     rct_signatures: RctSig { sig: None, p: None },
                 })
             }
             _ => {
                 let signatures = vec![];
                 let mut rct_signatures = RctSig { sig: None, p: None };
-                if inputs == 0 {
+                if prefix.inputs.is_empty() {
                     return Ok(Transaction {
                         prefix,
                         signatures,
-                        rct_signatures: RctSig { sig: None, p: None },
+                        rct_signatures,
                     });
                 }
 
+                let inputs = prefix.inputs.len();
+                let outputs = prefix.outputs.len();
                 if let Some(sig) = RctSigBase::consensus_decode(d, inputs, outputs)? {
-                    let p = {
-                        if sig.rct_type != RctType::Null {
-                            let mixin_size = if inputs > 0 {
-                                match &prefix.inputs[0] {
-                                    TxIn::ToKey { key_offsets, .. } => key_offsets.len() - 1,
-                                    _ => 0,
-                                }
-                            } else {
-                                0
-                            };
-                            RctSigPrunable::consensus_decode(
-                                d,
-                                sig.rct_type,
-                                inputs,
-                                outputs,
-                                mixin_size,
-                            )?
-                        } else {
-                            None
-                        }
-                    };
+                    let p = decode_prunable(d, &prefix, &sig)?;
                     rct_signatures = RctSig { sig: Some(sig), p };
                 }
 
@@ -733,15 +1788,23 @@ impl<S: Encoder> Encodable<S> for Transaction {
 mod tests {
     use std::str::FromStr;
 
-    use super::{ExtraField, Transaction, TransactionPrefix};
+    use super::{
+        check_ring_size, find_double_spend, Error, ExtraField, ExtraFieldBuilder, ExtraFieldError,
+        ExtraNonce, KeyImage, PrunedTransaction, SemanticError, Transaction, TransactionPrefix,
+    };
     use crate::blockdata::transaction::{SubField, TxOutTarget};
     use crate::blockdata::TxIn;
-    use crate::consensus::encode::{deserialize, deserialize_partial, serialize, VarInt};
-    use crate::cryptonote::hash::Hashable;
+    use crate::consensus::encode::{self, deserialize, deserialize_partial, serialize, VarInt};
+    use crate::cryptonote::hash::{Hash, Hashable};
+    use crate::cryptonote::onetime_key::KeyGenerator;
+    use crate::util::address::PaymentId;
     use crate::util::key::{PrivateKey, PublicKey, ViewPair};
     use crate::util::ringct::{RctSig, RctSigBase, RctType};
     use crate::TxOut;
 
+    /// Two inputs RingCT transaction
+    const RCT_TX: &str = "02000202000bc6aa98049bf603fcec06bd3ccbad04e807e328b5128f22a63bfb27b6e287e8d594664d5cddd6c89bc413d1bc607b242203a6eb3180041ff5ae679702000b90e4eb028298a101879110f5bc0383ad03cbfc03a750e52ace37d112c6064faf7d16e2d07c4cc979dccb858aa9b24e12479e4a2db8350a906ba7a1aec409020002ab6d783607d8e712bbd5aad54a412aec890fcdcc1b35bf0ca4a705c2159bfc32000262f4016d5d81ade9e555807a24d23d452f08b6400683da599abd7134fb75324a2c0209016631a2dee1d0f51f015fd9bf938cf132790bdc5c528037e347828c539e82da6e5921e3d1e6052cb25804d0b7ba81018a4cd5385ca23ff4f6d76dc41b5254abf579b1856d3fbd04e81ff97c113e318bf7e158fbb0db7adc6ece9c8d4ab94e91f68e9607667a858ddf3e6890b2835403db6dcc5a1c179a768bcf74d74ace86430176b0056de37f310884e8eed56ee86840f23f842f1db52945b2feef98f4b56b3d4407734e4e8d3b117b5fd78f0d94f6059b495f53cf855b3716bbe8614d51727556c8b2e5c303cffc694257a1e91372de2047c4e12381c1de8df46102cdd84a24692f68ada05d1ffc5122b655582c6307141e130a6963198085ddb67d304b0ddde87e62402a3cadcd07a315604607ddf1530bd85685e910aa879733549bde0d019edc36326d33edd6ccecc800395b7075e4959779bde803dd787c24bc25d40205071b180152dded8b0be1f48a6d6f8e97c3f934f866b1b697f73f73fc9f38c5d2082c610732c79b2f69f403f7e2d312399739dd8d4225a2914a3020bd88c362271df633e8387b5345b50f11c4f148f76c0c24ca5843580a02fe72d18f47dcf8d601e28bbef2cd6659e620179adad4dfa5a0c7b712d716c4e630fe40bbfc6184f76c401db4b801a7001f65a9c11053db919099f9a1a4fe575c6d783e041ee08222a46adb8a1f13b863d95da277b71ccdefbb32f713a13b5ac8d041bbbeed9df4ace5a6b730b871ad2fe14141dbb9c816a21fd7fc48cfba4d2cc3e5e5fba29f581c1507a6a36285a30344790b74d2212dd26178395cd96a18518ead5c59a410baf6ca0b9217865fff207d757bb465fdb053e8c80b2ec1a966ccc01f49096fb991b65cc160a5070532c47318720fb9a90f187ce53661b6ce1e29d6ccd2b131324101170bb87ef273f0e73d762e159264f0839c6b3b31f5264499bdeb029c66a7035108a84256aec2760e74c2e8e788b7747084da8953aa48696b7a46e6320a9534d6c06ddee1b26671f03ae70a30c76b8fbf268da16fbb685f1d3f602668afce2e3eaf089b8758069f398eaeccd01876cef623201dc46dd75f76dc2141a9a2071b2761eefbf735ca599ed15266acbd0f54f49de38b5c7b3b378c386767383396645778021b30910e6e06937d65dac82312968d4f63a0bb28c96b9a09ee1d95c4d3afbb021998a9e290647b51083a80d66e0a8900a89c1236214f2d7c0080e2c18cdc8c0b4bd66765c3c52a26b90ec549bc8358ff8aaac9ffaecb0f6c915113cc97147b06c007a1a6bcd536bddec7fba330877d80cb878c7c9f3da81f8eb3bfb07ac7a804d4d58faadfabc3421350b14af6500b931209b75813759509e8642574982d680632106041b2687bdaef1c2be67c63a0ccc427bf02dc1ba58b153f00f8fabc8c00d99934e3e835291b8fc5a0bd62a3059c22cbd6fc4ae403df254e17cb15f32b0b65a4ed1f0f5fff37e49417c5fee339c21bd4f1cdcb1c803df8cb4baa11fa210089b61e28fe42e112824ee705f13167bfd3e6c8d660f2307216f5eea91a6db505b70f38e4b1b72a8f1fa1dd90bf0a47ed9a71e2e11e4f20240c1dac370f2b18075c6537bb475897fdd90fae360afb0b6b02210c123a3ca8fae31e320639b5e00c91c821abe873c1aaee2c4ebb87b5ecc670bff65de191e1d8463ecf1367685c0f0d39efc20269e516f29b775060a0c7a1595e158e94f64407d8e22b16ff25ed00cd50c46fb95ab5a3ca60a04e222c83d26b11c08a678348c8cdad407a0d841c0a50e91e896cb4ff873e9fc81c35f4a146f25a64b294c07c6adf4e418a3f590f061a89267deea53d985b4576b70b95170970321e07c1397b6f7e1ed3d4629e8e0a81019a7c15ae6d252e856c761664862d7fd0620fbbc7020fcd675fc97dc7310420b0d428093b4a80012f7a46612161ee2eeec8996128876d093f71f954244004aded4185afeec305d104d3905e54ddfda59ce1f5d56cf078ff10a76b138db90789184149cab60dfb1491943a793e85c332b4b36f448b63e5f099e7beb11d07005a31ed1658251e9f880466e44c54357781c9cdaf17d48534b062de482ad94005cbdcf52d6fea2e70c20ecc62a339afbb971e455e38292b78b21393bce982ec06d3e6f3e27897877007283f5a9d44ae134efc0ff14a5ce2fbe711403b535413073c9769ddc0474d64643bd2d60f58e001717f0538e1cc1e6b211c5f06f6ddfa029ec10d7e949673c08cd71713728ff042948c5b75ea2b610f4b4db838696fa40e24d750aa75f910948af39de2eba2ff6864daf92004453e4fa5cdae2f553a460f9bc86073dd7d6d2ea0f31092d28110892d6077dcee3b6293e66867a7ae67c5048df756d253c768debf3989d7643ff8892b7f6f74bf2b36d01bd0b88760c9b30eb54d02aa5498e7b87e2d027ac2a449318deda9cabf356fcd07f4561e6370db09fbd0d081093d93a569f6968b291fb01995415293b7cd997d9367c352c75b230ee70de92efd35302572e9de2809cd8e25eb9f824cb559107a1b87dd8c365de106cc800350e1284d67555b8db9041dfd44b3d5bec42e2b186b6a72b5bc3df0470476c234277592f742c3bf3babd4759b115196117883434af1d014ba8aeca028054fb4e8d9dde6a0e4cca9388a9f4c6283cbf9ae89dd17e4dc2a2f511604b33f0ee6e42e4f9a20b556ebc66e18b6142bfdcd4bf3792d9659ca5f5ef041f5ba28053e752155263ea79bd65dd94157f9464625e6a2e1b1e6d8ff40b4af4cb3804606239b8370d69d14f5c9eb463f49b0e796efdf01ddc087cc710bae43968856000fdc3a6408861363a190edbf2ae3e13d2bc52f87d9f2e31c044698b79f37b75a03199c7585b880df73a362ff40a94ed1d6173998d247a8f74e28407ad933e8bb0cd91ee82b723b3f22152a53b3226e52b32b93b397b5b2c386d3468651e602360eba66e52a1844aac9bbbe1a7183e97fed0c8b4b649f1551bcb98248dd62ebec087d4020a042085d487c64ae3fcf25e11b443ff03eeb8345b6d45d5304fbba83030bce1bbe499a7f5aefff31fef134b3c2c85b2fd16e6a26133cdbf05069299f007e627036d5ce0536e10546ec3b0719c373e00792f45fa78ff62d543e204d9a0f54a2b1c934a1463620a5c789ed600792ace37bc0f79c84399018acd073e86309f16a4ee382baad3e98425be3dcea1fceb47e56d237a49a125a360f7eb56b0305632f3877c17e62204e5a2c2017a934be9e532c5d7fd14ed71c4a2d3947621d03373796d7ffd6c77a73a06e3cbb61e1d872fb012c9ea0327fb65c4ffa46f02507d4db98bd434a7e921130e8846e697da226cc85568aa83f95cdfc4ccbfbff8ab0653000211ee7438364596b53793f2dfc4705f6a491190b35960f9aec1ffaad8a";
+
     #[test]
     fn deserialize_transaction_prefix() {
         let hex = hex::decode("01f18d0601ffb58d0605efefead70202eb72f82bd8bdda51e0bdc25f04e99ffb90c6214e11b455abca7b116c7857738880e497d01202e87c65a22b78f4b7686ef3a30113674659a4fe769a7ded73d60e6f7c556a19858090dfc04a022ee52dca8845438995eb6d7af985ca07186cc34a7eb696937f78fc0fd9008e2280c0f9decfae0102cec392ffdcae05a370dc3c447465798d3688677f4a5937f1fef9661df99ac2fb80c0caf384a30202e2b6ce11475c2312d2de5c9f26fbd88b7fcac0dbbb7b31f49abe9bd631ed49e42b0104d46cf1a204ae727c14473d67ea95da3e97b250f3c63e0997198bfc812d7a81020800000000d8111b25").unwrap();
@@ -763,7 +1826,7 @@ mod tests {
 
     #[test]
     fn transaction_hash() {
-        let hex = hex::decode("02000202000bc6aa98049bf603fcec06bd3ccbad04e807e328b5128f22a63bfb27b6e287e8d594664d5cddd6c89bc413d1bc607b242203a6eb3180041ff5ae679702000b90e4eb028298a101879110f5bc0383ad03cbfc03a750e52ace37d112c6064faf7d16e2d07c4cc979dccb858aa9b24e12479e4a2db8350a906ba7a1aec409020002ab6d783607d8e712bbd5aad54a412aec890fcdcc1b35bf0ca4a705c2159bfc32000262f4016d5d81ade9e555807a24d23d452f08b6400683da599abd7134fb75324a2c0209016631a2dee1d0f51f015fd9bf938cf132790bdc5c528037e347828c539e82da6e5921e3d1e6052cb25804d0b7ba81018a4cd5385ca23ff4f6d76dc41b5254abf579b1856d3fbd04e81ff97c113e318bf7e158fbb0db7adc6ece9c8d4ab94e91f68e9607667a858ddf3e6890b2835403db6dcc5a1c179a768bcf74d74ace86430176b0056de37f310884e8eed56ee86840f23f842f1db52945b2feef98f4b56b3d4407734e4e8d3b117b5fd78f0d94f6059b495f53cf855b3716bbe8614d51727556c8b2e5c303cffc694257a1e91372de2047c4e12381c1de8df46102cdd84a24692f68ada05d1ffc5122b655582c6307141e130a6963198085ddb67d304b0ddde87e62402a3cadcd07a315604607ddf1530bd85685e910aa879733549bde0d019edc36326d33edd6ccecc800395b7075e4959779bde803dd787c24bc25d40205071b180152dded8b0be1f48a6d6f8e97c3f934f866b1b697f73f73fc9f38c5d2082c610732c79b2f69f403f7e2d312399739dd8d4225a2914a3020bd88c362271df633e8387b5345b50f11c4f148f76c0c24ca5843580a02fe72d18f47dcf8d601e28bbef2cd6659e620179adad4dfa5a0c7b712d716c4e630fe40bbfc6184f76c401db4b801a7001f65a9c11053db919099f9a1a4fe575c6d783e041ee08222a46adb8a1f13b863d95da277b71ccdefbb32f713a13b5ac8d041bbbeed9df4ace5a6b730b871ad2fe14141dbb9c816a21fd7fc48cfba4d2cc3e5e5fba29f581c1507a6a36285a30344790b74d2212dd26178395cd96a18518ead5c59a410baf6ca0b9217865fff207d757bb465fdb053e8c80b2ec1a966ccc01f49096fb991b65cc160a5070532c47318720fb9a90f187ce53661b6ce1e29d6ccd2b131324101170bb87ef273f0e73d762e159264f0839c6b3b31f5264499bdeb029c66a7035108a84256aec2760e74c2e8e788b7747084da8953aa48696b7a46e6320a9534d6c06ddee1b26671f03ae70a30c76b8fbf268da16fbb685f1d3f602668afce2e3eaf089b8758069f398eaeccd01876cef623201dc46dd75f76dc2141a9a2071b2761eefbf735ca599ed15266acbd0f54f49de38b5c7b3b378c386767383396645778021b30910e6e06937d65dac82312968d4f63a0bb28c96b9a09ee1d95c4d3afbb021998a9e290647b51083a80d66e0a8900a89c1236214f2d7c0080e2c18cdc8c0b4bd66765c3c52a26b90ec549bc8358ff8aaac9ffaecb0f6c915113cc97147b06c007a1a6bcd536bddec7fba330877d80cb878c7c9f3da81f8eb3bfb07ac7a804d4d58faadfabc3421350b14af6500b931209b75813759509e8642574982d680632106041b2687bdaef1c2be67c63a0ccc427bf02dc1ba58b153f00f8fabc8c00d99934e3e835291b8fc5a0bd62a3059c22cbd6fc4ae403df254e17cb15f32b0b65a4ed1f0f5fff37e49417c5fee339c21bd4f1cdcb1c803df8cb4baa11fa210089b61e28fe42e112824ee705f13167bfd3e6c8d660f2307216f5eea91a6db505b70f38e4b1b72a8f1fa1dd90bf0a47ed9a71e2e11e4f20240c1dac370f2b18075c6537bb475897fdd90fae360afb0b6b02210c123a3ca8fae31e320639b5e00c91c821abe873c1aaee2c4ebb87b5ecc670bff65de191e1d8463ecf1367685c0f0d39efc20269e516f29b775060a0c7a1595e158e94f64407d8e22b16ff25ed00cd50c46fb95ab5a3ca60a04e222c83d26b11c08a678348c8cdad407a0d841c0a50e91e896cb4ff873e9fc81c35f4a146f25a64b294c07c6adf4e418a3f590f061a89267deea53d985b4576b70b95170970321e07c1397b6f7e1ed3d4629e8e0a81019a7c15ae6d252e856c761664862d7fd0620fbbc7020fcd675fc97dc7310420b0d428093b4a80012f7a46612161ee2eeec8996128876d093f71f954244004aded4185afeec305d104d3905e54ddfda59ce1f5d56cf078ff10a76b138db90789184149cab60dfb1491943a793e85c332b4b36f448b63e5f099e7beb11d07005a31ed1658251e9f880466e44c54357781c9cdaf17d48534b062de482ad94005cbdcf52d6fea2e70c20ecc62a339afbb971e455e38292b78b21393bce982ec06d3e6f3e27897877007283f5a9d44ae134efc0ff14a5ce2fbe711403b535413073c9769ddc0474d64643bd2d60f58e001717f0538e1cc1e6b211c5f06f6ddfa029ec10d7e949673c08cd71713728ff042948c5b75ea2b610f4b4db838696fa40e24d750aa75f910948af39de2eba2ff6864daf92004453e4fa5cdae2f553a460f9bc86073dd7d6d2ea0f31092d28110892d6077dcee3b6293e66867a7ae67c5048df756d253c768debf3989d7643ff8892b7f6f74bf2b36d01bd0b88760c9b30eb54d02aa5498e7b87e2d027ac2a449318deda9cabf356fcd07f4561e6370db09fbd0d081093d93a569f6968b291fb01995415293b7cd997d9367c352c75b230ee70de92efd35302572e9de2809cd8e25eb9f824cb559107a1b87dd8c365de106cc800350e1284d67555b8db9041dfd44b3d5bec42e2b186b6a72b5bc3df0470476c234277592f742c3bf3babd4759b115196117883434af1d014ba8aeca028054fb4e8d9dde6a0e4cca9388a9f4c6283cbf9ae89dd17e4dc2a2f511604b33f0ee6e42e4f9a20b556ebc66e18b6142bfdcd4bf3792d9659ca5f5ef041f5ba28053e752155263ea79bd65dd94157f9464625e6a2e1b1e6d8ff40b4af4cb3804606239b8370d69d14f5c9eb463f49b0e796efdf01ddc087cc710bae43968856000fdc3a6408861363a190edbf2ae3e13d2bc52f87d9f2e31c044698b79f37b75a03199c7585b880df73a362ff40a94ed1d6173998d247a8f74e28407ad933e8bb0cd91ee82b723b3f22152a53b3226e52b32b93b397b5b2c386d3468651e602360eba66e52a1844aac9bbbe1a7183e97fed0c8b4b649f1551bcb98248dd62ebec087d4020a042085d487c64ae3fcf25e11b443ff03eeb8345b6d45d5304fbba83030bce1bbe499a7f5aefff31fef134b3c2c85b2fd16e6a26133cdbf05069299f007e627036d5ce0536e10546ec3b0719c373e00792f45fa78ff62d543e204d9a0f54a2b1c934a1463620a5c789ed600792ace37bc0f79c84399018acd073e86309f16a4ee382baad3e98425be3dcea1fceb47e56d237a49a125a360f7eb56b0305632f3877c17e62204e5a2c2017a934be9e532c5d7fd14ed71c4a2d3947621d03373796d7ffd6c77a73a06e3cbb61e1d872fb012c9ea0327fb65c4ffa46f02507d4db98bd434a7e921130e8846e697da226cc85568aa83f95cdfc4ccbfbff8ab0653000211ee7438364596b53793f2dfc4705f6a491190b35960f9aec1ffaad8a").unwrap();
+        let hex = hex::decode(RCT_TX).unwrap();
         let tx = deserialize_partial::<TransactionPrefix>(&hex[..]);
         assert_eq!(true, tx.is_ok());
         let tx = tx.unwrap().0;
@@ -782,6 +1845,211 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pruned_transaction() {
+        let hex = hex::decode(RCT_TX).unwrap();
+        let tx = deserialize::<Transaction>(&hex[..]).unwrap();
+
+        let (pruned, prunable) = tx.prune();
+        assert_eq!(tx.prunable_hash(), Some(pruned.prunable_hash));
+        assert_eq!(Some(tx.hash()), pruned.hash());
+        let blob = serialize(&pruned);
+        assert_eq!(hex, [&blob[..], &prunable[..]].concat());
+
+        let decoded = PrunedTransaction::from_pruned_blob(&blob, pruned.prunable_hash).unwrap();
+        assert_eq!(blob, serialize(&decoded));
+        assert_eq!(Some(tx.hash()), decoded.hash());
+        assert!(PrunedTransaction::from_pruned_blob(&hex, pruned.prunable_hash).is_err());
+
+        let full = decoded.clone().with_prunable(&prunable).unwrap();
+        assert_eq!(hex, serialize(&full));
+        assert!(decoded.clone().with_prunable(&prunable[1..]).is_err());
+
+        let mut wrong = decoded;
+        wrong.prunable_hash = Hash::null_hash();
+        assert!(matches!(
+            wrong.with_prunable(&prunable),
+            Err(encode::Error::Transaction(Error::PrunableHashMismatch))
+        ));
+    }
+
+    #[test]
+    fn check_semantics() {
+        let hex = hex::decode(RCT_TX).unwrap();
+        let tx = deserialize::<Transaction>(&hex[..]).unwrap();
+        assert_eq!(Ok(()), tx.check_semantics(10));
+        assert_eq!(Ok(()), tx.check_semantics(13));
+        assert_eq!(
+            Err(SemanticError::RctTypeNotAllowed {
+                rct_type: RctType::Bulletproof2,
+                hf_version: 8
+            }),
+            tx.check_semantics(8)
+        );
+        assert_eq!(
+            Err(SemanticError::InvalidVersion {
+                version: 2,
+                hf_version: 3
+            }),
+            tx.check_semantics(3)
+        );
+        assert_eq!(
+            Err(SemanticError::RctTypeNotAllowed {
+                rct_type: RctType::Bulletproof2,
+                hf_version: 14
+            }),
+            tx.check_semantics(14)
+        );
+        // Ring size 11 is still accepted at hard fork 15
+        assert_eq!(
+            Err(SemanticError::RctTypeNotAllowed {
+                rct_type: RctType::Bulletproof2,
+                hf_version: 15
+            }),
+            tx.check_semantics(15)
+        );
+        assert_eq!(
+            Err(SemanticError::InvalidRingSize {
+                input: 0,
+                expected: 16,
+                found: 11
+            }),
+            tx.check_semantics(16)
+        );
+        assert_eq!(Ok(()), check_ring_size(0, 11, 15));
+        assert_eq!(Ok(()), check_ring_size(0, 16, 15));
+        assert_eq!(
+            Err(SemanticError::InvalidRingSize {
+                input: 0,
+                expected: 16,
+                found: 12
+            }),
+            check_ring_size(0, 12, 15)
+        );
+
+        let mut unsorted = tx.clone();
+        unsorted.prefix.inputs.swap(0, 1);
+        assert_eq!(
+            Err(SemanticError::UnsortedKeyImages(1)),
+            unsorted.check_semantics(10)
+        );
+
+        let mut duplicate = tx.clone();
+        duplicate.prefix.inputs[1] = duplicate.prefix.inputs[0].clone();
+        assert_eq!(
+            Err(SemanticError::DuplicateKeyImage(1)),
+            duplicate.check_semantics(10)
+        );
+        assert_eq!(
+            Err(SemanticError::DuplicateKeyImage(1)),
+            duplicate.check_semantics(6)
+        );
+
+        let mut torsion = tx.clone();
+        if let TxIn::ToKey { k_image, .. } = &mut torsion.prefix.inputs[0] {
+            // Point of order 2
+            k_image.image = Hash::from_slice(
+                &hex::decode("ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f")
+                    .unwrap(),
+            );
+        }
+        assert_eq!(
+            Err(SemanticError::InvalidKeyImage(0)),
+            torsion.check_semantics(6)
+        );
+
+        let mut same_member = tx.clone();
+        if let TxIn::ToKey { key_offsets, .. } = &mut same_member.prefix.inputs[1] {
+            key_offsets[3] = VarInt(0);
+        }
+        assert_eq!(
+            Err(SemanticError::DuplicateRingMember(1)),
+            same_member.check_semantics(10)
+        );
+
+        let mut amount = tx.clone();
+        amount.prefix.outputs[1].amount = VarInt(1);
+        assert_eq!(
+            Err(SemanticError::NonZeroOutputAmount(1)),
+            amount.check_semantics(10)
+        );
+
+        let mut single_output = tx.clone();
+        single_output.prefix.outputs.pop();
+        assert_eq!(
+            Err(SemanticError::LengthMismatch {
+                field: "ecdh_info",
+                expected: 1,
+                found: 2
+            }),
+            single_output.check_semantics(10)
+        );
+
+        let mut gen = tx;
+        gen.prefix.inputs[0] = TxIn::Gen { height: VarInt(1) };
+        assert_eq!(
+            Err(SemanticError::InvalidInputType(0)),
+            gen.check_semantics(10)
+        );
+    }
+
+    #[test]
+    fn key_image_checks() {
+        let key_image = |bytes: &str| KeyImage {
+            image: Hash::from_slice(&hex::decode(bytes).unwrap()),
+        };
+
+        let identity =
+            key_image("0100000000000000000000000000000000000000000000000000000000000000");
+        assert!(identity.is_identity());
+        assert!(identity.is_torsion_free());
+
+        // Point of order 2
+        let torsion = key_image("ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        assert!(torsion.decompress().is_some());
+        assert!(!torsion.is_torsion_free());
+        assert!(!torsion.is_identity());
+
+        let invalid = key_image("0200000000000000000000000000000000000000000000000000000000000000");
+        assert!(invalid.decompress().is_none());
+        assert!(!invalid.is_torsion_free());
+
+        let hex = hex::decode(RCT_TX).unwrap();
+        let tx = deserialize::<Transaction>(&hex[..]).unwrap();
+        assert!(tx
+            .prefix
+            .key_images()
+            .all(|key_image| key_image.is_torsion_free() && !key_image.is_identity()));
+        assert_eq!(None, find_double_spend(tx.prefix.key_images()));
+
+        let spent = tx.prefix.key_images().nth(1).unwrap();
+        let double_spend = find_double_spend(
+            tx.prefix
+                .key_images()
+                .chain(std::iter::once(&torsion))
+                .chain(std::iter::once(spent)),
+        );
+        assert_eq!(Some(spent), double_spend);
+    }
+
+    #[test]
+    fn key_image_generation() {
+        // `generate_key_image` vector of monero `tests/crypto/tests.txt`
+        let secret = PrivateKey::from_slice(
+            &hex::decode("981d477fb18897fa1f784c89721a9d600bf283f06b89cb018a077f41dcefef0f")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            "e46b60ebfe610b8ba761032018471e5719bb77ea1cd945475c4a4abe7224bfd0",
+            PublicKey::from_private_key(&secret).to_string()
+        );
+        assert_eq!(
+            "a637203ec41eab772532d30420eac80612fce8e44f1758bc7e2cb1bdda815887",
+            format!("{:x}", KeyImage::new(&secret).image)
+        );
+    }
+
     #[test]
     fn find_outputs() {
         let view = PrivateKey::from_str(
@@ -815,6 +2083,206 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extra_nonce_payment_id() {
+        let view = PrivateKey::from_str(
+            "77916d0cd56ed1920aef6ca56d8a41bac915b68e4c46a589e0956e27a7b77404",
+        )
+        .unwrap();
+        let tx_secret = Hash::hash(b"tx secret key").as_scalar();
+        let tx_pubkey = PublicKey::from_private_key(&tx_secret);
+        let payment_id = PaymentId([88, 118, 184, 183, 41, 150, 255, 151]);
+
+        let encrypted = payment_id.encrypt(&PublicKey::from_private_key(&view), &tx_secret);
+        assert_ne!(payment_id, encrypted);
+        assert_eq!(payment_id, encrypted.decrypt(&tx_pubkey, &view));
+
+        let nonce = ExtraNonce::EncryptedPaymentId(encrypted);
+        assert_eq!(9, nonce.to_nonce().len());
+        assert_eq!(Some(nonce), ExtraNonce::from_nonce(&nonce.to_nonce()));
+        let extra = ExtraField(vec![SubField::TxPublicKey(tx_pubkey), nonce.into()]);
+        let extra: ExtraField = deserialize(&serialize(&extra)).unwrap();
+        assert_eq!(Some(nonce), extra.extra_nonce());
+        assert_eq!(Some(payment_id), extra.decrypted_payment_id(&view));
+
+        let nonce = ExtraNonce::PaymentId(Hash::hash(b"payment id"));
+        assert_eq!(Some(nonce), ExtraNonce::from_nonce(&nonce.to_nonce()));
+        let extra = ExtraField(vec![SubField::TxPublicKey(tx_pubkey), nonce.into()]);
+        assert_eq!(Some(nonce), extra.extra_nonce());
+        assert_eq!(None, extra.decrypted_payment_id(&view));
+
+        // Arbitrary nonces, e.g. from miners, are not payment ids
+        assert_eq!(
+            None,
+            ExtraNonce::from_nonce(&[0, 0, 0, 0, 0xd8, 0x11, 0x1b, 0x25])
+        );
+        assert_eq!(None, ExtraNonce::from_nonce(&[0x1, 0, 0]));
+        assert_eq!(None, ExtraNonce::from_nonce(&[]));
+    }
+
+    #[test]
+    fn lenient_extra() {
+        let pubkey = PublicKey::from_private_key(&Hash::hash(b"tx secret key").as_scalar());
+        let mut bytes = serialize(&SubField::TxPublicKey(pubkey));
+        bytes.extend_from_slice(&[0xff, 0x01, 0x02, 0x03]);
+        assert!(ExtraField::parse(&bytes).is_err());
+        let extra = ExtraField::parse_lenient(&bytes);
+        assert_eq!(
+            vec![
+                SubField::TxPublicKey(pubkey),
+                SubField::Unknown(vec![0xff, 0x01, 0x02, 0x03])
+            ],
+            extra.0
+        );
+        assert_eq!(serialize(&bytes), serialize(&extra));
+
+        let prefix = TransactionPrefix {
+            version: VarInt(2),
+            extra,
+            ..Default::default()
+        };
+        let prefix: TransactionPrefix = deserialize(&serialize(&prefix)).unwrap();
+        assert_eq!(Some(pubkey), prefix.extra.tx_pubkey());
+        assert_eq!(serialize(&bytes), serialize(&prefix.extra));
+
+        let mut nonce = vec![0x02, 0x80, 0x02];
+        nonce.extend_from_slice(&[0; 256]);
+        let invalid: Vec<Vec<u8>> = vec![
+            // Non canonical VarInt length
+            vec![0x02, 0x80, 0x00],
+            // Nonce larger than 255 bytes
+            nonce,
+            // Non-zero padding
+            vec![0x00, 0x00, 0x01],
+            // Padding larger than 255 bytes
+            [&[0x00][..], &[0x00; 255][..]].concat(),
+            // Truncated public key
+            vec![0x01, 0x00, 0x00],
+        ];
+        for bytes in invalid {
+            assert!(ExtraField::parse(&bytes).is_err());
+            let extra = ExtraField::parse_lenient(&bytes);
+            assert_eq!(vec![SubField::Unknown(bytes.clone())], extra.0);
+            assert_eq!(serialize(&bytes), serialize(&extra));
+        }
+
+        let bytes = [&[0x00][..], &[0x00; 254][..]].concat();
+        assert_eq!(
+            vec![SubField::Padding(254)],
+            ExtraField::parse(&bytes).unwrap().0
+        );
+    }
+
+    #[test]
+    fn sort_extra() {
+        let pubkey = PublicKey::from_private_key(&Hash::hash(b"tx secret key").as_scalar());
+        let mut extra = ExtraField(vec![
+            SubField::Unknown(vec![0xff]),
+            SubField::Padding(2),
+            SubField::Nonce(vec![1]),
+            SubField::AdditionalPublickKey(vec![pubkey]),
+            SubField::Nonce(vec![2]),
+            SubField::TxPublicKey(pubkey),
+        ]);
+        extra.sort();
+        assert_eq!(
+            vec![
+                SubField::TxPublicKey(pubkey),
+                SubField::AdditionalPublickKey(vec![pubkey]),
+                SubField::Nonce(vec![1]),
+                SubField::Nonce(vec![2]),
+                SubField::Padding(2),
+                SubField::Unknown(vec![0xff]),
+            ],
+            extra.0
+        );
+    }
+
+    #[test]
+    fn build_extra() {
+        let pubkey = PublicKey::from_private_key(&Hash::hash(b"tx secret key").as_scalar());
+        let nonce = ExtraNonce::EncryptedPaymentId(PaymentId([1; 8]));
+        let builder = ExtraFieldBuilder::new()
+            .field(SubField::Padding(3))
+            .field(nonce.into())
+            .field(SubField::AdditionalPublickKey(vec![pubkey, pubkey]))
+            .field(SubField::TxPublicKey(pubkey));
+        let bytes = builder.to_bytes().unwrap();
+        assert_eq!(
+            [
+                &[0x01][..],
+                pubkey.as_bytes(),
+                &[0x04, 0x02],
+                pubkey.as_bytes(),
+                pubkey.as_bytes(),
+                &[0x02, 0x09],
+                &nonce.to_nonce(),
+                &[0x00, 0x00, 0x00, 0x00],
+            ]
+            .concat(),
+            bytes
+        );
+        assert_eq!(
+            builder.build().unwrap().0,
+            ExtraField::parse(&bytes).unwrap().0
+        );
+        assert_eq!(Ok(vec![]), ExtraFieldBuilder::new().to_bytes());
+
+        let builder = ExtraFieldBuilder::new()
+            .field(SubField::TxPublicKey(pubkey))
+            .field(SubField::TxPublicKey(pubkey));
+        assert_eq!(
+            Err(ExtraFieldError::DuplicateTxPublicKey),
+            builder.to_bytes()
+        );
+        let builder = ExtraFieldBuilder::new().field(SubField::Nonce(vec![0; 256]));
+        assert_eq!(Err(ExtraFieldError::NonceTooLarge(256)), builder.to_bytes());
+        let builder = ExtraFieldBuilder::new().field(SubField::Padding(255));
+        assert_eq!(
+            Err(ExtraFieldError::PaddingTooLarge(256)),
+            builder.to_bytes()
+        );
+        let builder = ExtraFieldBuilder::new()
+            .field(SubField::Padding(1))
+            .field(SubField::Padding(1));
+        assert_eq!(Err(ExtraFieldError::PaddingNotLast), builder.to_bytes());
+        let builder = ExtraFieldBuilder::new()
+            .field(SubField::Unknown(vec![0xff]))
+            .field(SubField::Padding(1));
+        assert_eq!(Err(ExtraFieldError::PaddingNotLast), builder.to_bytes());
+    }
+
+    #[test]
+    fn output_amounts() {
+        let view = PrivateKey::from_str(
+            "bcfdda53205318e1c14fa0ddca1a45df363bb427972981d0249d0f4652a7df07",
+        )
+        .unwrap();
+        let spend = PrivateKey::from_str(
+            "e5f4301d32f3bdaef814a835a18aaaa24b13cc76cf01a832a7852faf9322e907",
+        )
+        .unwrap();
+        let viewpair = ViewPair {
+            view,
+            spend: PublicKey::from_private_key(&spend),
+        };
+
+        let raw_tx = hex::decode("02000102000bb2e38c0189ea01a9bc02a533fe02a90705fd0540745f59f49374365304f8b4d5da63b444b2d74a40f8007ea44940c15cbbc80c9d106802000267f0f669ead579c1067cbffdf67c4af80b0287c549a10463122b4860fe215f490002b6a2e2f35a93d637ff7d25e20da326cee8e92005d3b18b3c425dabe8336568992c01d6c75cf8c76ac458123f2a498512eb65bb3cecba346c8fcfc516dc0c88518bb90209016f82359eb1fe71d604f0dce9470ed5fd4624bb9fce349a0e8317eabf4172f78a8b27dec6ea1a46da10ed8620fa8367c6391eaa8aabf4ebf660d9fe0eb7e9dfa08365a089ad2df7bce7ef776467898d5ca8947152923c54a1c5030e0c2f01035c555ff4285dcc44dfadd6bc37ec8b9354c045c6590446a81c7f53d8f199cace3faa7f17b3b8302a7cbb3881e8fdc23cca0275c9245fdc2a394b8d3ae73911e3541b10e7725cdeef5e0307bc218caefaafe97c102f39c8ce78f62cccf23c69baf0af55933c9d384ceaf07488f2f1ac7343a593449afd54d1065f6a1a4658845817e4b0e810afc4ca249096e463f9f368625fa37d5bbcbe87af68ce3c4d630f93a66defa4205b178f4e9fa04107bd535c7a4b2251df2dad255e470b611ffe00078c2916fc1eb2af1273e0df30dd1c74b6987b9885e7916b6ca711cbd4b7b50576e51af1439e9ed9e33eb97d8faba4e3bd46066a5026a1940b852d965c1db455d1401687ccaccc524e000b05966763564b7deb8fd64c7fb3d649897c94583dca1558893b071f5e6700dad139f3c6f973c7a43b207ee3e67dc7f7f18b52df442258200c7fe6d16685127da1df9b0d93d764c2659599bc6d300ae33bf8b7c2a504317da90ea2f0bb2af09bd531feae57cb4a0273d8add62fadfc6d43402372e5caf854e112b88417936f1a9c4045d48b5b0b7703d96801b35ff66c716cddbee1b92407aa069a162c163071710e28ccddf6fb560feea32485f2c54a477ae23fd8210427eabe4288cbe0ecbef4ed19ca049ceded424d9f839da957f56ffeb73060ea15498fcbc2d73606e85e963a667dafdb2641fb91862c07b98c1fdae8fadf514600225036dd63c22cdadb57d2125ebf30bc77f7ea0bc0dafb484bf01434954c5053b9c8a143f06972f80fa66788ea1e3425dc0104a9e3674729967b9819552ebb172418da0e4b3778ad4b3d6acd8f354ba09e54bbc8604540010e1e1e4d3066515aed457bd3399c0ce787236dbcd3923de4fb8faded10199b33c1251191612ab5526c1cf0cd55a0aeaed3f7a955ceced16dabdbeb0a2a19a9fdb5aa8c4fc8767cf70e4ad1838518bc6b9de7c420c1f57636579a14a5a8bdacd24e61a68adede8a2e07416c25409dd91ab78905bc99bab4ab4fb9e4ea628e09a271837769c4e67e580dcd5485e12e4e308cb4509686a7484a71f7dfe334499808c7122f07d45d89230b1f19ed86f675b7fec44ef5f3b178ae0af92ff114bd96baa264604fea5a762307bdce6cb483b7bc780d32ed5343fcc3aa306997f211dc075f6dfd66035c1db10bef8656fefbb45645264d401682e42fe3e05906f79d65481b87508f1a4c434e0d1dfc247d4276306f801a6b57e4e4a525177bae24e0bd88a216597d9db44f2604c29d8a5f74e7b934f55048690b5dcefd6489a81aa64c1edb49b320faab94130e603d99e455cfd828bca782176192ece95e9b967fe3dd698574cf0c0b6926970b156e1134658de657de42c4930e72b49c0d94da66c330ab188c10f0d2f578590f31bcac6fcff7e21f9ff67ae1a40d5a03b19301dcbbadc1aa9392795cf81f1401ec16d986a7f96fbb9e8e12ce04a2226e26b78117a4dfb757c6a44481ff68bb0909e7010988cd37146fb45d4cca4ba490aae323bb51a12b6864f88ea6897aa700ee9142eaf0880844083026f044a5e3dba4aae08578cb057976001beb27b5110c41fe336bf7879733739ce22fb31a1a6ac2c900d6d6c6facdbc60085e5c93d502542cfea90dbc62d4e061b7106f09f9c4f6c1b5506dd0550eb8b2bf17678b140de33a10ba676829092e6a13445d1857d06c715eea4492ff864f0b34d178a75a0f1353078f83cfee1440b0a20e64abbd0cab5c6e7083486002970a4904f8371805d1a0ee4aea8524168f0f39d2dfc55f545a98a031841a740e8422a62e123c8303021fb81afbb76d1120c0fbc4d3d97ba69f4e2fe086822ece2047c9ccea507008654c199238a5d17f009aa2dd081f7901d0688aa15311865a319ccba8de4023027235b5725353561c5f1185f6a063fb32fc65ef6e90339d406a6884d66be49d03daaf116ee4b65ef80dd3052a13157b929f98640c0bbe99c8323ce3419a136403dc3f7a95178c3966d2d7bdecf516a28eb2cf8cddb3a0463dc7a6248883f7be0a10aae1bb50728ec9b8880d6011b366a850798f6d7fe07103695dded3f371ca097c1d3596967320071d7f548938afe287cb9b8fae761fa592425623dcbf653028").unwrap();
+        let tx = deserialize::<Transaction>(&raw_tx).unwrap();
+        let owned = tx.prefix.check_outputs(&viewpair, 0..2, 0..3).unwrap();
+        assert_eq!(1, owned.len());
+        // The derivation only depends on the view key and the output transaction public key
+        let shared_key =
+            KeyGenerator::from_key(&viewpair, owned[0].tx_pubkey).get_rvn_scalar(owned[0].index);
+        assert_eq!(
+            Some(7_000_000_000),
+            tx.output_amount(owned[0].index, &shared_key)
+        );
+        // A wrong shared key does not open the commitments
+        assert_eq!(None, tx.output_amount(0, &view));
+        assert_eq!(None, tx.output_amount(2, &view));
+    }
+
     #[test]
     fn test_tx_hash() {
         let tx = "f8ad7c58e6fce1792dd78d764ce88a11db0e3c3bb484d868ae05a7321fb6c6b0";
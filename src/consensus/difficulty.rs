@@ -0,0 +1,278 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Difficulty calculation
+//!
+//! Support for computing the difficulty of the next block from the timestamps and cumulative
+//! difficulties of the previous blocks, as done by monerod.
+//!
+//! ```rust
+//! use monero::consensus::difficulty::{next_difficulty, DIFFICULTY_TARGET_V2};
+//!
+//! // Blocks found exactly every two minutes at a constant difficulty
+//! let timestamps: Vec<u64> = (0..735).map(|i| i * DIFFICULTY_TARGET_V2).collect();
+//! let cumulative_difficulties: Vec<u128> = (1..=735).map(|i| i * 1_000).collect();
+//!
+//! let difficulty = next_difficulty(&timestamps, &cumulative_difficulties, DIFFICULTY_TARGET_V2);
+//! assert_eq!(Ok(1_000), difficulty);
+//! ```
+//!
+
+use thiserror::Error;
+
+/// Target time between two blocks in seconds before the second hard fork
+pub const DIFFICULTY_TARGET_V1: u64 = 60;
+/// Target time between two blocks in seconds since the second hard fork
+pub const DIFFICULTY_TARGET_V2: u64 = 120;
+/// Number of blocks used to compute the difficulty
+pub const DIFFICULTY_WINDOW: usize = 720;
+/// Number of most recent blocks ignored when computing the difficulty
+pub const DIFFICULTY_LAG: usize = 15;
+/// Number of outlier blocks removed on each side of the sorted window
+pub const DIFFICULTY_CUT: usize = 60;
+/// Number of blocks to pass to [`next_difficulty`], i.e. the window and the lag
+pub const DIFFICULTY_BLOCKS_COUNT: usize = DIFFICULTY_WINDOW + DIFFICULTY_LAG;
+
+/// Possible errors when computing the difficulty
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// Timestamps and cumulative difficulties have different lengths
+    #[error("{timestamps} timestamps given for {difficulties} cumulative difficulties")]
+    LengthMismatch {
+        /// Number of timestamps
+        timestamps: usize,
+        /// Number of cumulative difficulties
+        difficulties: usize,
+    },
+    /// More blocks than `DIFFICULTY_BLOCKS_COUNT` are given
+    #[error("too many blocks: {0}")]
+    TooManyBlocks(usize),
+    /// Cumulative difficulties are not increasing
+    #[error("cumulative difficulties are not increasing")]
+    NonIncreasingDifficulty,
+}
+
+/// Return the target time between two blocks in seconds for the given hard fork version
+pub fn difficulty_target(hf_version: u8) -> u64 {
+    if hf_version < 2 {
+        DIFFICULTY_TARGET_V1
+    } else {
+        DIFFICULTY_TARGET_V2
+    }
+}
+
+/// Compute the difficulty of the next block given the timestamps and the cumulative difficulties
+/// of at most the last [`DIFFICULTY_BLOCKS_COUNT`] blocks, ordered by height
+///
+/// The most recent [`DIFFICULTY_LAG`] blocks are ignored, then [`DIFFICULTY_CUT`] outliers are
+/// removed on each side of the sorted timestamps window. Timestamps can be given directly as
+/// `BlockHeader::timestamp` values. A difficulty of 0 is returned if the result does not fit in
+/// 128 bits, as monerod does.
+///
+/// **Same as** [`next_difficulty`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/difficulty.cpp#L213-L248)
+pub fn next_difficulty<T>(
+    timestamps: &[T],
+    cumulative_difficulties: &[u128],
+    target_seconds: u64,
+) -> Result<u128, Error>
+where
+    T: Clone + Into<u64>,
+{
+    if timestamps.len() != cumulative_difficulties.len() {
+        return Err(Error::LengthMismatch {
+            timestamps: timestamps.len(),
+            difficulties: cumulative_difficulties.len(),
+        });
+    }
+    if timestamps.len() > DIFFICULTY_BLOCKS_COUNT {
+        return Err(Error::TooManyBlocks(timestamps.len()));
+    }
+
+    // Ignore the lag, only the oldest blocks of the window are kept
+    let length = timestamps.len().min(DIFFICULTY_WINDOW);
+    if length <= 1 {
+        return Ok(1);
+    }
+    let mut timestamps: Vec<u64> = timestamps[..length]
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect();
+    let cumulative_difficulties = &cumulative_difficulties[..length];
+    timestamps.sort_unstable();
+
+    let (cut_begin, cut_end) = if length <= DIFFICULTY_WINDOW - 2 * DIFFICULTY_CUT {
+        (0, length)
+    } else {
+        let cut_begin = (length - (DIFFICULTY_WINDOW - 2 * DIFFICULTY_CUT)).div_ceil(2);
+        (
+            cut_begin,
+            cut_begin + (DIFFICULTY_WINDOW - 2 * DIFFICULTY_CUT),
+        )
+    };
+
+    let time_span = match timestamps[cut_end - 1] - timestamps[cut_begin] {
+        0 => 1,
+        span => span,
+    };
+    let total_work = cumulative_difficulties[cut_end - 1]
+        .checked_sub(cumulative_difficulties[cut_begin])
+        .filter(|work| *work > 0)
+        .ok_or(Error::NonIncreasingDifficulty)?;
+
+    Ok(mul_div_ceil(total_work, target_seconds, time_span).unwrap_or(0))
+}
+
+/// Compute `ceil(a * b / c)` with 192 bits of intermediate precision, return `None` if the result
+/// does not fit in 128 bits
+fn mul_div_ceil(a: u128, b: u64, c: u64) -> Option<u128> {
+    const LOW: u128 = u64::MAX as u128;
+    let (b, c) = (b as u128, c as u128);
+
+    // a * b as three 64 bits limbs, most significant first
+    let low = (a & LOW) * b;
+    let high = (a >> 64) * b + (low >> 64);
+    let mut limbs = [high >> 64, high & LOW, low & LOW];
+
+    // Add c - 1 to round the division up
+    let mut carry = c - 1;
+    for limb in limbs.iter_mut().rev() {
+        let sum = *limb + carry;
+        *limb = sum & LOW;
+        carry = sum >> 64;
+    }
+
+    // Long division by c
+    let mut remainder = 0u128;
+    let mut quotient = [0u128; 3];
+    for (q, limb) in quotient.iter_mut().zip(limbs.iter()) {
+        let current = (remainder << 64) | limb;
+        *q = current / c;
+        remainder = current % c;
+    }
+
+    match quotient {
+        [0, high, low] => Some((high << 64) | low),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        mul_div_ceil, next_difficulty, Error, DIFFICULTY_BLOCKS_COUNT, DIFFICULTY_TARGET_V2,
+    };
+    use crate::consensus::encode::VarInt;
+
+    #[test]
+    fn constant_difficulty() {
+        let timestamps: Vec<VarInt> = (0..DIFFICULTY_BLOCKS_COUNT as u64)
+            .map(|i| VarInt(1_600_000_000 + i * DIFFICULTY_TARGET_V2))
+            .collect();
+        let cumulative_difficulties: Vec<u128> = (1..=DIFFICULTY_BLOCKS_COUNT as u128)
+            .map(|i| i * 250_000_000_000)
+            .collect();
+        assert_eq!(
+            Ok(250_000_000_000),
+            next_difficulty(&timestamps, &cumulative_difficulties, DIFFICULTY_TARGET_V2)
+        );
+    }
+
+    #[test]
+    fn faster_blocks_raise_difficulty() {
+        // Blocks found every minute instead of two during the window
+        let timestamps: Vec<u64> = (0..DIFFICULTY_BLOCKS_COUNT as u64)
+            .map(|i| i * 60)
+            .collect();
+        let cumulative_difficulties: Vec<u128> = (1..=DIFFICULTY_BLOCKS_COUNT as u128)
+            .map(|i| i * 1000)
+            .collect();
+        assert_eq!(
+            Ok(2000),
+            next_difficulty(&timestamps, &cumulative_difficulties, DIFFICULTY_TARGET_V2)
+        );
+    }
+
+    #[test]
+    fn outliers_and_lag_are_ignored() {
+        let mut timestamps: Vec<u64> = (0..DIFFICULTY_BLOCKS_COUNT as u64)
+            .map(|i| i * DIFFICULTY_TARGET_V2)
+            .collect();
+        let cumulative_difficulties: Vec<u128> = (1..=DIFFICULTY_BLOCKS_COUNT as u128)
+            .map(|i| i * 1000)
+            .collect();
+        // Fake timestamps on the edges of the window and in the lag
+        timestamps[0] = 0;
+        timestamps[10] = u64::MAX / 2;
+        timestamps[DIFFICULTY_BLOCKS_COUNT - 1] = 1;
+        assert_eq!(
+            Ok(1000),
+            next_difficulty(&timestamps, &cumulative_difficulties, DIFFICULTY_TARGET_V2)
+        );
+    }
+
+    #[test]
+    fn short_chains() {
+        assert_eq!(
+            Ok(1),
+            next_difficulty::<u64>(&[], &[], DIFFICULTY_TARGET_V2)
+        );
+        assert_eq!(Ok(1), next_difficulty(&[0u64], &[1], DIFFICULTY_TARGET_V2));
+        // Same timestamps, time span is set to 1 second
+        assert_eq!(
+            Ok(120),
+            next_difficulty(&[5u64, 5], &[1, 2], DIFFICULTY_TARGET_V2)
+        );
+        assert_eq!(
+            Ok(1),
+            next_difficulty(&[0u64, 120], &[1, 2], DIFFICULTY_TARGET_V2)
+        );
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        assert_eq!(
+            Err(Error::LengthMismatch {
+                timestamps: 2,
+                difficulties: 1
+            }),
+            next_difficulty(&[0u64, 1], &[1], DIFFICULTY_TARGET_V2)
+        );
+        assert_eq!(
+            Err(Error::TooManyBlocks(DIFFICULTY_BLOCKS_COUNT + 1)),
+            next_difficulty(
+                &vec![0u64; DIFFICULTY_BLOCKS_COUNT + 1],
+                &vec![0; DIFFICULTY_BLOCKS_COUNT + 1],
+                DIFFICULTY_TARGET_V2
+            )
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingDifficulty),
+            next_difficulty(&[0u64, 120], &[2, 1], DIFFICULTY_TARGET_V2)
+        );
+    }
+
+    #[test]
+    fn wide_arithmetic() {
+        assert_eq!(Some(u128::MAX), mul_div_ceil(u128::MAX, 120, 120));
+        assert_eq!(Some(u128::MAX / 2 + 1), mul_div_ceil(u128::MAX, 1, 2));
+        assert_eq!(None, mul_div_ceil(u128::MAX, 120, 1));
+        // The result overflows 128 bits, monerod returns 0
+        assert_eq!(
+            Ok(0),
+            next_difficulty(&[0u64, 1], &[0, u128::MAX], DIFFICULTY_TARGET_V2)
+        );
+    }
+}
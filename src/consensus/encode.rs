@@ -35,6 +35,10 @@ use std::u32;
 use crate::blockdata::transaction;
 use crate::util::{key, ringct};
 
+/// Maximum number of elements preallocated when decoding a vector, larger vectors grow as
+/// elements are decoded so a forged length cannot exhaust memory
+pub(crate) const MAX_PREALLOCATED: u64 = 4096;
+
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
@@ -53,6 +57,12 @@ pub enum Error {
     ByteOrder(io::Error),
     /// Parsing error
     ParseFailed(&'static str),
+    /// A vector has more elements than allowed by the decoding limits
+    ElementLimitExceeded(u64),
+    /// The data is larger than allowed by the decoding limits
+    ByteLimitExceeded,
+    /// Vectors are nested deeper than allowed by the decoding limits
+    DepthLimitExceeded,
 }
 
 #[doc(hidden)]
@@ -96,11 +106,22 @@ where
 
 /// Deserialize an object from a vector, will error if said deserialization
 /// doesn't consume the entire vector.
+///
+/// The data is decoded with the default [`DecodeLimits`].
 pub fn deserialize<'a, T>(data: &'a [u8]) -> Result<T, Error>
 where
-    T: Decodable<Cursor<&'a [u8]>>,
+    T: Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+{
+    deserialize_with_limits(data, DecodeLimits::default())
+}
+
+/// Deserialize an object from a vector with the given limits, will error if said
+/// deserialization doesn't consume the entire vector.
+pub fn deserialize_with_limits<'a, T>(data: &'a [u8], limits: DecodeLimits) -> Result<T, Error>
+where
+    T: Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
 {
-    let (rv, consumed) = deserialize_partial(data)?;
+    let (rv, consumed) = deserialize_partial_with_limits(data, limits)?;
 
     // Fail if data are not consumed entirely.
     if consumed == data.len() {
@@ -114,13 +135,34 @@ where
 
 /// Deserialize an object from a vector, but will not report an error if said deserialization
 /// doesn't consume the entire vector.
+///
+/// The data is decoded with the default [`DecodeLimits`].
 pub fn deserialize_partial<'a, T>(data: &'a [u8]) -> Result<(T, usize), Error>
 where
-    T: Decodable<Cursor<&'a [u8]>>,
+    T: Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+{
+    deserialize_partial_with_limits(data, DecodeLimits::default())
+}
+
+/// Deserialize an object from a vector with the given limits, but will not report an error if
+/// said deserialization doesn't consume the entire vector.
+///
+/// The byte limit is lowered to the size of the vector, so the length of a vector in the data is
+/// never trusted beyond the remaining bytes.
+pub fn deserialize_partial_with_limits<'a, T>(
+    data: &'a [u8],
+    limits: DecodeLimits,
+) -> Result<(T, usize), Error>
+where
+    T: Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
 {
-    let mut decoder = Cursor::new(data);
+    let limits = DecodeLimits {
+        max_bytes: limits.max_bytes.min(data.len() as u64),
+        ..limits
+    };
+    let mut decoder = BoundedDecoder::new(Cursor::new(data), limits);
     let rv = Decodable::consensus_decode(&mut decoder)?;
-    let consumed = decoder.position() as usize;
+    let consumed = decoder.bytes_read() as usize;
 
     Ok((rv, consumed))
 }
@@ -171,6 +213,14 @@ pub trait Decoder {
 
     /// Read a boolean
     fn read_bool(&mut self) -> Result<bool, Error>;
+
+    /// Check the length of a vector before decoding its elements and enter it, the default
+    /// implementation has no limit
+    fn enter_vec(&mut self, _len: u64) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Leave a vector once its elements are decoded
+    fn exit_vec(&mut self) {}
 }
 
 macro_rules! encoder_fn {
@@ -235,6 +285,142 @@ impl<R: Read> Decoder for R {
     }
 }
 
+/// Limits of a [`BoundedDecoder`]
+///
+/// Every element of a vector takes at least one byte, the length of a vector is then checked
+/// against the remaining bytes before decoding it. At most 4096 elements are
+/// allocated ahead of decoding them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of elements of a vector
+    pub max_elements: u64,
+    /// Maximum number of bytes read
+    pub max_bytes: u64,
+    /// Maximum nesting depth of vectors
+    pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+    /// At most 100 MB, the maximum size of a levin packet, and 16M elements in vectors nested up
+    /// to 8 levels deep
+    fn default() -> DecodeLimits {
+        DecodeLimits {
+            max_elements: 1 << 24,
+            max_bytes: 100_000_000,
+            max_depth: 8,
+        }
+    }
+}
+
+/// Decoder enforcing [`DecodeLimits`] on a reader
+///
+/// ```rust
+/// use monero::consensus::encode::{BoundedDecoder, Decodable, DecodeLimits, Error};
+///
+/// // A vector of 2^32 - 1 bytes
+/// let data = [0xff, 0xff, 0xff, 0xff, 0x0f, 0x00];
+/// let mut decoder = BoundedDecoder::new(&data[..], DecodeLimits::default());
+/// let res: Result<Vec<u8>, Error> = Decodable::consensus_decode(&mut decoder);
+/// assert!(matches!(res, Err(Error::ElementLimitExceeded(4294967295))));
+/// ```
+///
+#[derive(Debug)]
+pub struct BoundedDecoder<R> {
+    inner: R,
+    limits: DecodeLimits,
+    bytes_read: u64,
+    depth: usize,
+}
+
+impl<R> BoundedDecoder<R> {
+    /// Create a decoder reading from the reader with the given limits
+    pub fn new(inner: R, limits: DecodeLimits) -> BoundedDecoder<R> {
+        BoundedDecoder {
+            inner,
+            limits,
+            bytes_read: 0,
+            depth: 0,
+        }
+    }
+
+    /// Return the limits of the decoder
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// Return the number of bytes read
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Return the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Check that `len` more bytes can be read within the byte limit
+    fn check_bytes(&self, len: u64) -> Result<(), Error> {
+        if len > self.limits.max_bytes.saturating_sub(self.bytes_read) {
+            return Err(Error::ByteLimitExceeded);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> BoundedDecoder<Cursor<&'a [u8]>> {
+    /// Create a decoder over a slice with the default limits, the byte limit being lowered to the
+    /// size of the slice
+    pub fn from_slice(data: &'a [u8]) -> BoundedDecoder<Cursor<&'a [u8]>> {
+        let limits = DecodeLimits {
+            max_bytes: data.len() as u64,
+            ..DecodeLimits::default()
+        };
+        BoundedDecoder::new(Cursor::new(data), limits)
+    }
+}
+
+macro_rules! bounded_decoder_fn {
+    ($name:ident, $val_type:ty) => {
+        #[inline]
+        fn $name(&mut self) -> Result<$val_type, Error> {
+            let len = std::mem::size_of::<$val_type>() as u64;
+            self.check_bytes(len)?;
+            let value = Decoder::$name(&mut self.inner)?;
+            // Only successful reads are counted
+            self.bytes_read += len;
+            Ok(value)
+        }
+    };
+}
+
+impl<R: Read> Decoder for BoundedDecoder<R> {
+    bounded_decoder_fn!(read_u64, u64);
+    bounded_decoder_fn!(read_u32, u32);
+    bounded_decoder_fn!(read_u16, u16);
+    bounded_decoder_fn!(read_u8, u8);
+    bounded_decoder_fn!(read_i64, i64);
+    bounded_decoder_fn!(read_i32, i32);
+    bounded_decoder_fn!(read_i16, i16);
+    bounded_decoder_fn!(read_i8, i8);
+    bounded_decoder_fn!(read_bool, bool);
+
+    fn enter_vec(&mut self, len: u64) -> Result<(), Error> {
+        if len > self.limits.max_elements {
+            return Err(Error::ElementLimitExceeded(len));
+        }
+        self.check_bytes(len)?;
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::DepthLimitExceeded);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn exit_vec(&mut self) {
+        self.depth -= 1;
+    }
+}
+
 /// Data which can be encoded in a consensus-consistent way
 pub trait Encodable<S: Encoder> {
     /// Encode an object with a well-defined format, should only ever error if
@@ -273,6 +459,12 @@ impl Deref for VarInt {
     }
 }
 
+impl From<VarInt> for u64 {
+    fn from(int: VarInt) -> u64 {
+        int.0
+    }
+}
+
 // Primitive types
 macro_rules! impl_int_encodable {
     ($ty:ident, $meth_dec:ident, $meth_enc:ident) => {
@@ -439,20 +631,26 @@ impl<D: Decoder, T: Decodable<D>> Decodable<D> for Vec<T> {
     #[inline]
     fn consensus_decode(d: &mut D) -> Result<Vec<T>, self::Error> {
         let len = VarInt::consensus_decode(d)?.0;
-        let mut ret = Vec::with_capacity(len as usize);
+        d.enter_vec(len)?;
+        let mut ret = Vec::with_capacity(len.min(MAX_PREALLOCATED) as usize);
         for _ in 0..len {
             ret.push(Decodable::consensus_decode(d)?);
         }
+        d.exit_vec();
         Ok(ret)
     }
 }
 
 macro_rules! decode_sized_vec {
     ( $size:expr, $d:expr ) => {{
-        let mut ret = Vec::with_capacity($size as usize);
+        $crate::consensus::encode::Decoder::enter_vec($d, $size as u64)?;
+        let mut ret = Vec::with_capacity(
+            ($size as u64).min($crate::consensus::encode::MAX_PREALLOCATED) as usize,
+        );
         for _ in 0..$size {
             ret.push(Decodable::consensus_decode($d)?);
         }
+        $crate::consensus::encode::Decoder::exit_vec($d);
         ret
     }};
 }
@@ -476,19 +674,21 @@ impl<D: Decoder, T: Decodable<D>> Decodable<D> for Box<[T]> {
     #[inline]
     fn consensus_decode(d: &mut D) -> Result<Box<[T]>, self::Error> {
         let len = VarInt::consensus_decode(d)?.0;
-        let len = len as usize;
-        let mut ret = Vec::with_capacity(len);
+        d.enter_vec(len)?;
+        let mut ret = Vec::with_capacity(len.min(MAX_PREALLOCATED) as usize);
         for _ in 0..len {
             ret.push(Decodable::consensus_decode(d)?);
         }
+        d.exit_vec();
         Ok(ret.into_boxed_slice())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::VarInt;
-    use super::{deserialize, serialize};
+    use super::{deserialize, deserialize_with_limits, serialize};
+    use super::{BoundedDecoder, Decodable, DecodeLimits, Decoder, Error, VarInt};
+    use crate::util::ringct::{RctSigPrunable, RctType};
 
     #[test]
     fn deserialize_varint() {
@@ -505,4 +705,79 @@ mod tests {
         assert_eq!(vec![0b1010_1100, 0b0000_0010], serialize(&VarInt(300)));
         assert_eq!("80e497d012", hex::encode(serialize(&VarInt(5000000000))));
     }
+
+    #[test]
+    fn decode_limits() {
+        let data = serialize(&vec![vec![1u8, 2], vec![3]]);
+        let limits = DecodeLimits::default();
+        let vecs: Vec<Vec<u8>> = deserialize_with_limits(&data, limits).unwrap();
+        assert_eq!(vec![vec![1u8, 2], vec![3]], vecs);
+
+        let limits = DecodeLimits {
+            max_elements: 1,
+            ..Default::default()
+        };
+        let res: Result<Vec<Vec<u8>>, Error> = deserialize_with_limits(&data, limits);
+        assert!(matches!(res, Err(Error::ElementLimitExceeded(2))));
+
+        let limits = DecodeLimits {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let res: Result<Vec<Vec<u8>>, Error> = deserialize_with_limits(&data, limits);
+        assert!(matches!(res, Err(Error::DepthLimitExceeded)));
+
+        let limits = DecodeLimits {
+            max_bytes: 3,
+            ..Default::default()
+        };
+        let res: Result<Vec<Vec<u8>>, Error> = deserialize_with_limits(&data, limits);
+        assert!(matches!(res, Err(Error::ByteLimitExceeded)));
+
+        // A length larger than the data is rejected before allocating the vector
+        let res: Result<Vec<u64>, Error> = deserialize(&[0xff, 0x7f, 0x00, 0x00]);
+        assert!(matches!(res, Err(Error::ByteLimitExceeded)));
+
+        // Failed reads are not counted
+        let mut decoder = BoundedDecoder::new(&[0x01, 0x02][..], DecodeLimits::default());
+        assert!(decoder.read_u32().is_err());
+        assert_eq!(0, decoder.bytes_read());
+        let mut decoder = BoundedDecoder::new(&[0x01, 0x02][..], DecodeLimits::default());
+        assert_eq!(1, decoder.read_u8().unwrap());
+        assert_eq!(1, decoder.bytes_read());
+    }
+
+    #[test]
+    fn bounded_preallocation() {
+        // A vector of `max_elements` pages does not fit in memory
+        #[allow(dead_code)]
+        struct Page([u8; 4096]);
+
+        impl<D: Decoder> Decodable<D> for Page {
+            fn consensus_decode(d: &mut D) -> Result<Page, Error> {
+                let mut page = [0u8; 4096];
+                page[0] = d.read_u8()?;
+                Ok(Page(page))
+            }
+        }
+
+        // On a stream the length cannot be checked against the remaining bytes
+        let limits = DecodeLimits::default();
+        let data = serialize(&VarInt(limits.max_elements - 1));
+        let mut decoder = BoundedDecoder::new(&data[..], limits);
+        let res: Result<Vec<Page>, Error> = Decodable::consensus_decode(&mut decoder);
+        assert!(matches!(res, Err(Error::Io(_))));
+        let mut decoder = BoundedDecoder::new(&data[..], limits);
+        let res: Result<Box<[Page]>, Error> = Decodable::consensus_decode(&mut decoder);
+        assert!(matches!(res, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn decode_limits_ringct() {
+        // Bulletproof prunable signatures starting with 65535 bulletproofs
+        let data = [0xff, 0xff, 0x00, 0x00, 0x00];
+        let mut decoder = super::BoundedDecoder::from_slice(&data);
+        let res = RctSigPrunable::consensus_decode(&mut decoder, RctType::Bulletproof, 1, 2, 10);
+        assert!(matches!(res, Err(Error::ByteLimitExceeded)));
+    }
 }
@@ -0,0 +1,132 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Hard fork schedule
+//!
+//! Support for finding the hard fork version, i.e. the expected block major version, active at a
+//! given height on each network.
+//!
+//! ```rust
+//! use monero::consensus::hardfork::hard_fork_version;
+//! use monero::Network;
+//!
+//! assert_eq!(1, hard_fork_version(Network::Mainnet, 0));
+//! assert_eq!(14, hard_fork_version(Network::Mainnet, 2_300_000));
+//! ```
+//!
+
+use crate::network::Network;
+
+/// Mainnet hard fork versions and their activation heights
+///
+/// **Same as** [`mainnet_hard_forks`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/hardforks/hardforks.cpp)
+pub const MAINNET_HARD_FORKS: &[(u8, u64)] = &[
+    (1, 1),
+    (2, 1_009_827),
+    (3, 1_141_317),
+    (4, 1_220_516),
+    (5, 1_288_616),
+    (6, 1_400_000),
+    (7, 1_546_000),
+    (8, 1_685_555),
+    (9, 1_686_275),
+    (10, 1_788_000),
+    (11, 1_788_720),
+    (12, 1_978_433),
+    (13, 2_210_000),
+    (14, 2_210_720),
+    (15, 2_688_888),
+    (16, 2_689_608),
+];
+
+/// Testnet hard fork versions and their activation heights
+///
+/// **Same as** [`testnet_hard_forks`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/hardforks/hardforks.cpp)
+pub const TESTNET_HARD_FORKS: &[(u8, u64)] = &[
+    (1, 1),
+    (2, 624_634),
+    (3, 800_500),
+    (4, 801_219),
+    (5, 802_660),
+    (6, 971_400),
+    (7, 1_057_027),
+    (8, 1_057_058),
+    (9, 1_057_778),
+    (10, 1_154_318),
+    (11, 1_155_038),
+    (12, 1_308_737),
+    (13, 1_543_939),
+    (14, 1_544_659),
+    (15, 1_982_800),
+    (16, 1_983_520),
+];
+
+/// Stagenet hard fork versions and their activation heights
+///
+/// **Same as** [`stagenet_hard_forks`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/hardforks/hardforks.cpp)
+pub const STAGENET_HARD_FORKS: &[(u8, u64)] = &[
+    (1, 1),
+    (2, 32_000),
+    (3, 33_000),
+    (4, 34_000),
+    (5, 35_000),
+    (6, 36_000),
+    (7, 37_000),
+    (8, 176_456),
+    (9, 177_176),
+    (10, 269_000),
+    (11, 269_720),
+    (12, 454_721),
+    (13, 675_405),
+    (14, 676_125),
+    (15, 1_151_000),
+    (16, 1_151_720),
+];
+
+/// Return the hard fork versions and their activation heights for a network
+pub fn hard_forks(network: Network) -> &'static [(u8, u64)] {
+    match network {
+        Network::Mainnet => MAINNET_HARD_FORKS,
+        Network::Testnet => TESTNET_HARD_FORKS,
+        Network::Stagenet => STAGENET_HARD_FORKS,
+    }
+}
+
+/// Return the hard fork version active at the given height, the genesis block is always version 1
+pub fn hard_fork_version(network: Network, height: u64) -> u8 {
+    hard_forks(network)
+        .iter()
+        .rev()
+        .find(|(_, activation)| height >= *activation)
+        .map(|(version, _)| *version)
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hard_fork_version;
+    use crate::network::Network;
+
+    #[test]
+    fn fork_boundaries() {
+        assert_eq!(1, hard_fork_version(Network::Mainnet, 0));
+        assert_eq!(1, hard_fork_version(Network::Mainnet, 1_009_826));
+        assert_eq!(2, hard_fork_version(Network::Mainnet, 1_009_827));
+        assert_eq!(12, hard_fork_version(Network::Mainnet, 1_978_433));
+        assert_eq!(16, hard_fork_version(Network::Mainnet, u64::MAX));
+        assert_eq!(15, hard_fork_version(Network::Testnet, 1_983_519));
+        assert_eq!(13, hard_fork_version(Network::Stagenet, 675_405));
+    }
+}
@@ -24,6 +24,8 @@
 
 #[macro_use]
 pub mod encode;
+pub mod difficulty;
+pub mod hardfork;
 
 pub use self::encode::{
     deserialize, serialize, serialize_hex, Decodable, Decoder, Encodable, Encoder,
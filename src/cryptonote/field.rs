@@ -0,0 +1,361 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Field arithmetic modulo `p = 2^255 - 19`
+//!
+//! The field elements of `curve25519-dalek` are not public, this minimal implementation is only
+//! used to map hashes to curve points. It is not constant time and must not handle secrets.
+//!
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+
+/// The prime `p = 2^255 - 19`, little endian 64-bit limbs
+const P: [u64; 4] = [
+    0xffff_ffff_ffff_ffed,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+];
+/// `(p - 5) / 8`
+const P_MINUS_5_DIV_8: [u64; 4] = [
+    0xffff_ffff_ffff_fffd,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x0fff_ffff_ffff_ffff,
+];
+/// `(p + 3) / 8`
+const P_PLUS_3_DIV_8: [u64; 4] = [
+    0xffff_ffff_ffff_fffe,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x0fff_ffff_ffff_ffff,
+];
+/// `(p - 1) / 4`
+const P_MINUS_1_DIV_4: [u64; 4] = [
+    0xffff_ffff_ffff_fffb,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x1fff_ffff_ffff_ffff,
+];
+/// `p - 2`
+const P_MINUS_2: [u64; 4] = [
+    0xffff_ffff_ffff_ffeb,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+];
+/// Montgomery curve constant `A`
+const MONTGOMERY_A: u64 = 486_662;
+
+/// A field element, always reduced modulo `p`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FieldElement([u64; 4]);
+
+impl FieldElement {
+    fn from_u64(value: u64) -> FieldElement {
+        FieldElement([value, 0, 0, 0])
+    }
+
+    /// Interpret the 256 bits as a little endian number and reduce it, unlike the point
+    /// decompression the highest bit is not ignored
+    fn from_bytes(bytes: &[u8; 32]) -> FieldElement {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(buf);
+        }
+        reduce(limbs, 0)
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn is_zero(self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Return whether the element is negative, i.e. odd
+    fn is_negative(self) -> bool {
+        self.0[0] & 1 == 1
+    }
+
+    fn square(self) -> FieldElement {
+        self * self
+    }
+
+    fn pow(self, exponent: &[u64; 4]) -> FieldElement {
+        let mut result = FieldElement::from_u64(1);
+        for limb in exponent.iter().rev() {
+            for bit in (0..64).rev() {
+                result = result.square();
+                if (limb >> bit) & 1 == 1 {
+                    result = result * self;
+                }
+            }
+        }
+        result
+    }
+
+    fn invert(self) -> FieldElement {
+        self.pow(&P_MINUS_2)
+    }
+
+    /// Return a square root of the element, if any
+    fn sqrt(self) -> Option<FieldElement> {
+        let root = self.pow(&P_PLUS_3_DIV_8);
+        if root.square() == self {
+            Some(root)
+        } else if root.square() == -self {
+            Some(root * sqrt_m1())
+        } else {
+            None
+        }
+    }
+}
+
+/// Reduce the number `limbs + carry * 2^256` modulo `p`, with `2^256 = 38 mod p`
+fn reduce(mut limbs: [u64; 4], carry: u64) -> FieldElement {
+    let mut carry = carry as u128 * 38;
+    while carry != 0 {
+        for limb in limbs.iter_mut() {
+            let acc = *limb as u128 + carry;
+            *limb = acc as u64;
+            carry = acc >> 64;
+        }
+        carry *= 38;
+    }
+    // With 2^255 = 19 mod p the number is below 2^255 + 19 < 2p
+    let mut carry = (limbs[3] >> 63) as u128 * 19;
+    limbs[3] &= P[3];
+    for limb in limbs.iter_mut() {
+        let acc = *limb as u128 + carry;
+        *limb = acc as u64;
+        carry = acc >> 64;
+    }
+    let (reduced, borrow) = sub_limbs(&limbs, &P);
+    if borrow {
+        FieldElement(limbs)
+    } else {
+        FieldElement(reduced)
+    }
+}
+
+/// Subtract the numbers and return whether the result wrapped
+fn sub_limbs(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut result = [0u64; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (diff, b1) = a[i].overflowing_sub(b[i]);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        result[i] = diff;
+        borrow = b1 || b2;
+    }
+    (result, borrow)
+}
+
+impl Add for FieldElement {
+    type Output = FieldElement;
+
+    fn add(self, other: FieldElement) -> FieldElement {
+        let mut limbs = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let acc = self.0[i] as u128 + other.0[i] as u128 + carry;
+            *limb = acc as u64;
+            carry = acc >> 64;
+        }
+        reduce(limbs, carry as u64)
+    }
+}
+
+impl Sub for FieldElement {
+    type Output = FieldElement;
+
+    fn sub(self, other: FieldElement) -> FieldElement {
+        let (limbs, borrow) = sub_limbs(&self.0, &other.0);
+        if borrow {
+            // Wrapped around 2^256, adding p wraps back into the field
+            let mut result = [0u64; 4];
+            let mut carry = 0u128;
+            for (i, limb) in result.iter_mut().enumerate() {
+                let acc = limbs[i] as u128 + P[i] as u128 + carry;
+                *limb = acc as u64;
+                carry = acc >> 64;
+            }
+            FieldElement(result)
+        } else {
+            FieldElement(limbs)
+        }
+    }
+}
+
+impl Neg for FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> FieldElement {
+        FieldElement([0; 4]) - self
+    }
+}
+
+impl Mul for FieldElement {
+    type Output = FieldElement;
+
+    fn mul(self, other: FieldElement) -> FieldElement {
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let acc = wide[i + j] as u128 + self.0[i] as u128 * other.0[j] as u128 + carry;
+                wide[i + j] = acc as u64;
+                carry = acc >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+        // Fold the high half with 2^256 = 38 mod p
+        let mut limbs = [0u64; 4];
+        let mut carry = 0u128;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let acc = wide[i] as u128 + wide[i + 4] as u128 * 38 + carry;
+            *limb = acc as u64;
+            carry = acc >> 64;
+        }
+        reduce(limbs, carry as u64)
+    }
+}
+
+/// `sqrt(-1) = 2^((p - 1) / 4)`
+fn sqrt_m1() -> FieldElement {
+    FieldElement::from_u64(2).pow(&P_MINUS_1_DIV_4)
+}
+
+/// Compute `(u / v)^((p + 3) / 8)` as `u * v^3 * (u * v^7)^((p - 5) / 8)`
+fn div_pow_m1(u: FieldElement, v: FieldElement) -> FieldElement {
+    let v3 = v.square() * v;
+    let v7 = v3.square() * v;
+    u * v3 * (u * v7).pow(&P_MINUS_5_DIV_8)
+}
+
+/// Map 32 bytes to a curve point, without clearing the cofactor
+///
+/// The choice of the square roots below does not matter, the sign of the resulting `x`
+/// coordinate is fixed at the end.
+///
+/// **Same as** `ge_fromfe_frombytes_vartime` in [`crypto-ops.c`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto-ops.c)
+pub(crate) fn map_to_point(bytes: &[u8; 32]) -> EdwardsPoint {
+    let a = FieldElement::from_u64(MONTGOMERY_A);
+    let a_a2 = a * (a + FieldElement::from_u64(2));
+    let two = FieldElement::from_u64(2);
+    let sqrt_m1 = sqrt_m1();
+
+    let u = FieldElement::from_bytes(bytes);
+    // v = 2 * u^2
+    let v = two * u.square();
+    // w = 2 * u^2 + 1
+    let w = v + FieldElement::from_u64(1);
+    // x = w^2 - 2 * A^2 * u^2
+    let mut x = w.square() - a.square() * v;
+    let mut rx = div_pow_m1(w, x);
+    x = rx.square() * x;
+
+    let (z, sign) = if (w - x).is_zero() {
+        // sqrt(2 * A * (A + 2))
+        rx = rx
+            * (two * a_a2)
+                .sqrt()
+                .expect("2 * A * (A + 2) is a square. QED");
+        (-a * v, false)
+    } else if (w + x).is_zero() {
+        // sqrt(-2 * A * (A + 2))
+        rx = rx
+            * (-two * a_a2)
+                .sqrt()
+                .expect("-2 * A * (A + 2) is a square. QED");
+        (-a * v, false)
+    } else {
+        x = x * sqrt_m1;
+        if (w - x).is_zero() {
+            // sqrt(sqrt(-1) * A * (A + 2))
+            rx = rx
+                * (sqrt_m1 * a_a2)
+                    .sqrt()
+                    .expect("sqrt(-1) * A * (A + 2) is a square. QED");
+        } else {
+            // sqrt(-sqrt(-1) * A * (A + 2))
+            rx = rx
+                * (-sqrt_m1 * a_a2)
+                    .sqrt()
+                    .expect("-sqrt(-1) * A * (A + 2) is a square. QED");
+        }
+        (-a, true)
+    };
+    if !sign {
+        rx = rx * u;
+    }
+    if rx.is_negative() != sign {
+        rx = -rx;
+    }
+
+    // Projective coordinates (X : Y : Z)
+    let pz = z + w;
+    let py = z - w;
+    let px = rx * pz;
+    let recip = pz.invert();
+    let mut bytes = (py * recip).to_bytes();
+    bytes[31] ^= ((px * recip).is_negative() as u8) << 7;
+    CompressedEdwardsY(bytes)
+        .decompress()
+        .expect("The mapped coordinates are on the curve. QED")
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::traits::IsIdentity;
+
+    use super::{map_to_point, sqrt_m1, FieldElement, P};
+
+    #[test]
+    fn field_arithmetic() {
+        let p_minus_1 = FieldElement([P[0] - 1, P[1], P[2], P[3]]);
+        let one = FieldElement::from_u64(1);
+        assert_eq!(FieldElement([0; 4]), p_minus_1 + one);
+        assert_eq!(p_minus_1, -one);
+        assert_eq!(one, p_minus_1 * p_minus_1);
+        assert_eq!(-one, sqrt_m1().square());
+        let x = FieldElement::from_bytes(&[0xff; 32]);
+        assert_eq!(one, x * x.invert());
+        // 2^256 - 1 = 2p + 37
+        assert_eq!(FieldElement::from_u64(37), x);
+        let root = x.square().sqrt().unwrap();
+        assert!(root == x || root == -x);
+        // 2 is not a square modulo p
+        assert_eq!(None, FieldElement::from_u64(2).sqrt());
+    }
+
+    #[test]
+    fn map_to_curve() {
+        for i in 0..32u8 {
+            let point = map_to_point(&[i; 32]);
+            assert!(!point.is_identity());
+        }
+    }
+}
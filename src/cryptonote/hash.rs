@@ -15,23 +15,31 @@
 
 //! CryptoNote hash structures and functions
 //!
-//! Support for (de)serializable hashes (Keccak 256) and `Hn` (hash to number, or hash to scalar).
+//! Support for (de)serializable hashes (Keccak 256), `Hn` (hash to number, or hash to scalar) and
+//! `Hp` (hash to point).
 //!
 
+use curve25519_dalek::edwards::EdwardsPoint;
 use curve25519_dalek::scalar::Scalar;
 use keccak_hash::keccak_256;
 
 use crate::consensus::encode::{self, Decodable, Decoder, Encodable, Encoder};
+use crate::cryptonote::field;
 use crate::util::key::PrivateKey;
+
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
 fixed_hash::construct_fixed_hash!(
     /// Result of a Keccak-256
-    #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
     pub struct Hash(32);
 );
 
+impl_hex_serde!(Hash, 32, |bytes: &[u8]| match bytes.len() {
+    32 => Ok(Hash::from_slice(bytes)),
+    _ => Err("invalid length"),
+});
+
 impl Hash {
     /// Create a null hash with all zeros
     pub fn null_hash() -> Hash {
@@ -63,6 +71,13 @@ impl Hash {
     pub fn hash_to_scalar(input: &[u8]) -> PrivateKey {
         Self::hash(input).as_scalar()
     }
+
+    /// Hash a stream of bytes and map it to a point of the prime order subgroup, `Hp()`
+    ///
+    /// **Same as** `hash_to_ec` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+    pub fn hash_to_point(input: &[u8]) -> EdwardsPoint {
+        field::map_to_point(&Self::hash(input).0).mul_by_cofactor()
+    }
 }
 
 impl<D: Decoder> Decodable<D> for Hash {
@@ -77,6 +92,135 @@ impl<S: Encoder> Encodable<S> for Hash {
     }
 }
 
+/// Compute the Merkle root of a list of hashes with the CryptoNote tree hash algorithm, return
+/// `None` if the list is empty
+///
+/// **Same as** [`monero/src/crypto/tree-hash.c`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/tree-hash.c#L68-L104)
+pub fn tree_hash(hashes: &[Hash]) -> Option<Hash> {
+    match hashes.len() {
+        0 => None,
+        1 => Some(hashes[0]),
+        2 => Some(hash_pair(&hashes[0], &hashes[1])),
+        count => {
+            // Largest power of two strictly lower than the number of hashes
+            let mut cnt = tree_hash_cnt(count);
+            // The first `2 * cnt - count` hashes are kept as is, the remaining ones are paired
+            let (kept, paired) = hashes.split_at(2 * cnt - count);
+            let mut ints: Vec<Hash> = kept.to_vec();
+            ints.extend(paired.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])));
+            while cnt > 2 {
+                cnt >>= 1;
+                ints = ints
+                    .chunks(2)
+                    .map(|pair| hash_pair(&pair[0], &pair[1]))
+                    .collect();
+            }
+            Some(hash_pair(&ints[0], &ints[1]))
+        }
+    }
+}
+
+/// Maximum length of a [`MerkleBranch`], one bit of its path per sibling hash
+pub const MAX_BRANCH_DEPTH: usize = u32::BITS as usize;
+
+/// Merkle branch of a leaf in a tree built with [`tree_hash`], from the leaf to the root
+///
+/// Siblings are ordered from the leaf to the root and bit `0` of the path is the position of the
+/// leaf. This is the reverse of the `tree_branch` and `tree_branch_hash` convention in
+/// [`tree-hash.c`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/tree-hash.c),
+/// where the branch starts at the root and bit `depth - 1` is the position of the leaf.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct MerkleBranch {
+    /// Sibling hashes, from the leaf to the root
+    pub hashes: Vec<Hash>,
+    /// Position bits, bit `i` is set if the node at depth `i` (from the leaf) is a right child
+    pub path: u32,
+}
+
+impl MerkleBranch {
+    fn push(&mut self, sibling: Hash, is_right: bool) {
+        debug_assert!(self.hashes.len() < MAX_BRANCH_DEPTH);
+        if is_right {
+            self.path |= 1 << self.hashes.len();
+        }
+        self.hashes.push(sibling);
+    }
+
+    /// Compute the Merkle root from the leaf and the branch, return `None` if the branch is
+    /// longer than [`MAX_BRANCH_DEPTH`]
+    pub fn root(&self, leaf: &Hash) -> Option<Hash> {
+        if self.hashes.len() > MAX_BRANCH_DEPTH {
+            return None;
+        }
+        Some(
+            self.hashes
+                .iter()
+                .enumerate()
+                .fold(*leaf, |node, (i, sibling)| {
+                    if self.path & (1 << i) != 0 {
+                        hash_pair(sibling, &node)
+                    } else {
+                        hash_pair(&node, sibling)
+                    }
+                }),
+        )
+    }
+}
+
+/// Compute the Merkle branch of the hash at `index` in a tree built with [`tree_hash`], return
+/// `None` if the index is out of bounds
+pub fn tree_branch(hashes: &[Hash], index: usize) -> Option<MerkleBranch> {
+    if index >= hashes.len() {
+        return None;
+    }
+    let mut branch = MerkleBranch::default();
+    let (mut level, mut pos) = match hashes.len() {
+        1 | 2 => (hashes.to_vec(), index),
+        count => {
+            // Same first step as `tree_hash`, brings the number of nodes to a power of two
+            let cnt = tree_hash_cnt(count);
+            let (kept, paired) = hashes.split_at(2 * cnt - count);
+            let mut ints: Vec<Hash> = kept.to_vec();
+            ints.extend(paired.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])));
+            let pos = match index.checked_sub(kept.len()) {
+                None => index,
+                Some(i) => {
+                    branch.push(paired[i ^ 1], i & 1 == 1);
+                    kept.len() + i / 2
+                }
+            };
+            (ints, pos)
+        }
+    };
+    while level.len() > 1 {
+        branch.push(level[pos ^ 1], pos & 1 == 1);
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        pos >>= 1;
+    }
+    Some(branch)
+}
+
+/// Return the largest power of two strictly lower than `count`, `count` must be greater than 2
+pub(crate) fn tree_hash_cnt(count: usize) -> usize {
+    let mut pow = 2;
+    while pow < count {
+        pow <<= 1;
+    }
+    pow >> 1
+}
+
+/// Hash the concatenation of two hashes
+pub(crate) fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(left.as_bytes());
+    buf[32..].copy_from_slice(right.as_bytes());
+    Hash::hash(&buf)
+}
+
 /// Capacity of an object to hash itself
 pub trait Hashable {
     /// Return its own hash
@@ -90,10 +234,14 @@ pub trait Hashable {
 
 fixed_hash::construct_fixed_hash!(
     /// 8 bytes hash
-    #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
     pub struct Hash8(8);
 );
 
+impl_hex_serde!(Hash8, 8, |bytes: &[u8]| match bytes.len() {
+    8 => Ok(Hash8::from_slice(bytes)),
+    _ => Err("invalid length"),
+});
+
 impl<D: Decoder> Decodable<D> for Hash8 {
     fn consensus_decode(d: &mut D) -> Result<Hash8, encode::Error> {
         Ok(Hash8(Decodable::consensus_decode(d)?))
@@ -105,3 +253,123 @@ impl<S: Encoder> Encodable<S> for Hash8 {
         self.0.consensus_encode(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use curve25519_dalek::traits::IsIdentity;
+
+    use super::{hash_pair, tree_branch, tree_hash, Hash, MerkleBranch, MAX_BRANCH_DEPTH};
+    use crate::util::ringct::H;
+
+    #[test]
+    fn hash_to_point() {
+        let point = Hash::hash_to_point(ED25519_BASEPOINT_POINT.compress().as_bytes());
+        assert!(point.is_torsion_free());
+        assert!(!point.is_identity());
+        assert_ne!(point, Hash::hash_to_point(&H));
+        // The amount generator is `8 * Hash(G)`, the hash read directly as a point
+        let hash = Hash::hash(ED25519_BASEPOINT_POINT.compress().as_bytes());
+        let point = CompressedEdwardsY(hash.to_bytes()).decompress().unwrap();
+        assert_eq!(H, point.mul_by_cofactor().compress().to_bytes());
+
+        // `hash_to_ec` vector of monero `tests/crypto/tests.txt`
+        let key = hex::decode("da66e9ba613919dec28ef367a125bb310d6d83fb9052e71034164b6dc4f392d0")
+            .unwrap();
+        assert_eq!(
+            "52b3f38753b4e13b74624862e253072cf12f745d43fcfafbe8c217701a6e5875",
+            hex::encode(Hash::hash_to_point(&key).compress().as_bytes())
+        );
+    }
+
+    #[test]
+    fn tree_hash_small_lists() {
+        let hashes: Vec<Hash> = (0u8..5).map(|i| Hash::hash(&[i])).collect();
+
+        assert_eq!(None, tree_hash(&[]));
+        assert_eq!(Some(hashes[0]), tree_hash(&hashes[..1]));
+        assert_eq!(
+            Some(hash_pair(&hashes[0], &hashes[1])),
+            tree_hash(&hashes[..2])
+        );
+        // With three hashes the first one is kept and the last two are paired
+        assert_eq!(
+            Some(hash_pair(&hashes[0], &hash_pair(&hashes[1], &hashes[2]))),
+            tree_hash(&hashes[..3])
+        );
+        assert_eq!(
+            Some(hash_pair(
+                &hash_pair(&hashes[0], &hashes[1]),
+                &hash_pair(&hashes[2], &hashes[3])
+            )),
+            tree_hash(&hashes[..4])
+        );
+        // With five hashes the first three are kept and the last two are paired
+        assert_eq!(
+            Some(hash_pair(
+                &hash_pair(&hashes[0], &hashes[1]),
+                &hash_pair(&hashes[2], &hash_pair(&hashes[3], &hashes[4]))
+            )),
+            tree_hash(&hashes)
+        );
+    }
+
+    #[test]
+    fn tree_branch_all_leaves() {
+        let hashes: Vec<Hash> = (0u8..17).map(|i| Hash::hash(&[i])).collect();
+        for count in 1..=hashes.len() {
+            let root = tree_hash(&hashes[..count]).unwrap();
+            for index in 0..count {
+                let branch = tree_branch(&hashes[..count], index).unwrap();
+                assert_eq!(Some(root), branch.root(&hashes[index]));
+                // The branch does not hold for another leaf
+                if count > 1 {
+                    let other = hashes[(index + 1) % count];
+                    assert_ne!(Some(root), branch.root(&other));
+                }
+            }
+            assert_eq!(None, tree_branch(&hashes[..count], count));
+        }
+
+        // With three hashes the first one is kept and the last two are paired
+        let branch = tree_branch(&hashes[..3], 2).unwrap();
+        assert_eq!(vec![hashes[1], hashes[0]], branch.hashes);
+        assert_eq!(0b11, branch.path);
+
+        // Every sibling needs a bit of the path
+        let branch = MerkleBranch {
+            hashes: vec![hashes[0]; MAX_BRANCH_DEPTH + 1],
+            path: u32::MAX,
+        };
+        assert_eq!(None, branch.root(&hashes[1]));
+        let branch = MerkleBranch {
+            hashes: vec![hashes[0]; MAX_BRANCH_DEPTH],
+            path: u32::MAX,
+        };
+        assert!(branch.root(&hashes[1]).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn serde_hash() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Configure, Token};
+
+        let hash = Hash::hash(b"");
+        assert_tokens(
+            &hash.readable(),
+            &[Token::Str(
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            )],
+        );
+        assert_de_tokens_error::<serde_test::Readable<Hash>>(
+            &[Token::Str("c5d2")],
+            "invalid length",
+        );
+
+        let mut tokens = vec![Token::Tuple { len: 32 }];
+        tokens.extend(hash.as_bytes().iter().map(|b| Token::U8(*b)));
+        tokens.push(Token::TupleEnd);
+        assert_tokens(&hash.compact(), &tokens);
+    }
+}
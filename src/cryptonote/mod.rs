@@ -18,6 +18,10 @@
 //! Support for CryptoNote protocols such as Hash to number `Hn()`, One-time keys, and Subaddresses.
 //!
 
+mod field;
 pub mod hash;
 pub mod onetime_key;
+pub mod reserve_proof;
+pub mod spend_proof;
 pub mod subaddress;
+pub mod tx_proof;
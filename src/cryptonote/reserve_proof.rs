@@ -0,0 +1,853 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Reserve proofs
+//!
+//! A reserve proof shows that an address owns a set of outputs. For each output it holds the
+//! shared secret `D = a*R` with a proof of the view secret key, the key image with a signature
+//! of the one-time secret key, and for each (sub)address spend key receiving the outputs a
+//! signature of the spend secret key. The verifier decrypts the amounts and checks the key images
+//! against the spent ones to compute the total and spent amounts.
+//!
+//! Proofs are encoded as `wallet2` does: the entries followed by the spend key signatures in a
+//! boost portable binary archive, encoded in base58 after the `ReserveProofV2` header.
+//!
+//! **Same as** `get_reserve_proof` and `check_reserve_proof` in [`wallet2.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet2.cpp)
+//!
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use base58_monero::base58;
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use thiserror::Error;
+
+use crate::blockdata::transaction::{KeyImage, OwnedTxOut, Transaction, TxOutTarget};
+use crate::consensus::encode::MAX_PREALLOCATED;
+use crate::cryptonote::hash::{Hash, Hashable};
+use crate::cryptonote::onetime_key::{KeyGenerator, MONERO_MUL_FACTOR};
+use crate::cryptonote::spend_proof::{check_ring_signature, generate_ring_signature};
+use crate::cryptonote::subaddress;
+use crate::cryptonote::tx_proof::{check_tx_proof, generate_tx_proof};
+use crate::util::address::Address;
+use crate::util::key::{KeyPair, PrivateKey, PublicKey};
+use crate::util::ringct::{Key, Signature};
+
+/// Header of the reserve proofs
+pub const RESERVE_PROOF_HEADER: &str = "ReserveProofV2";
+
+/// Header of the version 1 reserve proofs, not supported
+const RESERVE_PROOF_V1_HEADER: &str = "ReserveProofV1";
+
+/// Signature at the start of boost archives
+const BOOST_ARCHIVE_SIGNATURE: &str = "serialization::archive";
+
+/// Library version of the written boost archives, readers reject archives of a later version than
+/// their own so an old version is written
+const BOOST_LIBRARY_VERSION: u64 = 12;
+
+/// Possible errors when parsing or verifying reserve proofs
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// The proof does not start with the reserve proof header
+    #[error("invalid proof header")]
+    InvalidHeader,
+    /// Version 1 proofs are not supported
+    #[error("unsupported proof version")]
+    UnsupportedVersion,
+    /// The proof is not a valid base58 encoded reserve proof
+    #[error("invalid proof encoding")]
+    InvalidEncoding,
+    /// The transaction of a proof entry is not provided
+    #[error("missing transaction {0}")]
+    MissingTransaction(Hash),
+    /// The output of a proof entry does not exist or is not a key output
+    #[error("invalid output for entry {0}")]
+    InvalidOutput(usize),
+    /// The transaction of a proof entry has no public key
+    #[error("no transaction public key for entry {0}")]
+    NoTxPublicKey(usize),
+    /// The shared secret signature of a proof entry is invalid
+    #[error("invalid shared secret signature for entry {0}")]
+    InvalidSharedSecret(usize),
+    /// The key image signature of a proof entry is invalid
+    #[error("invalid key image signature for entry {0}")]
+    InvalidKeyImage(usize),
+    /// The output of a proof entry is not received by a signed spend key
+    #[error("no spend key signature for entry {0}")]
+    MissingSpendKeySignature(usize),
+    /// A spend key signature is invalid
+    #[error("invalid spend key signature")]
+    InvalidSpendKeySignature,
+    /// The decrypted amount of a proof entry does not open the output commitment
+    #[error("invalid amount for entry {0}")]
+    InvalidAmount(usize),
+    /// A proof entry repeats the output or the key image of a previous entry
+    #[error("duplicate entry {0}")]
+    DuplicateEntry(usize),
+    /// The proven amounts overflow
+    #[error("amount overflow")]
+    AmountOverflow,
+}
+
+/// Proof of ownership of an output
+///
+/// **Same as** `reserve_proof_entry` in [`wallet2.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet2.h)
+#[derive(Debug, Clone)]
+pub struct ReserveProofEntry {
+    /// Id of the transaction of the output
+    pub txid: Hash,
+    /// Index of the output in the transaction
+    pub index_in_tx: u64,
+    /// Shared secret `D = a*R` of the output
+    pub shared_secret: PublicKey,
+    /// Key image of the output
+    pub key_image: KeyImage,
+    /// Signature of the shared secret with the view secret key
+    pub shared_secret_sig: Signature,
+    /// Signature of the key image with the one-time secret key
+    pub key_image_sig: Signature,
+}
+
+/// Signature of a (sub)address spend public key
+#[derive(Debug, Clone)]
+pub struct SpendKeySignature {
+    /// The (sub)address spend public key
+    pub spend: PublicKey,
+    /// Signature with the spend secret key
+    pub signature: Signature,
+}
+
+/// A reserve proof
+///
+/// The spend key signatures are a map from the spend public keys in `wallet2`, their order is not
+/// significant.
+#[derive(Debug, Clone)]
+pub struct ReserveProof {
+    /// Proofs of the outputs
+    pub entries: Vec<ReserveProofEntry>,
+    /// Signatures of the spend keys receiving the outputs
+    pub spend_key_signatures: Vec<SpendKeySignature>,
+}
+
+/// Amounts proven by a reserve proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReserveAmounts {
+    /// Total amount of the outputs
+    pub total: u64,
+    /// Amount of the outputs with a spent key image
+    pub spent: u64,
+}
+
+impl ReserveProof {
+    /// Create a reserve proof of outputs owned by the key pair, with the id of their transaction
+    pub fn generate<R: RngCore + CryptoRng>(
+        keys: &KeyPair,
+        outputs: &[(Hash, OwnedTxOut)],
+        message: &str,
+        rng: &mut R,
+    ) -> ReserveProof {
+        let address_view = PublicKey::from_private_key(&keys.view);
+        let address_spend = PublicKey::from_private_key(&keys.spend);
+        let key_images: Vec<KeyImage> = outputs
+            .iter()
+            .map(|(_, out)| KeyImage::new(&out.recover_key(keys)))
+            .collect();
+        let msg = prefix_hash(message, &address_spend, &address_view, &key_images);
+
+        // Subaddress indexes receiving the outputs
+        let mut indexes = vec![];
+        let entries = outputs
+            .iter()
+            .zip(key_images)
+            .map(|((txid, out), key_image)| {
+                if !indexes.contains(&out.sub_index) {
+                    indexes.push(out.sub_index);
+                }
+                let shared_secret = keys.view * &out.tx_pubkey;
+                let shared_secret_sig = generate_tx_proof(
+                    &msg,
+                    &address_view,
+                    &out.tx_pubkey,
+                    None,
+                    &shared_secret,
+                    &keys.view,
+                    rng,
+                );
+                let secret = out.recover_key(keys);
+                let ring = [PublicKey::from_private_key(&secret)];
+                let key_image_sig =
+                    generate_ring_signature(&msg, &key_image, &ring, &secret, 0, rng).remove(0);
+                ReserveProofEntry {
+                    txid: *txid,
+                    index_in_tx: out.index as u64,
+                    shared_secret,
+                    key_image,
+                    shared_secret_sig,
+                    key_image_sig,
+                }
+            })
+            .collect();
+
+        let spend_key_signatures = indexes
+            .into_iter()
+            .map(|index| {
+                let secret = subaddress::get_spend_secret_key(keys, index);
+                let spend = PublicKey::from_private_key(&secret);
+                SpendKeySignature {
+                    spend,
+                    signature: generate_signature(&msg, &spend, &secret, rng),
+                }
+            })
+            .collect();
+
+        ReserveProof {
+            entries,
+            spend_key_signatures,
+        }
+    }
+
+    /// Verify the reserve proof of the address with the transactions of its entries, return the
+    /// total amount and the amount already spent according to the set of spent key images
+    pub fn verify(
+        &self,
+        address: &Address,
+        message: &str,
+        txs: &[Transaction],
+        spent_key_images: &HashSet<KeyImage>,
+    ) -> Result<ReserveAmounts, Error> {
+        // Each output must be counted only once
+        let mut outputs = HashSet::new();
+        let mut seen_key_images = HashSet::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            if !outputs.insert((entry.txid, entry.index_in_tx))
+                || !seen_key_images.insert(&entry.key_image)
+            {
+                return Err(Error::DuplicateEntry(i));
+            }
+        }
+
+        let key_images: Vec<KeyImage> = self
+            .entries
+            .iter()
+            .map(|entry| entry.key_image.clone())
+            .collect();
+        let msg = prefix_hash(
+            message,
+            &address.public_spend,
+            &address.public_view,
+            &key_images,
+        );
+
+        let mut spend_keys = HashSet::new();
+        for SpendKeySignature { spend, signature } in &self.spend_key_signatures {
+            if !check_signature(&msg, spend, signature) {
+                return Err(Error::InvalidSpendKeySignature);
+            }
+            spend_keys.insert(spend);
+        }
+
+        let txs: HashMap<Hash, &Transaction> = txs.iter().map(|tx| (tx.hash(), tx)).collect();
+        let mut amounts = ReserveAmounts { total: 0, spent: 0 };
+        for (i, entry) in self.entries.iter().enumerate() {
+            let tx = txs
+                .get(&entry.txid)
+                .ok_or(Error::MissingTransaction(entry.txid))?;
+            let index = entry.index_in_tx as usize;
+            let key = match tx.prefix.outputs.get(index).map(|output| &output.target) {
+                Some(TxOutTarget::ToKey { key }) => *key,
+                _ => return Err(Error::InvalidOutput(i)),
+            };
+
+            // The output is derived from the transaction public key or its additional key
+            let mut tx_pubkeys = vec![tx.prefix.tx_pubkey().ok_or(Error::NoTxPublicKey(i))?];
+            if let Some(additional) = tx.prefix.tx_additional_pubkeys() {
+                tx_pubkeys.extend(additional.get(index));
+            }
+            if !tx_pubkeys.iter().any(|tx_pubkey| {
+                check_tx_proof(
+                    &msg,
+                    &address.public_view,
+                    tx_pubkey,
+                    None,
+                    &entry.shared_secret,
+                    &entry.shared_secret_sig,
+                )
+            }) {
+                return Err(Error::InvalidSharedSecret(i));
+            }
+
+            let ring = [key];
+            if !check_ring_signature(
+                &msg,
+                &entry.key_image,
+                &ring,
+                std::slice::from_ref(&entry.key_image_sig),
+            ) {
+                return Err(Error::InvalidKeyImage(i));
+            }
+
+            // The spend key receiving the output is `P - Hs(8*D || n)*G`
+            let shared_key = KeyGenerator {
+                spend: address.public_spend,
+                rv: PrivateKey::from_scalar(Scalar::from(MONERO_MUL_FACTOR)) * &entry.shared_secret,
+            }
+            .get_rvn_scalar(index);
+            let spend = key - PublicKey::from_private_key(&shared_key);
+            if !spend_keys.contains(&spend) {
+                return Err(Error::MissingSpendKeySignature(i));
+            }
+
+            let amount = tx
+                .output_amount(index, &shared_key)
+                .ok_or(Error::InvalidAmount(i))?;
+            amounts.total = amounts
+                .total
+                .checked_add(amount)
+                .ok_or(Error::AmountOverflow)?;
+            if spent_key_images.contains(&entry.key_image) {
+                amounts.spent = amounts
+                    .spent
+                    .checked_add(amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+        }
+        Ok(amounts)
+    }
+}
+
+impl fmt::Display for ReserveProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut archive = ArchiveWriter::new();
+        archive.collection("vector", self.entries.len());
+        for entry in &self.entries {
+            archive.class("reserve_proof_entry");
+            archive.array("hash", entry.txid.as_bytes());
+            archive.int(entry.index_in_tx);
+            archive.array("public_key", entry.shared_secret.as_bytes());
+            archive.array("key_image", entry.key_image.image.as_bytes());
+            archive.signature(&entry.shared_secret_sig);
+            archive.signature(&entry.key_image_sig);
+        }
+        // Maps only hold their size before their pairs
+        archive.class("unordered_map");
+        archive.int(self.spend_key_signatures.len() as u64);
+        for SpendKeySignature { spend, signature } in &self.spend_key_signatures {
+            archive.array("public_key", spend.as_bytes());
+            archive.signature(signature);
+        }
+        let data = base58::encode(&archive.buf).map_err(|_| fmt::Error)?;
+        write!(f, "{}{}", RESERVE_PROOF_HEADER, data)
+    }
+}
+
+impl FromStr for ReserveProof {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ReserveProof, Error> {
+        if s.starts_with(RESERVE_PROOF_V1_HEADER) {
+            return Err(Error::UnsupportedVersion);
+        }
+        let data = s
+            .strip_prefix(RESERVE_PROOF_HEADER)
+            .ok_or(Error::InvalidHeader)?;
+        let data = base58::decode(data).map_err(|_| Error::InvalidEncoding)?;
+
+        let mut archive = ArchiveReader::new(&data)?;
+        let count = archive.collection("vector")?;
+        let mut entries = Vec::with_capacity(count.min(MAX_PREALLOCATED) as usize);
+        for _ in 0..count {
+            archive.class("reserve_proof_entry")?;
+            entries.push(ReserveProofEntry {
+                txid: archive.hash("hash")?,
+                index_in_tx: archive.int()?,
+                shared_secret: archive.public_key()?,
+                key_image: KeyImage {
+                    image: archive.hash("key_image")?,
+                },
+                shared_secret_sig: archive.signature()?,
+                key_image_sig: archive.signature()?,
+            });
+        }
+        archive.class("unordered_map")?;
+        let count = archive.int()?;
+        let mut spend_key_signatures = Vec::with_capacity(count.min(MAX_PREALLOCATED) as usize);
+        for _ in 0..count {
+            spend_key_signatures.push(SpendKeySignature {
+                spend: archive.public_key()?,
+                signature: archive.signature()?,
+            });
+        }
+        if !archive.data.is_empty() {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(ReserveProof {
+            entries,
+            spend_key_signatures,
+        })
+    }
+}
+
+/// Writer of a boost portable binary archive
+///
+/// Integers are written as their number of bytes followed by their little endian bytes without
+/// the trailing zeros. The first object of each class is preceded by its tracking level and
+/// version, both zero, and Monero keys, hashes and signatures are written as arrays of bytes.
+struct ArchiveWriter {
+    buf: Vec<u8>,
+    classes: HashSet<&'static str>,
+}
+
+impl ArchiveWriter {
+    /// Create an archive starting with the boost header and the little endian flags
+    fn new() -> ArchiveWriter {
+        let mut archive = ArchiveWriter {
+            buf: vec![],
+            classes: HashSet::new(),
+        };
+        archive.int(BOOST_ARCHIVE_SIGNATURE.len() as u64);
+        archive
+            .buf
+            .extend_from_slice(BOOST_ARCHIVE_SIGNATURE.as_bytes());
+        archive.int(BOOST_LIBRARY_VERSION);
+        archive.buf.push(0);
+        archive
+    }
+
+    fn int(&mut self, value: u64) {
+        let size = 8 - value.leading_zeros() as usize / 8;
+        self.buf.push(size as u8);
+        self.buf.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    /// Write the tracking level and version of the class on its first object
+    fn class(&mut self, name: &'static str) {
+        if self.classes.insert(name) {
+            self.int(0);
+            self.int(0);
+        }
+    }
+
+    /// Write the size and item version of a collection
+    fn collection(&mut self, name: &'static str, len: usize) {
+        self.class(name);
+        self.int(len as u64);
+        self.int(0);
+    }
+
+    fn array(&mut self, name: &'static str, bytes: &[u8]) {
+        self.class(name);
+        self.int(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn signature(&mut self, signature: &Signature) {
+        self.array("signature", &[signature.c.key, signature.r.key].concat());
+    }
+}
+
+/// Reader of a boost portable binary archive written by [`ArchiveWriter`] or `wallet2`, tracked
+/// objects are not supported
+struct ArchiveReader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    library_version: u64,
+    classes: HashSet<&'static str>,
+}
+
+impl<'a> ArchiveReader<'a> {
+    /// Read the boost header and flags of the archive
+    fn new(data: &'a [u8]) -> Result<ArchiveReader<'a>, Error> {
+        let mut archive = ArchiveReader {
+            data,
+            big_endian: false,
+            library_version: 0,
+            classes: HashSet::new(),
+        };
+        let len = archive.int()? as usize;
+        if archive.take(len)? != BOOST_ARCHIVE_SIGNATURE.as_bytes() {
+            return Err(Error::InvalidEncoding);
+        }
+        archive.library_version = archive.int()?;
+        archive.big_endian = archive.take(1)?[0] & 0x40 != 0;
+        Ok(archive)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.data.len() {
+            return Err(Error::InvalidEncoding);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn int(&mut self) -> Result<u64, Error> {
+        let size = self.take(1)?[0] as usize;
+        // Negative sizes are negative integers
+        if size > 8 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(self.take(size)?);
+        if self.big_endian {
+            bytes[..size].reverse();
+        }
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Read the tracking level and version of the class on its first object
+    fn class(&mut self, name: &'static str) -> Result<(), Error> {
+        if self.classes.insert(name) {
+            if self.int()? != 0 {
+                return Err(Error::InvalidEncoding);
+            }
+            self.int()?;
+        }
+        Ok(())
+    }
+
+    /// Read the size of a collection, followed by an item version since the library version 4
+    fn collection(&mut self, name: &'static str) -> Result<u64, Error> {
+        self.class(name)?;
+        let len = self.int()?;
+        if self.library_version > 3 {
+            self.int()?;
+        }
+        Ok(len)
+    }
+
+    fn array(&mut self, name: &'static str, len: usize) -> Result<&'a [u8], Error> {
+        self.class(name)?;
+        if self.int()? != len as u64 {
+            return Err(Error::InvalidEncoding);
+        }
+        self.take(len)
+    }
+
+    fn hash(&mut self, name: &'static str) -> Result<Hash, Error> {
+        Ok(Hash::from_slice(self.array(name, 32)?))
+    }
+
+    fn public_key(&mut self) -> Result<PublicKey, Error> {
+        PublicKey::from_slice(self.array("public_key", 32)?).map_err(|_| Error::InvalidEncoding)
+    }
+
+    fn signature(&mut self) -> Result<Signature, Error> {
+        let bytes = self.array("signature", 64)?;
+        let mut c = [0u8; 32];
+        let mut r = [0u8; 32];
+        c.copy_from_slice(&bytes[..32]);
+        r.copy_from_slice(&bytes[32..]);
+        Ok(Signature {
+            c: Key { key: c },
+            r: Key { key: r },
+        })
+    }
+}
+
+/// Hash of the message, the address and the key images, the message signed by the proof
+fn prefix_hash(
+    message: &str,
+    spend: &PublicKey,
+    view: &PublicKey,
+    key_images: &[KeyImage],
+) -> Hash {
+    let mut buf = message.as_bytes().to_vec();
+    buf.extend_from_slice(spend.as_bytes());
+    buf.extend_from_slice(view.as_bytes());
+    for key_image in key_images {
+        buf.extend_from_slice(key_image.image.as_bytes());
+    }
+    Hash::hash(&buf)
+}
+
+/// Sign the message with the secret key of `pubkey`
+///
+/// **Same as** `generate_signature` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+fn generate_signature<R: RngCore + CryptoRng>(
+    msg: &Hash,
+    pubkey: &PublicKey,
+    secret: &PrivateKey,
+    rng: &mut R,
+) -> Signature {
+    let k = Scalar::random(rng);
+    let c = challenge(
+        msg,
+        pubkey,
+        &PublicKey::from_private_key(&PrivateKey::from_scalar(k)),
+    );
+    Signature {
+        c: Key { key: c.to_bytes() },
+        r: Key {
+            key: (k - c * secret.scalar).to_bytes(),
+        },
+    }
+}
+
+/// Verify a signature created with [`generate_signature`]
+///
+/// **Same as** `check_signature` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+fn check_signature(msg: &Hash, pubkey: &PublicKey, signature: &Signature) -> bool {
+    let (c, r) = match (
+        Scalar::from_canonical_bytes(signature.c.key),
+        Scalar::from_canonical_bytes(signature.r.key),
+    ) {
+        (Some(c), Some(r)) => (c, r),
+        _ => return false,
+    };
+    let point = match pubkey.point.decompress() {
+        Some(point) => c * point + &r * &ED25519_BASEPOINT_TABLE,
+        None => return false,
+    };
+    let commitment = PublicKey {
+        point: point.compress(),
+    };
+    challenge(msg, pubkey, &commitment) == c
+}
+
+/// Compute the challenge `Hs(msg || P || R)`
+fn challenge(msg: &Hash, pubkey: &PublicKey, commitment: &PublicKey) -> Scalar {
+    let buf = [msg.as_bytes(), pubkey.as_bytes(), commitment.as_bytes()].concat();
+    Hash::hash_to_scalar(&buf).scalar
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use base58_monero::base58;
+
+    use super::{Error, ReserveAmounts, ReserveProof, ReserveProofEntry, SpendKeySignature};
+    use crate::blockdata::transaction::KeyImage;
+    use crate::cryptonote::hash::{Hash, Hashable};
+    use crate::network::Network;
+    use crate::test_utils::{keys, miner_tx};
+    use crate::util::address::Address;
+    use crate::util::key::{PublicKey, ViewPair};
+    use crate::util::ringct::{Key, Signature};
+
+    #[test]
+    fn reserve_proof() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let keys = keys();
+        let viewpair = ViewPair::from(&keys);
+        let address = Address::from_keypair(Network::Mainnet, &keys);
+        let txs = vec![
+            miner_tx(address, 10, 600_000_000_000).tx,
+            miner_tx(address, 11, 700_000_000_000).tx,
+        ];
+        let outputs: Vec<_> = txs
+            .iter()
+            .map(|tx| {
+                let mut owned = tx.prefix.check_outputs(&viewpair, 0..1, 0..1).unwrap();
+                (tx.hash(), owned.remove(0))
+            })
+            .collect();
+
+        let proof = ReserveProof::generate(&keys, &outputs, "audit", &mut rng);
+        assert_eq!(1, proof.spend_key_signatures.len());
+        let proof = ReserveProof::from_str(&proof.to_string()).unwrap();
+        let mut spent = HashSet::new();
+        assert_eq!(
+            Ok(ReserveAmounts {
+                total: 1_300_000_000_000,
+                spent: 0
+            }),
+            proof.verify(&address, "audit", &txs, &spent)
+        );
+        spent.insert(KeyImage::new(&outputs[1].1.recover_key(&keys)));
+        assert_eq!(
+            Ok(ReserveAmounts {
+                total: 1_300_000_000_000,
+                spent: 700_000_000_000
+            }),
+            proof.verify(&address, "audit", &txs, &spent)
+        );
+
+        assert_eq!(
+            Err(Error::InvalidSpendKeySignature),
+            proof.verify(&address, "other audit", &txs, &spent)
+        );
+        assert_eq!(
+            Err(Error::MissingTransaction(txs[1].hash())),
+            proof.verify(&address, "audit", &txs[..1], &spent)
+        );
+
+        // The spend key signature must cover the outputs
+        let mut unsigned = proof.clone();
+        unsigned.spend_key_signatures.clear();
+        assert_eq!(
+            Err(Error::MissingSpendKeySignature(0)),
+            unsigned.verify(&address, "audit", &txs, &spent)
+        );
+        let mut swapped = proof.clone();
+        swapped.entries[0].key_image_sig = swapped.entries[1].key_image_sig.clone();
+        assert_eq!(
+            Err(Error::InvalidKeyImage(0)),
+            swapped.verify(&address, "audit", &txs, &spent)
+        );
+        let mut swapped = proof.clone();
+        swapped.entries[1].shared_secret = swapped.entries[0].shared_secret;
+        assert_eq!(
+            Err(Error::InvalidSharedSecret(1)),
+            swapped.verify(&address, "audit", &txs, &spent)
+        );
+
+        // An output cannot be counted twice
+        let mut duplicated = proof.clone();
+        duplicated.entries.push(duplicated.entries[0].clone());
+        assert_eq!(
+            Err(Error::DuplicateEntry(2)),
+            duplicated.verify(&address, "audit", &txs, &spent)
+        );
+        let mut duplicated = proof;
+        duplicated.entries[1].key_image = duplicated.entries[0].key_image.clone();
+        assert_eq!(
+            Err(Error::DuplicateEntry(1)),
+            duplicated.verify(&address, "audit", &txs, &spent)
+        );
+    }
+
+    #[test]
+    fn amount_overflow() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let keys = keys();
+        let viewpair = ViewPair::from(&keys);
+        let address = Address::from_keypair(Network::Mainnet, &keys);
+        let txs = vec![
+            miner_tx(address, 10, u64::MAX).tx,
+            miner_tx(address, 11, 1).tx,
+        ];
+        let outputs: Vec<_> = txs
+            .iter()
+            .map(|tx| {
+                let mut owned = tx.prefix.check_outputs(&viewpair, 0..1, 0..1).unwrap();
+                (tx.hash(), owned.remove(0))
+            })
+            .collect();
+
+        let proof = ReserveProof::generate(&keys, &outputs, "", &mut rng);
+        assert_eq!(
+            Err(Error::AmountOverflow),
+            proof.verify(&address, "", &txs, &HashSet::new())
+        );
+    }
+
+    #[test]
+    fn archive_layout() {
+        let key = PublicKey::from_private_key(&keys().spend);
+        let signature = Signature {
+            c: Key { key: [1; 32] },
+            r: Key { key: [2; 32] },
+        };
+        let proof = ReserveProof {
+            entries: vec![ReserveProofEntry {
+                txid: Hash::from([3; 32]),
+                index_in_tx: 0x0102,
+                shared_secret: key,
+                key_image: KeyImage {
+                    image: Hash::from([4; 32]),
+                },
+                shared_secret_sig: signature.clone(),
+                key_image_sig: signature.clone(),
+            }],
+            spend_key_signatures: vec![SpendKeySignature {
+                spend: key,
+                signature: signature.clone(),
+            }],
+        };
+        let encoded = proof.to_string();
+        let data = base58::decode(encoded.strip_prefix("ReserveProofV2").unwrap()).unwrap();
+
+        let sig = [&[0x01, 0x40][..], &[1; 32], &[2; 32]].concat();
+        let key = [&[0x01, 0x20][..], key.as_bytes()].concat();
+        let expected = [
+            // Signature, library version and flags
+            &[0x01, 0x16][..],
+            b"serialization::archive",
+            &[0x01, 0x0c, 0x00],
+            // Vector class, size and item version
+            &[0x00, 0x00, 0x01, 0x01, 0x00],
+            // Entry class, then each class before its first object
+            &[0x00, 0x00],
+            &[0x00, 0x00, 0x01, 0x20],
+            &[3; 32],
+            &[0x02, 0x02, 0x01],
+            &[0x00, 0x00],
+            &key,
+            &[0x00, 0x00, 0x01, 0x20],
+            &[4; 32],
+            &[0x00, 0x00],
+            &sig,
+            &sig,
+            // Map class, size and pairs
+            &[0x00, 0x00, 0x01, 0x01],
+            &key,
+            &sig,
+        ]
+        .concat();
+        assert_eq!(expected, data);
+
+        let decoded = ReserveProof::from_str(&encoded).unwrap();
+        assert_eq!(0x0102, decoded.entries[0].index_in_tx);
+        assert_eq!(proof.to_string(), decoded.to_string());
+
+        // Integers of big endian archives are reversed
+        let mut big_endian = expected.clone();
+        big_endian[26] = 0x40;
+        big_endian.swap(71, 72);
+        let encoded = format!("ReserveProofV2{}", base58::encode(&big_endian).unwrap());
+        let decoded = ReserveProof::from_str(&encoded).unwrap();
+        assert_eq!(0x0102, decoded.entries[0].index_in_tx);
+
+        // Trailing and missing bytes are rejected
+        let trailing = [&expected[..], &[0]].concat();
+        for data in [&trailing[..], &expected[..expected.len() - 1]].iter() {
+            let encoded = format!("ReserveProofV2{}", base58::encode(data).unwrap());
+            assert_eq!(
+                Err(Error::InvalidEncoding),
+                ReserveProof::from_str(&encoded).map(|_| ())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_proofs() {
+        assert_eq!(
+            Err(Error::InvalidHeader),
+            ReserveProof::from_str("SpendProofV1").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::UnsupportedVersion),
+            ReserveProof::from_str("ReserveProofV1").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            ReserveProof::from_str("ReserveProofV2").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            ReserveProof::from_str("ReserveProofV20OIl").map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,451 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Spend proofs
+//!
+//! A spend proof shows that the creator of a transaction knows the one-time secret keys of the
+//! spent outputs. For each input it holds a ring signature, over the transaction id and an
+//! optional message, of the input key image with the ring members of the input.
+//!
+//! The ring members are not part of the transaction, only their global output indexes are, the
+//! caller provides the ring public keys of each input in the order of its key offsets, for
+//! instance from a local copy of the blockchain.
+//!
+//! **Same as** `get_spend_proof` and `check_spend_proof` in [`wallet2.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet2.cpp)
+//!
+
+use std::fmt;
+use std::str::FromStr;
+
+use base58_monero::base58;
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use thiserror::Error;
+
+use crate::blockdata::transaction::{KeyImage, Transaction, TxIn};
+use crate::cryptonote::hash::{Hash, Hashable};
+use crate::util::key::{PrivateKey, PublicKey};
+use crate::util::ringct::{Key, Signature};
+
+/// Header of the spend proofs
+pub const SPEND_PROOF_HEADER: &str = "SpendProofV1";
+
+/// Length of a base58 encoded signature
+const SIGNATURE_LEN: usize = 88;
+
+/// Possible errors when creating, parsing or verifying spend proofs
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// The proof does not start with the spend proof header
+    #[error("invalid proof header")]
+    InvalidHeader,
+    /// The proof is not a valid list of base58 signatures
+    #[error("invalid proof encoding")]
+    InvalidEncoding,
+    /// The transaction has an input that does not spend a key output
+    #[error("input {0} is not a key input")]
+    NotKeyInput(usize),
+    /// The number of rings or secret keys does not match the number of inputs
+    #[error("expected {expected} rings or keys, found {found}")]
+    InputCountMismatch {
+        /// Number of inputs
+        expected: usize,
+        /// Number of rings or secret keys
+        found: usize,
+    },
+    /// The ring size does not match the number of key offsets of the input
+    #[error("input {input} has a ring of {expected} members, found {found}")]
+    RingSizeMismatch {
+        /// Index of the input
+        input: usize,
+        /// Number of key offsets
+        expected: usize,
+        /// Number of ring members
+        found: usize,
+    },
+    /// The secret key does not match the key image or a ring member of the input
+    #[error("secret key does not spend input {0}")]
+    InvalidSecretKey(usize),
+    /// The number of signatures does not match the ring sizes
+    #[error("expected {expected} signatures, found {found}")]
+    SignatureCountMismatch {
+        /// Sum of the ring sizes
+        expected: usize,
+        /// Number of signatures
+        found: usize,
+    },
+    /// The ring signature of an input is invalid
+    #[error("invalid signature for input {0}")]
+    InvalidSignature(usize),
+}
+
+/// A spend proof, the ring signatures of all inputs one after the other
+#[derive(Debug, Clone)]
+pub struct SpendProof {
+    /// Signatures of the ring members, input by input
+    pub signatures: Vec<Signature>,
+}
+
+impl SpendProof {
+    /// Create a spend proof of the transaction with the rings and the one-time secret keys of
+    /// its inputs
+    pub fn generate<R: RngCore + CryptoRng>(
+        tx: &Transaction,
+        message: &str,
+        rings: &[Vec<PublicKey>],
+        secrets: &[PrivateKey],
+        rng: &mut R,
+    ) -> Result<SpendProof, Error> {
+        let inputs = key_inputs(tx, rings)?;
+        if secrets.len() != inputs.len() {
+            return Err(Error::InputCountMismatch {
+                expected: inputs.len(),
+                found: secrets.len(),
+            });
+        }
+        let msg = prefix_hash(&tx.hash(), message);
+        let mut signatures = vec![];
+        for (i, ((key_image, ring), secret)) in
+            inputs.into_iter().zip(rings).zip(secrets).enumerate()
+        {
+            let pubkey = PublicKey::from_private_key(secret);
+            let real_index = ring
+                .iter()
+                .position(|key| *key == pubkey)
+                .ok_or(Error::InvalidSecretKey(i))?;
+            if KeyImage::new(secret) != *key_image {
+                return Err(Error::InvalidSecretKey(i));
+            }
+            signatures.extend(generate_ring_signature(
+                &msg, key_image, ring, secret, real_index, rng,
+            ));
+        }
+        Ok(SpendProof { signatures })
+    }
+
+    /// Verify the spend proof of the transaction with the rings of its inputs
+    pub fn verify(
+        &self,
+        tx: &Transaction,
+        message: &str,
+        rings: &[Vec<PublicKey>],
+    ) -> Result<(), Error> {
+        let inputs = key_inputs(tx, rings)?;
+        let expected = rings.iter().map(Vec::len).sum();
+        if self.signatures.len() != expected {
+            return Err(Error::SignatureCountMismatch {
+                expected,
+                found: self.signatures.len(),
+            });
+        }
+        let msg = prefix_hash(&tx.hash(), message);
+        let mut signatures = &self.signatures[..];
+        for (i, (key_image, ring)) in inputs.into_iter().zip(rings).enumerate() {
+            let (ring_signature, rest) = signatures.split_at(ring.len());
+            if !check_ring_signature(&msg, key_image, ring, ring_signature) {
+                return Err(Error::InvalidSignature(i));
+            }
+            signatures = rest;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SpendProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", SPEND_PROOF_HEADER)?;
+        for signature in &self.signatures {
+            let signature = [signature.c.key, signature.r.key].concat();
+            write!(f, "{}", base58::encode(&signature).map_err(|_| fmt::Error)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SpendProof {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SpendProof, Error> {
+        let data = s
+            .strip_prefix(SPEND_PROOF_HEADER)
+            .ok_or(Error::InvalidHeader)?;
+        if data.is_empty() || !data.is_ascii() || data.len() % SIGNATURE_LEN != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let signatures = data
+            .as_bytes()
+            .chunks(SIGNATURE_LEN)
+            .map(|chunk| {
+                let chunk = std::str::from_utf8(chunk).map_err(|_| Error::InvalidEncoding)?;
+                decode_signature(chunk)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SpendProof { signatures })
+    }
+}
+
+/// Decode a base58 encoded `c || r` signature
+fn decode_signature(s: &str) -> Result<Signature, Error> {
+    let bytes = base58::decode(s).map_err(|_| Error::InvalidEncoding)?;
+    if bytes.len() != 64 {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut c = [0u8; 32];
+    let mut r = [0u8; 32];
+    c.copy_from_slice(&bytes[..32]);
+    r.copy_from_slice(&bytes[32..]);
+    Ok(Signature {
+        c: Key { key: c },
+        r: Key { key: r },
+    })
+}
+
+/// Hash of the transaction id and the message, the message signed by the proof
+fn prefix_hash(txid: &Hash, message: &str) -> Hash {
+    Hash::hash(&[txid.as_bytes(), message.as_bytes()].concat())
+}
+
+/// Return the key images of the transaction inputs, checking that the rings match the inputs
+fn key_inputs<'a>(
+    tx: &'a Transaction,
+    rings: &[Vec<PublicKey>],
+) -> Result<Vec<&'a KeyImage>, Error> {
+    if rings.len() != tx.prefix.inputs.len() {
+        return Err(Error::InputCountMismatch {
+            expected: tx.prefix.inputs.len(),
+            found: rings.len(),
+        });
+    }
+    tx.prefix
+        .inputs
+        .iter()
+        .zip(rings)
+        .enumerate()
+        .map(|(i, (input, ring))| match input {
+            TxIn::ToKey {
+                key_offsets,
+                k_image,
+                ..
+            } => {
+                if key_offsets.len() != ring.len() {
+                    return Err(Error::RingSizeMismatch {
+                        input: i,
+                        expected: key_offsets.len(),
+                        found: ring.len(),
+                    });
+                }
+                Ok(k_image)
+            }
+            _ => Err(Error::NotKeyInput(i)),
+        })
+        .collect()
+}
+
+/// Create a ring signature of the key image, `secret` being the secret key of the ring member at
+/// `real_index`
+///
+/// **Same as** `generate_ring_signature` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+pub(crate) fn generate_ring_signature<R: RngCore + CryptoRng>(
+    msg: &Hash,
+    key_image: &KeyImage,
+    ring: &[PublicKey],
+    secret: &PrivateKey,
+    real_index: usize,
+    rng: &mut R,
+) -> Vec<Signature> {
+    let image = key_image
+        .decompress()
+        .expect("The key image is computed from the secret key. QED");
+    let mut buf = msg.as_bytes().to_vec();
+    let mut scalars = vec![(Scalar::zero(), Scalar::zero()); ring.len()];
+    let mut sum = Scalar::zero();
+    let mut k = Scalar::zero();
+    for (i, key) in ring.iter().enumerate() {
+        let hp = Hash::hash_to_point(key.as_bytes());
+        let (a, b) = if i == real_index {
+            k = Scalar::random(rng);
+            (&k * &ED25519_BASEPOINT_TABLE, k * hp)
+        } else {
+            let c = Scalar::random(rng);
+            let r = Scalar::random(rng);
+            sum += c;
+            scalars[i] = (c, r);
+            (
+                c * decompress(key) + &r * &ED25519_BASEPOINT_TABLE,
+                r * hp + c * image,
+            )
+        };
+        buf.extend_from_slice(a.compress().as_bytes());
+        buf.extend_from_slice(b.compress().as_bytes());
+    }
+    let c = Hash::hash_to_scalar(&buf).scalar - sum;
+    scalars[real_index] = (c, k - c * secret.scalar);
+    scalars
+        .into_iter()
+        .map(|(c, r)| Signature {
+            c: Key { key: c.to_bytes() },
+            r: Key { key: r.to_bytes() },
+        })
+        .collect()
+}
+
+/// Verify a ring signature of the key image
+///
+/// **Same as** `check_ring_signature` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+pub(crate) fn check_ring_signature(
+    msg: &Hash,
+    key_image: &KeyImage,
+    ring: &[PublicKey],
+    signatures: &[Signature],
+) -> bool {
+    let image = match key_image.decompress() {
+        Some(image) if image.is_torsion_free() => image,
+        _ => return false,
+    };
+    let mut buf = msg.as_bytes().to_vec();
+    let mut sum = Scalar::zero();
+    for (key, signature) in ring.iter().zip(signatures) {
+        let (c, r) = match (
+            Scalar::from_canonical_bytes(signature.c.key),
+            Scalar::from_canonical_bytes(signature.r.key),
+        ) {
+            (Some(c), Some(r)) => (c, r),
+            _ => return false,
+        };
+        let a = c * decompress(key) + &r * &ED25519_BASEPOINT_TABLE;
+        let b = r * Hash::hash_to_point(key.as_bytes()) + c * image;
+        buf.extend_from_slice(a.compress().as_bytes());
+        buf.extend_from_slice(b.compress().as_bytes());
+        sum += c;
+    }
+    Hash::hash_to_scalar(&buf).scalar == sum
+}
+
+fn decompress(key: &PublicKey) -> EdwardsPoint {
+    key.point
+        .decompress()
+        .expect("PublicKey Can only be created if a valid point is found. QED")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{Error, SpendProof};
+    use crate::blockdata::transaction::{KeyImage, Transaction, TxIn};
+    use crate::consensus::encode::VarInt;
+    use crate::cryptonote::hash::Hash;
+    use crate::util::key::{PrivateKey, PublicKey};
+
+    fn spend(secrets: &[PrivateKey], ring_size: usize) -> (Transaction, Vec<Vec<PublicKey>>) {
+        let mut tx = Transaction::default();
+        tx.prefix.version = VarInt(2);
+        let mut rings = vec![];
+        for (i, secret) in secrets.iter().enumerate() {
+            let mut ring: Vec<PublicKey> = (0..ring_size)
+                .map(|j| PublicKey::from_private_key(&Hash::hash(&[i as u8, j as u8]).as_scalar()))
+                .collect();
+            ring[i % ring_size] = PublicKey::from_private_key(secret);
+            tx.prefix.inputs.push(TxIn::ToKey {
+                amount: VarInt(0),
+                key_offsets: (0..ring_size as u64).map(VarInt).collect(),
+                k_image: KeyImage::new(secret),
+            });
+            rings.push(ring);
+        }
+        (tx, rings)
+    }
+
+    #[test]
+    fn spend_proof() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let secrets = vec![
+            Hash::hash(b"input 0").as_scalar(),
+            Hash::hash(b"input 1").as_scalar(),
+        ];
+        let (tx, rings) = spend(&secrets, 11);
+        assert!(tx.prefix.key_images().all(KeyImage::is_torsion_free));
+
+        let proof = SpendProof::generate(&tx, "message", &rings, &secrets, &mut rng).unwrap();
+        let encoded = proof.to_string();
+        assert_eq!("SpendProofV1".len() + 22 * 88, encoded.len());
+        let proof = SpendProof::from_str(&encoded).unwrap();
+        assert_eq!(Ok(()), proof.verify(&tx, "message", &rings));
+        assert_eq!(
+            Err(Error::InvalidSignature(0)),
+            proof.verify(&tx, "other message", &rings)
+        );
+
+        let mut other_rings = rings.clone();
+        other_rings[1].swap(0, 1);
+        assert_eq!(
+            Err(Error::InvalidSignature(1)),
+            proof.verify(&tx, "message", &other_rings)
+        );
+        assert_eq!(
+            Err(Error::InputCountMismatch {
+                expected: 2,
+                found: 1
+            }),
+            proof.verify(&tx, "message", &rings[..1])
+        );
+        other_rings[1].pop();
+        assert_eq!(
+            Err(Error::RingSizeMismatch {
+                input: 1,
+                expected: 11,
+                found: 10
+            }),
+            proof.verify(&tx, "message", &other_rings)
+        );
+
+        let (other_tx, other_rings) = spend(&secrets, 10);
+        assert_eq!(
+            Err(Error::SignatureCountMismatch {
+                expected: 20,
+                found: 22
+            }),
+            proof.verify(&other_tx, "message", &other_rings)
+        );
+
+        // The secret keys must match the key images
+        let wrong_secrets = vec![secrets[0], Hash::hash(b"input 2").as_scalar()];
+        assert_eq!(
+            Err(Error::InvalidSecretKey(1)),
+            SpendProof::generate(&tx, "", &rings, &wrong_secrets, &mut rng).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn invalid_proofs() {
+        assert_eq!(
+            Err(Error::InvalidHeader),
+            SpendProof::from_str("OutProofV2").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            SpendProof::from_str("SpendProofV1").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            SpendProof::from_str(&format!("SpendProofV1{}", "1".repeat(87))).map(|_| ())
+        );
+    }
+}
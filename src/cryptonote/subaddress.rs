@@ -28,9 +28,12 @@ use crate::cryptonote::hash;
 use crate::network::Network;
 use crate::util::address::Address;
 use crate::util::key::{KeyPair, PrivateKey, PublicKey, ViewPair};
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
 
 /// A subaddress index with `major` and `minor` indexes
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct Index {
     /// The major index, also account
     pub major: u32,
@@ -0,0 +1,589 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Transaction proofs
+//!
+//! A transaction proof shows that a transaction pays an address. The sender creates an
+//! `OutProofV2` with the transaction secret keys `r`, the recipient creates an `InProofV2` with
+//! its view secret key `a`. For the transaction public key and each additional public key the
+//! proof holds the shared secret `D = r*A = a*R` and a proof that `D` and `R` (or `A`) are built
+//! with the same secret. The verifier recomputes the key derivations `8*D` to find the outputs of
+//! the address and decrypt the received amount.
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use monero::blockdata::coinbase::MinerTxBuilder;
+//! use monero::cryptonote::hash::Hashable;
+//! use monero::cryptonote::tx_proof::TxProof;
+//! use monero::{Address, PrivateKey};
+//!
+//! let address = Address::from_str("4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra").unwrap();
+//! let tx_key = PrivateKey::from_str("77916d0cd56ed1920aef6ca56d8a41bac915b68e4c46a589e0956e27a7b77404").unwrap();
+//! let tx = MinerTxBuilder::new(2_200_000, 1_000_000_000_000, address, tx_key)
+//!     .build()
+//!     .unwrap()
+//!     .tx;
+//!
+//! let proof = TxProof::generate_out(&tx.hash(), &address, "invoice 42", &tx_key, &[], &mut rand::thread_rng());
+//! let proof = TxProof::from_str(&proof.to_string()).unwrap();
+//! assert_eq!(Ok(1_000_000_000_000), proof.verify(&tx, &address, "invoice 42"));
+//! ```
+//!
+//! **Same as** `get_tx_proof` and `check_tx_proof` in [`wallet2.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet2.cpp)
+//!
+
+use std::fmt;
+use std::str::FromStr;
+
+use base58_monero::base58;
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::Scalar;
+use rand_core::{CryptoRng, RngCore};
+use thiserror::Error;
+
+use crate::blockdata::transaction::{Transaction, TxOutTarget};
+use crate::cryptonote::hash::{Hash, Hashable};
+use crate::cryptonote::onetime_key::KeyGenerator;
+use crate::util::address::{Address, AddressType};
+use crate::util::key::{PrivateKey, PublicKey};
+use crate::util::ringct::{Key, Signature};
+
+/// Header of the proofs created by the sender
+pub const OUT_PROOF_HEADER: &str = "OutProofV2";
+/// Header of the proofs created by the recipient
+pub const IN_PROOF_HEADER: &str = "InProofV2";
+
+/// Domain separator of the version 2 proofs
+///
+/// **Same as** `HASH_KEY_TXPROOF_V2` in `cryptonote_config.h`
+const HASH_KEY_TXPROOF_V2: &[u8] = b"TXPROOF_V2";
+
+/// Length of a base58 encoded shared secret
+const SHARED_SECRET_LEN: usize = 44;
+/// Length of a base58 encoded signature
+const SIGNATURE_LEN: usize = 88;
+
+/// Possible errors when parsing or verifying transaction proofs
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// The proof does not start with a known header
+    #[error("invalid proof header")]
+    InvalidHeader,
+    /// Version 1 proofs are not supported
+    #[error("unsupported proof version")]
+    UnsupportedVersion,
+    /// The proof is not a valid list of base58 shared secrets and signatures
+    #[error("invalid proof encoding")]
+    InvalidEncoding,
+    /// The transaction has no public key
+    #[error("no transaction public key")]
+    NoTxPublicKey,
+    /// The number of signatures does not match the number of transaction public keys
+    #[error("expected {expected} signatures, found {found}")]
+    SignatureCountMismatch {
+        /// Number of transaction public keys
+        expected: usize,
+        /// Number of signatures
+        found: usize,
+    },
+    /// No signature is valid
+    #[error("invalid signature")]
+    InvalidSignature,
+    /// The decrypted amount of a received output does not open the output commitment
+    #[error("invalid amount for output {0}")]
+    InvalidAmount(usize),
+    /// The received amounts overflow
+    #[error("amount overflow")]
+    AmountOverflow,
+}
+
+/// Creator of a transaction proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxProofType {
+    /// Created by the sender with the transaction secret keys
+    Out,
+    /// Created by the recipient with its view secret key
+    In,
+}
+
+/// A transaction proof, one shared secret and signature for the transaction public key and each
+/// additional public key
+#[derive(Debug, Clone)]
+pub struct TxProof {
+    /// Creator of the proof
+    pub proof_type: TxProofType,
+    /// Shared secrets `D = r*A = a*R`
+    pub shared_secrets: Vec<PublicKey>,
+    /// Signatures proving the shared secrets
+    pub signatures: Vec<Signature>,
+}
+
+impl TxProof {
+    /// Create a proof, as the sender, that the transaction `txid` pays `address`, with the
+    /// transaction secret key and the additional transaction secret keys, if any
+    pub fn generate_out<R: RngCore + CryptoRng>(
+        txid: &Hash,
+        address: &Address,
+        message: &str,
+        tx_key: &PrivateKey,
+        additional_tx_keys: &[PrivateKey],
+        rng: &mut R,
+    ) -> TxProof {
+        let msg = prefix_hash(txid, message);
+        let b = subaddress_spend(address);
+        let mut shared_secrets = vec![];
+        let mut signatures = vec![];
+        for r in std::iter::once(tx_key).chain(additional_tx_keys) {
+            let d = *r * &address.public_view;
+            // With a subaddress the transaction public key is `r*B`
+            let tx_pubkey = match b {
+                Some(spend) => *r * spend,
+                None => PublicKey::from_private_key(r),
+            };
+            let signature =
+                generate_tx_proof(&msg, &tx_pubkey, &address.public_view, b, &d, r, rng);
+            shared_secrets.push(d);
+            signatures.push(signature);
+        }
+        TxProof {
+            proof_type: TxProofType::Out,
+            shared_secrets,
+            signatures,
+        }
+    }
+
+    /// Create a proof, as the recipient, that the transaction pays `address` with the view secret
+    /// key of the address
+    pub fn generate_in<R: RngCore + CryptoRng>(
+        tx: &Transaction,
+        address: &Address,
+        view: &PrivateKey,
+        message: &str,
+        rng: &mut R,
+    ) -> Result<TxProof, Error> {
+        let msg = prefix_hash(&tx.hash(), message);
+        let b = subaddress_spend(address);
+        let mut shared_secrets = vec![];
+        let mut signatures = vec![];
+        for tx_pubkey in tx_pubkeys(tx)? {
+            let d = *view * &tx_pubkey;
+            let signature =
+                generate_tx_proof(&msg, &address.public_view, &tx_pubkey, b, &d, view, rng);
+            shared_secrets.push(d);
+            signatures.push(signature);
+        }
+        Ok(TxProof {
+            proof_type: TxProofType::In,
+            shared_secrets,
+            signatures,
+        })
+    }
+
+    /// Verify that the transaction pays `address` and return the received amount
+    ///
+    /// The proof is valid if at least one signature is valid, only the outputs of the valid
+    /// shared secrets are counted. An error is returned if the amount of one of these outputs
+    /// does not open its commitment or if the total amount overflows.
+    pub fn verify(&self, tx: &Transaction, address: &Address, message: &str) -> Result<u64, Error> {
+        let tx_pubkeys = tx_pubkeys(tx)?;
+        if self.signatures.len() != tx_pubkeys.len()
+            || self.shared_secrets.len() != tx_pubkeys.len()
+        {
+            return Err(Error::SignatureCountMismatch {
+                expected: tx_pubkeys.len(),
+                found: self.signatures.len(),
+            });
+        }
+
+        let msg = prefix_hash(&tx.hash(), message);
+        let b = subaddress_spend(address);
+        // Key generators from the derivations `8*D` of the valid signatures
+        let generators: Vec<Option<KeyGenerator>> = tx_pubkeys
+            .iter()
+            .zip(&self.shared_secrets)
+            .zip(&self.signatures)
+            .map(|((tx_pubkey, d), signature)| {
+                let valid = match self.proof_type {
+                    TxProofType::Out => {
+                        check_tx_proof(&msg, tx_pubkey, &address.public_view, b, d, signature)
+                    }
+                    TxProofType::In => {
+                        check_tx_proof(&msg, &address.public_view, tx_pubkey, b, d, signature)
+                    }
+                };
+                if valid {
+                    Some(KeyGenerator {
+                        spend: address.public_spend,
+                        rv: PrivateKey::from_scalar(Scalar::from(8u8)) * d,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if generators.iter().all(Option::is_none) {
+            return Err(Error::InvalidSignature);
+        }
+
+        let mut received: u64 = 0;
+        for (index, output) in tx.prefix.outputs.iter().enumerate() {
+            let key = match output.target {
+                TxOutTarget::ToKey { key } => key,
+                _ => continue,
+            };
+            // Outputs use the main derivation or the additional derivation of their index
+            let found = [
+                generators[0].as_ref(),
+                generators.get(index + 1).and_then(Option::as_ref),
+            ]
+            .iter()
+            .flatten()
+            .find(|generator| generator.check(index, key))
+            .cloned()
+            .cloned();
+            if let Some(generator) = found {
+                let shared_key = generator.get_rvn_scalar(index);
+                let amount = tx
+                    .output_amount(index, &shared_key)
+                    .ok_or(Error::InvalidAmount(index))?;
+                received = received.checked_add(amount).ok_or(Error::AmountOverflow)?;
+            }
+        }
+        Ok(received)
+    }
+}
+
+impl fmt::Display for TxProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header = match self.proof_type {
+            TxProofType::Out => OUT_PROOF_HEADER,
+            TxProofType::In => IN_PROOF_HEADER,
+        };
+        write!(f, "{}", header)?;
+        for (d, signature) in self.shared_secrets.iter().zip(&self.signatures) {
+            let signature = [signature.c.key, signature.r.key].concat();
+            let d = base58::encode(d.as_bytes()).map_err(|_| fmt::Error)?;
+            let signature = base58::encode(&signature).map_err(|_| fmt::Error)?;
+            write!(f, "{}{}", d, signature)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TxProof {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<TxProof, Error> {
+        let (proof_type, data) = if let Some(data) = s.strip_prefix(OUT_PROOF_HEADER) {
+            (TxProofType::Out, data)
+        } else if let Some(data) = s.strip_prefix(IN_PROOF_HEADER) {
+            (TxProofType::In, data)
+        } else if s.starts_with("OutProofV1") || s.starts_with("InProofV1") {
+            return Err(Error::UnsupportedVersion);
+        } else {
+            return Err(Error::InvalidHeader);
+        };
+
+        let len = SHARED_SECRET_LEN + SIGNATURE_LEN;
+        if data.is_empty() || !data.is_ascii() || data.len() % len != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut shared_secrets = vec![];
+        let mut signatures = vec![];
+        for chunk in data.as_bytes().chunks(len) {
+            // The data is ASCII, chunks are valid strings
+            let chunk = std::str::from_utf8(chunk).map_err(|_| Error::InvalidEncoding)?;
+            let d =
+                base58::decode(&chunk[..SHARED_SECRET_LEN]).map_err(|_| Error::InvalidEncoding)?;
+            let d = PublicKey::from_slice(&d).map_err(|_| Error::InvalidEncoding)?;
+            let signature =
+                base58::decode(&chunk[SHARED_SECRET_LEN..]).map_err(|_| Error::InvalidEncoding)?;
+            if signature.len() != 64 {
+                return Err(Error::InvalidEncoding);
+            }
+            let mut c = [0u8; 32];
+            let mut r = [0u8; 32];
+            c.copy_from_slice(&signature[..32]);
+            r.copy_from_slice(&signature[32..]);
+            shared_secrets.push(d);
+            signatures.push(Signature {
+                c: Key { key: c },
+                r: Key { key: r },
+            });
+        }
+        Ok(TxProof {
+            proof_type,
+            shared_secrets,
+            signatures,
+        })
+    }
+}
+
+/// Hash of the transaction id and the message, the message signed by the proofs
+fn prefix_hash(txid: &Hash, message: &str) -> Hash {
+    Hash::hash(&[txid.as_bytes(), message.as_bytes()].concat())
+}
+
+/// Return the spend public key of the address if it is a subaddress
+fn subaddress_spend(address: &Address) -> Option<&PublicKey> {
+    match address.addr_type {
+        AddressType::SubAddress => Some(&address.public_spend),
+        _ => None,
+    }
+}
+
+/// Return the transaction public key followed by the additional public keys
+fn tx_pubkeys(tx: &Transaction) -> Result<Vec<PublicKey>, Error> {
+    let mut keys = vec![tx.prefix.extra.tx_pubkey().ok_or(Error::NoTxPublicKey)?];
+    keys.extend(tx.prefix.extra.tx_additional_pubkeys().unwrap_or_default());
+    Ok(keys)
+}
+
+/// Compute the challenge `Hs(msg || D || X || Y || Hs("TXPROOF_V2") || R || A || B)`, `B` being
+/// zero without subaddress
+#[allow(clippy::too_many_arguments)]
+fn challenge(
+    msg: &Hash,
+    r: &PublicKey,
+    a: &PublicKey,
+    b: Option<&PublicKey>,
+    d: &PublicKey,
+    x: &EdwardsPoint,
+    y: &EdwardsPoint,
+) -> Scalar {
+    let mut buf = Vec::with_capacity(8 * 32);
+    buf.extend_from_slice(msg.as_bytes());
+    buf.extend_from_slice(d.as_bytes());
+    buf.extend_from_slice(x.compress().as_bytes());
+    buf.extend_from_slice(y.compress().as_bytes());
+    buf.extend_from_slice(Hash::hash(HASH_KEY_TXPROOF_V2).as_bytes());
+    buf.extend_from_slice(r.as_bytes());
+    buf.extend_from_slice(a.as_bytes());
+    buf.extend_from_slice(b.map_or(&[0u8; 32][..], |b| b.as_bytes()));
+    Hash::hash_to_scalar(&buf).scalar
+}
+
+/// Prove that `R = r*G` (or `R = r*B`) and `D = r*A` share the same secret `r`
+///
+/// **Same as** `generate_tx_proof` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+pub(crate) fn generate_tx_proof<R: RngCore + CryptoRng>(
+    msg: &Hash,
+    r: &PublicKey,
+    a: &PublicKey,
+    b: Option<&PublicKey>,
+    d: &PublicKey,
+    secret: &PrivateKey,
+    rng: &mut R,
+) -> Signature {
+    let k = Scalar::random(rng);
+    let x = match b {
+        Some(b) => k * decompress(b),
+        None => &k * &ED25519_BASEPOINT_TABLE,
+    };
+    let y = k * decompress(a);
+    let c = challenge(msg, r, a, b, d, &x, &y);
+    Signature {
+        c: Key { key: c.to_bytes() },
+        r: Key {
+            key: (k - c * secret.scalar).to_bytes(),
+        },
+    }
+}
+
+/// Verify a proof created with [`generate_tx_proof`]
+///
+/// **Same as** `check_tx_proof` in [`crypto.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/crypto/crypto.cpp)
+pub(crate) fn check_tx_proof(
+    msg: &Hash,
+    r: &PublicKey,
+    a: &PublicKey,
+    b: Option<&PublicKey>,
+    d: &PublicKey,
+    signature: &Signature,
+) -> bool {
+    let (c, s) = match (
+        Scalar::from_canonical_bytes(signature.c.key),
+        Scalar::from_canonical_bytes(signature.r.key),
+    ) {
+        (Some(c), Some(s)) => (c, s),
+        _ => return false,
+    };
+    // X = c*R + s*G (or s*B) and Y = c*D + s*A
+    let x = c * decompress(r)
+        + match b {
+            Some(b) => s * decompress(b),
+            None => &s * &ED25519_BASEPOINT_TABLE,
+        };
+    let y = c * decompress(d) + s * decompress(a);
+    challenge(msg, r, a, b, d, &x, &y) == c
+}
+
+fn decompress(key: &PublicKey) -> EdwardsPoint {
+    key.point
+        .decompress()
+        .expect("PublicKey Can only be created if a valid point is found. QED")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::{Error, TxProof, TxProofType};
+    use crate::blockdata::coinbase::MinerTx;
+    use crate::blockdata::transaction::{SubField, Transaction, TxOut, TxOutTarget};
+    use crate::consensus::encode::VarInt;
+    use crate::cryptonote::hash::{Hash, Hash8, Hashable};
+    use crate::cryptonote::onetime_key::KeyGenerator;
+    use crate::cryptonote::subaddress::{self, Index};
+    use crate::network::Network;
+    use crate::test_utils::{keys, miner_tx};
+    use crate::util::address::Address;
+    use crate::util::key::{PublicKey, ViewPair};
+    use crate::util::ringct::{CtKey, EcdhInfo, Key, RctSigBase, RctType};
+
+    #[test]
+    fn out_and_in_proofs() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let viewpair = ViewPair::from(&keys());
+        let address = Address::from_viewpair(Network::Mainnet, &viewpair);
+        let MinerTx { tx, tx_key, .. } = miner_tx(address, 2_000_000, 600_000_000_000);
+        let txid = tx.hash();
+
+        let proof = TxProof::generate_out(&txid, &address, "message", &tx_key, &[], &mut rng);
+        let encoded = proof.to_string();
+        assert!(encoded.starts_with("OutProofV2"));
+        assert_eq!("OutProofV2".len() + 132, encoded.len());
+        let proof = TxProof::from_str(&encoded).unwrap();
+        assert_eq!(TxProofType::Out, proof.proof_type);
+        assert_eq!(Ok(600_000_000_000), proof.verify(&tx, &address, "message"));
+        assert_eq!(
+            Err(Error::InvalidSignature),
+            proof.verify(&tx, &address, "other message")
+        );
+        let other = Address::from_viewpair(
+            Network::Mainnet,
+            &ViewPair {
+                view: viewpair.view,
+                spend: PublicKey::from_private_key(&tx_key),
+            },
+        );
+        assert_eq!(Ok(0), proof.verify(&tx, &other, "message"));
+
+        let proof = TxProof::generate_in(&tx, &address, &viewpair.view, "", &mut rng).unwrap();
+        let encoded = proof.to_string();
+        assert!(encoded.starts_with("InProofV2"));
+        let proof = TxProof::from_str(&encoded).unwrap();
+        assert_eq!(Ok(600_000_000_000), proof.verify(&tx, &address, ""));
+
+        // A wrong view key does not prove anything
+        let proof = TxProof::generate_in(&tx, &address, &tx_key, "", &mut rng).unwrap();
+        assert_eq!(
+            Err(Error::InvalidSignature),
+            proof.verify(&tx, &address, "")
+        );
+    }
+
+    #[test]
+    fn subaddress_proofs() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let viewpair = ViewPair::from(&keys());
+        let index = Index { major: 0, minor: 1 };
+        let address = subaddress::get_subaddress(&viewpair, index, Some(Network::Mainnet));
+        let tx_key = Hash::hash(b"tx key").as_scalar();
+        // A transaction paying the subaddress, the transaction public key is `r*B`
+        let mut tx = Transaction::default();
+        tx.prefix.version = VarInt(2);
+        tx.prefix.outputs.push(TxOut {
+            amount: VarInt(42),
+            target: TxOutTarget::ToKey {
+                key: KeyGenerator::from_random(address.public_view, address.public_spend, tx_key)
+                    .one_time_key(0),
+            },
+        });
+        tx.prefix
+            .extra
+            .0
+            .push(SubField::TxPublicKey(tx_key * &address.public_spend));
+
+        let proof = TxProof::generate_out(&tx.hash(), &address, "", &tx_key, &[], &mut rng);
+        assert_eq!(Ok(42), proof.verify(&tx, &address, ""));
+        let proof = TxProof::generate_in(&tx, &address, &viewpair.view, "", &mut rng).unwrap();
+        assert_eq!(Ok(42), proof.verify(&tx, &address, ""));
+
+        // The received amounts must not overflow
+        let mut overflow = tx.clone();
+        overflow.prefix.outputs[0].amount = VarInt(u64::MAX);
+        overflow.prefix.outputs.push(TxOut {
+            amount: VarInt(1),
+            target: TxOutTarget::ToKey {
+                key: KeyGenerator::from_random(address.public_view, address.public_spend, tx_key)
+                    .one_time_key(1),
+            },
+        });
+        let proof = TxProof::generate_out(&overflow.hash(), &address, "", &tx_key, &[], &mut rng);
+        assert_eq!(
+            Err(Error::AmountOverflow),
+            proof.verify(&overflow, &address, "")
+        );
+
+        // The amount of a received output must open its commitment
+        let mut rct_tx = tx;
+        rct_tx.prefix.outputs[0].amount = VarInt(0);
+        rct_tx.rct_signatures.sig = Some(RctSigBase {
+            rct_type: RctType::Bulletproof2,
+            txn_fee: VarInt(0),
+            pseudo_outs: vec![],
+            ecdh_info: vec![EcdhInfo::Bulletproof {
+                amount: Hash8([0; 8]),
+            }],
+            out_pk: vec![CtKey {
+                mask: Key { key: [0; 32] },
+            }],
+        });
+        let proof = TxProof::generate_out(&rct_tx.hash(), &address, "", &tx_key, &[], &mut rng);
+        assert_eq!(
+            Err(Error::InvalidAmount(0)),
+            proof.verify(&rct_tx, &address, "")
+        );
+    }
+
+    #[test]
+    fn invalid_proofs() {
+        assert_eq!(
+            Err(Error::InvalidHeader),
+            TxProof::from_str("SpendProofV1").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::UnsupportedVersion),
+            TxProof::from_str("OutProofV1").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            TxProof::from_str("OutProofV2").map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            TxProof::from_str(&format!("InProofV2{}", "1".repeat(131))).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            TxProof::from_str(&format!("InProofV2{}", "0".repeat(132))).map(|_| ())
+        );
+    }
+}
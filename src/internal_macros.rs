@@ -54,3 +54,37 @@ macro_rules! impl_hex_display {
         }
     };
 }
+
+macro_rules! impl_hex_serde {
+    ( $data:ident, $len:expr, $from_slice:expr ) => {
+        #[cfg(feature = "serde_support")]
+        impl serde::Serialize for $data {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&hex::encode(self.as_bytes()))
+                } else {
+                    let mut bytes = [0u8; $len];
+                    bytes.copy_from_slice(self.as_bytes());
+                    serde::Serialize::serialize(&bytes, serializer)
+                }
+            }
+        }
+
+        #[cfg(feature = "serde_support")]
+        impl<'de> serde::Deserialize<'de> for $data {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<$data, D::Error> {
+                use serde::de::Error;
+                if deserializer.is_human_readable() {
+                    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+                    let bytes = hex::decode(s).map_err(D::Error::custom)?;
+                    ($from_slice)(&bytes[..]).map_err(D::Error::custom)
+                } else {
+                    let bytes = <[u8; $len] as serde::Deserialize>::deserialize(deserializer)?;
+                    ($from_slice)(&bytes[..]).map_err(D::Error::custom)
+                }
+            }
+        }
+    };
+}
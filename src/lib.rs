@@ -49,6 +49,12 @@ pub mod consensus;
 pub mod blockdata;
 pub mod cryptonote;
 pub mod network;
+pub mod p2p;
+pub mod portable_storage;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(test)]
+mod test_utils;
 pub mod util;
 
 pub use blockdata::transaction::OwnedTxOut;
@@ -0,0 +1,216 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Levin protocol framing
+//!
+//! Every message exchanged between monerod peers is a bucket: a fixed size header followed by a
+//! body, usually a portable storage. Fragmented buckets, i.e. with the begin and end flags, are
+//! not reassembled.
+//!
+//! ```rust
+//! use monero::p2p::levin::{Bucket, LEVIN_PACKET_REQUEST};
+//!
+//! // Ping request with an empty portable storage body
+//! let body = hex::decode("01110101010102010100").unwrap();
+//! let bucket = Bucket::new(1003, body, LEVIN_PACKET_REQUEST, true);
+//!
+//! let mut bytes = vec![];
+//! bucket.write(&mut bytes).unwrap();
+//! assert_eq!(bucket, Bucket::read(&mut &bytes[..], 1024).unwrap());
+//! ```
+//!
+//! **Same as** [`levin_base.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/contrib/epee/include/net/levin_base.h)
+//!
+
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::Error;
+
+/// Signature starting every levin bucket
+pub const LEVIN_SIGNATURE: u64 = 0x0101_0101_0101_2101;
+/// Levin protocol version
+pub const LEVIN_PROTOCOL_VERSION: u32 = 1;
+/// Flag of a request bucket
+pub const LEVIN_PACKET_REQUEST: u32 = 0x01;
+/// Flag of a response bucket
+pub const LEVIN_PACKET_RESPONSE: u32 = 0x02;
+/// Flag of the first bucket of a fragmented message
+pub const LEVIN_PACKET_BEGIN: u32 = 0x04;
+/// Flag of the last bucket of a fragmented message
+pub const LEVIN_PACKET_END: u32 = 0x08;
+/// Return code of a successful command
+pub const LEVIN_OK: i32 = 0;
+/// Default maximum size of a bucket body accepted by monerod
+pub const LEVIN_DEFAULT_MAX_PACKET_SIZE: u64 = 100_000_000;
+/// Size of a serialized bucket header
+pub const BUCKET_HEADER_SIZE: usize = 33;
+
+/// Levin bucket header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketHeader {
+    /// Size of the body in bytes
+    pub size: u64,
+    /// Whether the sender expects a response
+    pub have_to_return_data: bool,
+    /// Command identifier
+    pub command: u32,
+    /// Return code, negative values are errors
+    pub return_code: i32,
+    /// Request, response and fragmentation flags
+    pub flags: u32,
+    /// Protocol version
+    pub protocol_version: u32,
+}
+
+impl BucketHeader {
+    /// Read a header from the reader, the signature is checked
+    pub fn read<R: Read>(r: &mut R) -> Result<BucketHeader, Error> {
+        let signature = r.read_u64::<LittleEndian>()?;
+        if signature != LEVIN_SIGNATURE {
+            return Err(Error::InvalidSignature(signature));
+        }
+        Ok(BucketHeader {
+            size: r.read_u64::<LittleEndian>()?,
+            have_to_return_data: r.read_u8()? != 0,
+            command: r.read_u32::<LittleEndian>()?,
+            return_code: r.read_i32::<LittleEndian>()?,
+            flags: r.read_u32::<LittleEndian>()?,
+            protocol_version: r.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    /// Write the header, preceded by the signature, into the writer
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_u64::<LittleEndian>(LEVIN_SIGNATURE)?;
+        w.write_u64::<LittleEndian>(self.size)?;
+        w.write_u8(self.have_to_return_data as u8)?;
+        w.write_u32::<LittleEndian>(self.command)?;
+        w.write_i32::<LittleEndian>(self.return_code)?;
+        w.write_u32::<LittleEndian>(self.flags)?;
+        w.write_u32::<LittleEndian>(self.protocol_version)?;
+        Ok(())
+    }
+
+    /// Return whether the bucket is a request or a notification
+    pub fn is_request(&self) -> bool {
+        self.flags & LEVIN_PACKET_REQUEST != 0
+    }
+
+    /// Return whether the bucket is a response
+    pub fn is_response(&self) -> bool {
+        self.flags & LEVIN_PACKET_RESPONSE != 0
+    }
+}
+
+/// Levin bucket, a header and its body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bucket {
+    /// The bucket header
+    pub header: BucketHeader,
+    /// The bucket body
+    pub body: Vec<u8>,
+}
+
+impl Bucket {
+    /// Create a bucket for a command with a successful return code
+    pub fn new(command: u32, body: Vec<u8>, flags: u32, have_to_return_data: bool) -> Bucket {
+        Bucket {
+            header: BucketHeader {
+                size: body.len() as u64,
+                have_to_return_data,
+                command,
+                return_code: LEVIN_OK,
+                flags,
+                protocol_version: LEVIN_PROTOCOL_VERSION,
+            },
+            body,
+        }
+    }
+
+    /// Read a bucket from the reader, buckets with a body larger than `max_size` are rejected
+    /// before reading the body
+    pub fn read<R: Read>(r: &mut R, max_size: u64) -> Result<Bucket, Error> {
+        let header = BucketHeader::read(r)?;
+        if header.size > max_size {
+            return Err(Error::PacketTooLarge(header.size));
+        }
+        let mut body = vec![0u8; header.size as usize];
+        r.read_exact(&mut body)?;
+        Ok(Bucket { header, body })
+    }
+
+    /// Write the bucket into the writer, the header size is the body length
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let header = BucketHeader {
+            size: self.body.len() as u64,
+            ..self.header.clone()
+        };
+        header.write(w)?;
+        w.write_all(&self.body)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bucket, BucketHeader, LEVIN_PACKET_REQUEST, LEVIN_PACKET_RESPONSE};
+    use crate::p2p::Error;
+
+    #[test]
+    fn ping_request_bucket() {
+        let hex = "01210101010101010a00000000000000\
+                   01eb030000000000000100000001000000\
+                   01110101010102010100";
+        let bytes = hex::decode(hex).unwrap();
+        let bucket = Bucket::read(&mut &bytes[..], 1024).unwrap();
+        assert_eq!(1003, bucket.header.command);
+        assert!(bucket.header.have_to_return_data);
+        assert!(bucket.header.is_request());
+        assert!(!bucket.header.is_response());
+        assert_eq!(10, bucket.body.len());
+
+        let mut encoded = vec![];
+        bucket.write(&mut encoded).unwrap();
+        assert_eq!(bytes, encoded);
+        assert_eq!(
+            bucket,
+            Bucket::new(1003, bucket.body.clone(), LEVIN_PACKET_REQUEST, true)
+        );
+    }
+
+    #[test]
+    fn invalid_buckets() {
+        let mut bytes = vec![];
+        Bucket::new(1003, vec![0; 64], LEVIN_PACKET_RESPONSE, false)
+            .write(&mut bytes)
+            .unwrap();
+        assert!(matches!(
+            Bucket::read(&mut &bytes[..], 63),
+            Err(Error::PacketTooLarge(64))
+        ));
+        assert!(matches!(
+            Bucket::read(&mut &bytes[..40], 64),
+            Err(Error::Io(_))
+        ));
+
+        bytes[0] = 0x02;
+        assert!(matches!(
+            BucketHeader::read(&mut &bytes[..]),
+            Err(Error::InvalidSignature(0x0101_0101_0101_2102))
+        ));
+    }
+}
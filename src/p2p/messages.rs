@@ -0,0 +1,771 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! P2P and CryptoNote protocol messages
+//!
+//! Messages are portable storages carried in levin buckets. Blocks and transactions are embedded
+//! as the crate's [`Block`] and [`Transaction`] types and written as consensus encoded blobs.
+//!
+//! ```rust
+//! use monero::network::Network;
+//! use monero::p2p::levin::{Bucket, LEVIN_DEFAULT_MAX_PACKET_SIZE};
+//! use monero::p2p::messages::{self, PingRequest, PingResponse};
+//!
+//! let bucket = messages::to_request(&PingRequest {}).unwrap();
+//! let mut bytes = vec![];
+//! bucket.write(&mut bytes).unwrap();
+//!
+//! let bucket = Bucket::read(&mut &bytes[..], LEVIN_DEFAULT_MAX_PACKET_SIZE).unwrap();
+//! let _request: PingRequest = messages::from_bucket(&bucket).unwrap();
+//! let response = PingResponse {
+//!     status: "OK".to_string(),
+//!     peer_id: 42,
+//! };
+//! let bucket = messages::to_response(&response).unwrap();
+//! assert_eq!(response, messages::from_bucket(&bucket).unwrap());
+//! ```
+//!
+//! **Same as** [`p2p_protocol_defs.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/p2p/p2p_protocol_defs.h)
+//! and [`cryptonote_protocol_defs.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_protocol/cryptonote_protocol_defs.h)
+//!
+
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+
+use super::levin::{Bucket, LEVIN_PACKET_REQUEST, LEVIN_PACKET_RESPONSE};
+use super::Error;
+use crate::blockdata::block::Block;
+use crate::blockdata::transaction::{PrunedTransaction, Transaction};
+use crate::consensus::encode::{deserialize, serialize};
+use crate::cryptonote::hash::Hash;
+use crate::portable_storage::{self, blob};
+
+/// Base of the P2P commands
+pub const P2P_COMMANDS_POOL_BASE: u32 = 1000;
+/// Base of the CryptoNote protocol commands
+pub const BC_COMMANDS_POOL_BASE: u32 = 2000;
+
+/// Status of a successful ping response
+pub const PING_OK_RESPONSE_STATUS_TEXT: &str = "OK";
+
+/// A message carried in the body of a levin bucket
+pub trait Message: Serialize + DeserializeOwned {
+    /// Levin command of the message
+    const COMMAND: u32;
+    /// Whether a request of this message expects a response, `false` for notifications
+    const EXPECTS_RESPONSE: bool;
+}
+
+/// Create a request, or notification, bucket carrying the message
+pub fn to_request<M: Message>(message: &M) -> Result<Bucket, Error> {
+    Ok(Bucket::new(
+        M::COMMAND,
+        portable_storage::to_vec(message)?,
+        LEVIN_PACKET_REQUEST,
+        M::EXPECTS_RESPONSE,
+    ))
+}
+
+/// Create a response bucket carrying the message
+pub fn to_response<M: Message>(message: &M) -> Result<Bucket, Error> {
+    Ok(Bucket::new(
+        M::COMMAND,
+        portable_storage::to_vec(message)?,
+        LEVIN_PACKET_RESPONSE,
+        false,
+    ))
+}
+
+/// Parse the message carried by a bucket, the bucket command must be the message command
+pub fn from_bucket<M: Message>(bucket: &Bucket) -> Result<M, Error> {
+    if bucket.header.command != M::COMMAND {
+        return Err(Error::UnexpectedCommand {
+            expected: M::COMMAND,
+            found: bucket.header.command,
+        });
+    }
+    Ok(portable_storage::from_slice(&bucket.body)?)
+}
+
+/// Address of a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkAddress {
+    /// IPv4 address and port
+    Ipv4(SocketAddrV4),
+    /// IPv6 address and port
+    Ipv6(SocketAddrV6),
+    /// Tor onion host and port
+    Tor {
+        /// Onion host name
+        host: String,
+        /// Port
+        port: u16,
+    },
+    /// I2P host and port
+    I2p {
+        /// I2P host name
+        host: String,
+        /// Port
+        port: u16,
+    },
+}
+
+/// Address type identifiers
+const ADDRESS_TYPE_IPV4: u8 = 1;
+const ADDRESS_TYPE_IPV6: u8 = 2;
+const ADDRESS_TYPE_I2P: u8 = 3;
+const ADDRESS_TYPE_TOR: u8 = 4;
+
+/// Network address as written by monerod, the fields of `addr` depend on the address type
+#[derive(Serialize, Deserialize)]
+struct RawNetworkAddress {
+    #[serde(rename = "type")]
+    ty: u8,
+    addr: RawAddress,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct RawAddress {
+    #[serde(default)]
+    m_ip: Option<u32>,
+    #[serde(default)]
+    m_port: Option<u16>,
+    #[serde(default)]
+    addr: Option<[u8; 16]>,
+    #[serde(default)]
+    host: Option<String>,
+    #[serde(default)]
+    port: Option<u16>,
+}
+
+impl Serialize for NetworkAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let raw = match self {
+            NetworkAddress::Ipv4(addr) => RawNetworkAddress {
+                ty: ADDRESS_TYPE_IPV4,
+                addr: RawAddress {
+                    // The address is stored in network byte order
+                    m_ip: Some(u32::from_le_bytes(addr.ip().octets())),
+                    m_port: Some(addr.port()),
+                    ..Default::default()
+                },
+            },
+            NetworkAddress::Ipv6(addr) => RawNetworkAddress {
+                ty: ADDRESS_TYPE_IPV6,
+                addr: RawAddress {
+                    addr: Some(addr.ip().octets()),
+                    m_port: Some(addr.port()),
+                    ..Default::default()
+                },
+            },
+            NetworkAddress::Tor { host, port } | NetworkAddress::I2p { host, port } => {
+                if host.is_empty() {
+                    return Err(ser::Error::custom("empty host name"));
+                }
+                RawNetworkAddress {
+                    ty: match self {
+                        NetworkAddress::Tor { .. } => ADDRESS_TYPE_TOR,
+                        _ => ADDRESS_TYPE_I2P,
+                    },
+                    addr: RawAddress {
+                        host: Some(host.clone()),
+                        port: Some(*port),
+                        ..Default::default()
+                    },
+                }
+            }
+        };
+        raw.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NetworkAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NetworkAddress, D::Error> {
+        fn missing<E: de::Error>(field: &'static str) -> E {
+            E::missing_field(field)
+        }
+
+        let RawNetworkAddress { ty, addr } = RawNetworkAddress::deserialize(deserializer)?;
+        match ty {
+            ADDRESS_TYPE_IPV4 => Ok(NetworkAddress::Ipv4(SocketAddrV4::new(
+                Ipv4Addr::from(addr.m_ip.ok_or_else(|| missing("m_ip"))?.to_le_bytes()),
+                addr.m_port.ok_or_else(|| missing("m_port"))?,
+            ))),
+            ADDRESS_TYPE_IPV6 => Ok(NetworkAddress::Ipv6(SocketAddrV6::new(
+                Ipv6Addr::from(addr.addr.ok_or_else(|| missing("addr"))?),
+                addr.m_port.ok_or_else(|| missing("m_port"))?,
+                0,
+                0,
+            ))),
+            ADDRESS_TYPE_I2P => Ok(NetworkAddress::I2p {
+                host: addr.host.ok_or_else(|| missing("host"))?,
+                port: addr.port.ok_or_else(|| missing("port"))?,
+            }),
+            ADDRESS_TYPE_TOR => Ok(NetworkAddress::Tor {
+                host: addr.host.ok_or_else(|| missing("host"))?,
+                port: addr.port.ok_or_else(|| missing("port"))?,
+            }),
+            ty => Err(de::Error::custom(format!("unknown address type {}", ty))),
+        }
+    }
+}
+
+/// Information about a node sent in handshakes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BasicNodeData {
+    /// Network id, see [`network_id`](super::network_id)
+    pub network_id: [u8; 16],
+    /// P2P port the node listens on, 0 if not reachable
+    pub my_port: u32,
+    /// RPC port advertised by the node
+    #[serde(default)]
+    pub rpc_port: u16,
+    /// RPC credits per hash advertised by the node
+    #[serde(default)]
+    pub rpc_credits_per_hash: u32,
+    /// Random identifier of the node
+    pub peer_id: u64,
+    /// Supported P2P features
+    #[serde(default)]
+    pub support_flags: u32,
+}
+
+/// Chain state of a node sent in handshakes and timed syncs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreSyncData {
+    /// Height of the chain
+    pub current_height: u64,
+    /// Low 64 bits of the cumulative difficulty
+    pub cumulative_difficulty: u64,
+    /// High 64 bits of the cumulative difficulty
+    #[serde(default)]
+    pub cumulative_difficulty_top64: u64,
+    /// Id of the top block
+    pub top_id: Hash,
+    /// Major version of the top block
+    #[serde(default)]
+    pub top_version: u8,
+    /// Pruning seed of the node, 0 if not pruned
+    #[serde(default)]
+    pub pruning_seed: u32,
+}
+
+impl CoreSyncData {
+    /// Return the full 128 bits cumulative difficulty
+    pub fn cumulative_difficulty(&self) -> u128 {
+        (self.cumulative_difficulty_top64 as u128) << 64 | self.cumulative_difficulty as u128
+    }
+}
+
+/// Known peer shared in peer lists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerlistEntry {
+    /// Address of the peer
+    pub adr: NetworkAddress,
+    /// Identifier of the peer
+    pub id: u64,
+    /// Last time the peer was seen, as a unix timestamp
+    #[serde(default)]
+    pub last_seen: i64,
+    /// Pruning seed of the peer
+    #[serde(default)]
+    pub pruning_seed: u32,
+    /// RPC port advertised by the peer
+    #[serde(default)]
+    pub rpc_port: u16,
+    /// RPC credits per hash advertised by the peer
+    #[serde(default)]
+    pub rpc_credits_per_hash: u32,
+}
+
+/// Handshake request, `COMMAND_HANDSHAKE`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeRequest {
+    /// Information about the node
+    pub node_data: BasicNodeData,
+    /// Chain state of the node
+    pub payload_data: CoreSyncData,
+}
+
+/// Handshake response, `COMMAND_HANDSHAKE`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandshakeResponse {
+    /// Information about the node
+    pub node_data: BasicNodeData,
+    /// Chain state of the node
+    pub payload_data: CoreSyncData,
+    /// Peers known by the node
+    #[serde(default)]
+    pub local_peerlist_new: Vec<PeerlistEntry>,
+}
+
+/// Timed sync request, `COMMAND_TIMED_SYNC`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedSyncRequest {
+    /// Chain state of the node
+    pub payload_data: CoreSyncData,
+}
+
+/// Timed sync response, `COMMAND_TIMED_SYNC`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedSyncResponse {
+    /// Chain state of the node
+    pub payload_data: CoreSyncData,
+    /// Peers known by the node
+    #[serde(default)]
+    pub local_peerlist_new: Vec<PeerlistEntry>,
+}
+
+/// Ping request, `COMMAND_PING`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PingRequest {}
+
+/// Ping response, `COMMAND_PING`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PingResponse {
+    /// [`PING_OK_RESPONSE_STATUS_TEXT`] if the node is reachable
+    pub status: String,
+    /// Identifier of the node
+    pub peer_id: u64,
+}
+
+/// Transactions relayed to a peer, `NOTIFY_NEW_TRANSACTIONS`
+///
+/// With dandelion++ transactions are first relayed to a single peer in the stem phase, with
+/// `dandelionpp_fluff` set to `false`, before being broadcast in the fluff phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewTransactions {
+    /// Relayed transactions
+    #[serde(with = "blob::vec", default)]
+    pub txs: Vec<Transaction>,
+    /// Random padding hiding the size of the message
+    #[serde(rename = "_", default)]
+    pub padding: String,
+    /// Whether the transactions are in the fluff phase, `false` in the stem phase
+    #[serde(default = "default_fluff")]
+    pub dandelionpp_fluff: bool,
+}
+
+fn default_fluff() -> bool {
+    true
+}
+
+/// Request of blocks by id, `NOTIFY_REQUEST_GET_OBJECTS`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestGetObjects {
+    /// Ids of the requested blocks
+    #[serde(with = "blob::pod", default)]
+    pub blocks: Vec<Hash>,
+    /// Whether pruned blocks are requested
+    #[serde(default)]
+    pub prune: bool,
+}
+
+/// Transaction of a block complete entry
+///
+/// Transactions of pruned entries are written as their pruned blob with their prunable hash,
+/// others as their full blob.
+///
+/// **Same as** `tx_blob_entry` in [`cryptonote_protocol_defs.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_protocol/cryptonote_protocol_defs.h)
+#[derive(Debug, Clone)]
+pub enum TxBlobEntry {
+    /// Full transaction
+    Full(Transaction),
+    /// Pruned transaction
+    Pruned(PrunedTransaction),
+}
+
+#[derive(Serialize, Deserialize)]
+struct PrunedTxBlobEntry {
+    #[serde(with = "blob::bytes")]
+    blob: Vec<u8>,
+    #[serde(with = "blob::bytes")]
+    prunable_hash: Vec<u8>,
+}
+
+impl Serialize for TxBlobEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TxBlobEntry::Full(tx) => blob::serialize(tx, serializer),
+            TxBlobEntry::Pruned(tx) => PrunedTxBlobEntry {
+                blob: serialize(tx),
+                prunable_hash: tx.prunable_hash.to_bytes().to_vec(),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TxBlobEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TxBlobEntry, D::Error> {
+        struct TxBlobEntryVisitor;
+
+        impl<'de> de::Visitor<'de> for TxBlobEntryVisitor {
+            type Value = TxBlobEntry;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a transaction blob or a pruned transaction entry")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<TxBlobEntry, E> {
+                deserialize(v)
+                    .map(TxBlobEntry::Full)
+                    .map_err(|e| E::custom(format!("invalid blob: {:?}", e)))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<TxBlobEntry, E> {
+                self.visit_bytes(&v)
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<TxBlobEntry, A::Error> {
+                let entry =
+                    PrunedTxBlobEntry::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                if entry.prunable_hash.len() != 32 {
+                    return Err(de::Error::invalid_length(
+                        entry.prunable_hash.len(),
+                        &"a 32 bytes hash",
+                    ));
+                }
+                PrunedTransaction::from_pruned_blob(
+                    &entry.blob,
+                    Hash::from_slice(&entry.prunable_hash),
+                )
+                .map(TxBlobEntry::Pruned)
+                .map_err(|e| de::Error::custom(format!("invalid blob: {:?}", e)))
+            }
+        }
+
+        deserializer.deserialize_any(TxBlobEntryVisitor)
+    }
+}
+
+/// Block and its transactions
+///
+/// Transactions of pruned entries are [`TxBlobEntry::Pruned`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockCompleteEntry {
+    /// Whether the entry is pruned
+    #[serde(default)]
+    pub pruned: bool,
+    /// The block
+    #[serde(with = "blob")]
+    pub block: Block,
+    /// Weight of the block, only set for pruned entries
+    #[serde(default)]
+    pub block_weight: u64,
+    /// Transactions of the block, without the miner transaction
+    #[serde(default)]
+    pub txs: Vec<TxBlobEntry>,
+}
+
+/// Requested blocks, `NOTIFY_RESPONSE_GET_OBJECTS`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseGetObjects {
+    /// Requested blocks
+    #[serde(default)]
+    pub blocks: Vec<BlockCompleteEntry>,
+    /// Ids of the requested blocks unknown to the node
+    #[serde(with = "blob::pod", default)]
+    pub missed_ids: Vec<Hash>,
+    /// Height of the chain
+    pub current_blockchain_height: u64,
+}
+
+/// Request of the chain following known blocks, `NOTIFY_REQUEST_CHAIN`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestChain {
+    /// Sparse list of known block ids, from the top block to the genesis block
+    #[serde(with = "blob::pod", default)]
+    pub block_ids: Vec<Hash>,
+    /// Whether pruned blocks are requested
+    #[serde(default)]
+    pub prune: bool,
+}
+
+/// Chain following the known blocks, `NOTIFY_RESPONSE_CHAIN_ENTRY`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseChainEntry {
+    /// Height of the first block id
+    pub start_height: u64,
+    /// Height of the chain
+    pub total_height: u64,
+    /// Low 64 bits of the cumulative difficulty
+    pub cumulative_difficulty: u64,
+    /// High 64 bits of the cumulative difficulty
+    #[serde(default)]
+    pub cumulative_difficulty_top64: u64,
+    /// Ids of the blocks
+    #[serde(with = "blob::pod", default)]
+    pub m_block_ids: Vec<Hash>,
+    /// Weights of the blocks
+    #[serde(with = "blob::pod", default)]
+    pub m_block_weights: Vec<u64>,
+    /// First block of the list
+    #[serde(with = "blob::bytes", default)]
+    pub first_block: Vec<u8>,
+}
+
+/// New block relayed with the ids of its transactions, `NOTIFY_NEW_FLUFFY_BLOCK`
+///
+/// Transactions already relayed are not included, the receiver requests the missing ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewFluffyBlock {
+    /// The block and its missing transactions
+    pub b: BlockCompleteEntry,
+    /// Height of the chain
+    pub current_blockchain_height: u64,
+}
+
+macro_rules! impl_message {
+    ($($message:ty => $command:expr, $expects_response:expr;)*) => {
+        $(
+            impl Message for $message {
+                const COMMAND: u32 = $command;
+                const EXPECTS_RESPONSE: bool = $expects_response;
+            }
+        )*
+    };
+}
+
+impl_message! {
+    HandshakeRequest => P2P_COMMANDS_POOL_BASE + 1, true;
+    HandshakeResponse => P2P_COMMANDS_POOL_BASE + 1, false;
+    TimedSyncRequest => P2P_COMMANDS_POOL_BASE + 2, true;
+    TimedSyncResponse => P2P_COMMANDS_POOL_BASE + 2, false;
+    PingRequest => P2P_COMMANDS_POOL_BASE + 3, true;
+    PingResponse => P2P_COMMANDS_POOL_BASE + 3, false;
+    NewTransactions => BC_COMMANDS_POOL_BASE + 2, false;
+    RequestGetObjects => BC_COMMANDS_POOL_BASE + 3, false;
+    ResponseGetObjects => BC_COMMANDS_POOL_BASE + 4, false;
+    RequestChain => BC_COMMANDS_POOL_BASE + 6, false;
+    ResponseChainEntry => BC_COMMANDS_POOL_BASE + 7, false;
+    NewFluffyBlock => BC_COMMANDS_POOL_BASE + 8, false;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddrV4;
+
+    use super::*;
+    use crate::cryptonote::hash::Hashable;
+    use crate::network::Network;
+    use crate::p2p::{levin::LEVIN_DEFAULT_MAX_PACKET_SIZE, network_id};
+
+    fn core_sync_data() -> CoreSyncData {
+        CoreSyncData {
+            current_height: 558_176,
+            cumulative_difficulty: u64::MAX,
+            cumulative_difficulty_top64: 1,
+            top_id: Hash::hash(b"top"),
+            top_version: 12,
+            pruning_seed: 0,
+        }
+    }
+
+    #[test]
+    fn handshake_round_trip() {
+        let request = HandshakeRequest {
+            node_data: BasicNodeData {
+                network_id: network_id(Network::Stagenet),
+                my_port: 38080,
+                rpc_port: 0,
+                rpc_credits_per_hash: 0,
+                peer_id: 0x0123_4567_89ab_cdef,
+                support_flags: 1,
+            },
+            payload_data: core_sync_data(),
+        };
+        let bucket = to_request(&request).unwrap();
+        assert_eq!(1001, bucket.header.command);
+        assert!(bucket.header.have_to_return_data);
+
+        let mut bytes = vec![];
+        bucket.write(&mut bytes).unwrap();
+        let bucket = Bucket::read(&mut &bytes[..], LEVIN_DEFAULT_MAX_PACKET_SIZE).unwrap();
+        assert_eq!(request, from_bucket(&bucket).unwrap());
+        assert_eq!(
+            (1u128 << 65) - 1,
+            request.payload_data.cumulative_difficulty()
+        );
+        assert!(matches!(
+            from_bucket::<PingRequest>(&bucket),
+            Err(Error::UnexpectedCommand {
+                expected: 1003,
+                found: 1001
+            })
+        ));
+
+        let response = TimedSyncResponse {
+            payload_data: core_sync_data(),
+            local_peerlist_new: vec![
+                PeerlistEntry {
+                    adr: NetworkAddress::Ipv4(SocketAddrV4::new([1, 2, 3, 4].into(), 18080)),
+                    id: 1,
+                    last_seen: 1_600_000_000,
+                    pruning_seed: 0,
+                    rpc_port: 18089,
+                    rpc_credits_per_hash: 0,
+                },
+                PeerlistEntry {
+                    adr: NetworkAddress::Tor {
+                        host: "example.onion".to_string(),
+                        port: 18083,
+                    },
+                    id: 2,
+                    last_seen: 0,
+                    pruning_seed: 0,
+                    rpc_port: 0,
+                    rpc_credits_per_hash: 0,
+                },
+            ],
+        };
+        let bucket = to_response(&response).unwrap();
+        assert!(bucket.header.is_response());
+        assert_eq!(response, from_bucket(&bucket).unwrap());
+    }
+
+    #[test]
+    fn ipv4_address_in_network_byte_order() {
+        let entry = PeerlistEntry {
+            adr: NetworkAddress::Ipv4(SocketAddrV4::new([127, 0, 0, 1].into(), 18080)),
+            id: 1,
+            last_seen: 0,
+            pruning_seed: 0,
+            rpc_port: 0,
+            rpc_credits_per_hash: 0,
+        };
+        let section = portable_storage::to_section(&entry).unwrap();
+        let adr = match section.get("adr") {
+            Some(portable_storage::Value::Section(adr)) => adr,
+            _ => panic!("address must be a section"),
+        };
+        assert_eq!(Some(&portable_storage::Value::U8(1)), adr.get("type"));
+        match adr.get("addr") {
+            Some(portable_storage::Value::Section(addr)) => assert_eq!(
+                Some(&portable_storage::Value::U32(0x0100_007f)),
+                addr.get("m_ip")
+            ),
+            _ => panic!("addr must be a section"),
+        }
+    }
+
+    #[test]
+    fn fluffy_block_and_objects() {
+        // stagenet block 558175
+        let hex = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000";
+        let bytes = hex::decode(hex).unwrap();
+        let block = deserialize::<Block>(&bytes).unwrap();
+
+        let notification = NewFluffyBlock {
+            b: BlockCompleteEntry {
+                pruned: false,
+                block: block.clone(),
+                block_weight: 0,
+                txs: vec![TxBlobEntry::Full(block.miner_tx.clone())],
+            },
+            current_blockchain_height: 558_176,
+        };
+        let bucket = to_request(&notification).unwrap();
+        assert_eq!(2008, bucket.header.command);
+        assert!(!bucket.header.have_to_return_data);
+        let decoded: NewFluffyBlock = from_bucket(&bucket).unwrap();
+        assert_eq!(bytes, serialize(&decoded.b.block));
+        match &decoded.b.txs[0] {
+            TxBlobEntry::Full(tx) => assert_eq!(serialize(&block.miner_tx), serialize(tx)),
+            TxBlobEntry::Pruned(_) => panic!("transaction must not be pruned"),
+        }
+
+        let request = RequestGetObjects {
+            blocks: vec![Hash::hash(b"a"), Hash::hash(b"b")],
+            prune: false,
+        };
+        let section = portable_storage::to_section(&request).unwrap();
+        assert!(matches!(
+            section.get("blocks"),
+            Some(portable_storage::Value::Bytes(ids)) if ids.len() == 64
+        ));
+        assert_eq!(
+            request,
+            from_bucket(&to_request(&request).unwrap()).unwrap()
+        );
+
+        let response = ResponseGetObjects {
+            blocks: vec![notification.b],
+            missed_ids: vec![Hash::hash(b"b")],
+            current_blockchain_height: 558_176,
+        };
+        let decoded: ResponseGetObjects = from_bucket(&to_request(&response).unwrap()).unwrap();
+        assert_eq!(1, decoded.blocks.len());
+        assert_eq!(response.missed_ids, decoded.missed_ids);
+    }
+
+    #[test]
+    fn pruned_block_complete_entry() {
+        // stagenet block 558175
+        let hex = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000";
+        let block = deserialize::<Block>(&hex::decode(hex).unwrap()).unwrap();
+        let (pruned, _) = block.miner_tx.prune();
+
+        let entry = BlockCompleteEntry {
+            pruned: true,
+            block: block.clone(),
+            block_weight: 98,
+            txs: vec![TxBlobEntry::Pruned(pruned)],
+        };
+        let section = portable_storage::to_section(&entry).unwrap();
+        assert!(matches!(
+            section.get("txs"),
+            Some(portable_storage::Value::Array(portable_storage::Array::Section(txs))) if txs.len() == 1
+        ));
+        let decoded: BlockCompleteEntry = portable_storage::from_section(section).unwrap();
+        assert!(decoded.pruned);
+        match &decoded.txs[0] {
+            TxBlobEntry::Pruned(tx) => assert_eq!(Some(block.miner_tx.hash()), tx.hash()),
+            TxBlobEntry::Full(_) => panic!("transaction must be pruned"),
+        }
+    }
+
+    #[test]
+    fn chain_and_transactions() {
+        let entry = ResponseChainEntry {
+            start_height: 10,
+            total_height: 12,
+            cumulative_difficulty: 1000,
+            cumulative_difficulty_top64: 0,
+            m_block_ids: vec![Hash::hash(b"10"), Hash::hash(b"11")],
+            m_block_weights: vec![300_000, 300_001],
+            first_block: vec![0x0c, 0x0c],
+        };
+        assert_eq!(entry, from_bucket(&to_request(&entry).unwrap()).unwrap());
+
+        let stem = NewTransactions {
+            txs: vec![],
+            padding: "pad".to_string(),
+            dandelionpp_fluff: false,
+        };
+        let decoded: NewTransactions = from_bucket(&to_request(&stem).unwrap()).unwrap();
+        assert!(!decoded.dandelionpp_fluff);
+        assert!(decoded.txs.is_empty());
+
+        // The fluff flag defaults to `true` when missing
+        let decoded: NewTransactions =
+            portable_storage::from_section(portable_storage::Section::new()).unwrap();
+        assert!(decoded.dandelionpp_fluff);
+    }
+}
@@ -0,0 +1,71 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Monero peer-to-peer protocol
+//!
+//! This module implements the levin framing used between monerod peers in [`levin`] and, with
+//! the `serde_support` feature, the P2P and CryptoNote protocol messages in [`messages`], encoded
+//! as [portable storages](crate::portable_storage).
+//!
+
+pub mod levin;
+#[cfg(feature = "serde_support")]
+pub mod messages;
+
+use std::io;
+
+use crate::network::Network;
+use crate::portable_storage;
+use thiserror::Error;
+
+/// Possible errors when reading or writing P2P messages
+#[derive(Error, Debug)]
+pub enum Error {
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The bucket does not start with the levin signature
+    #[error("invalid levin signature: {0:#x}")]
+    InvalidSignature(u64),
+    /// The bucket body is larger than the accepted maximum
+    #[error("levin packet of {0} bytes is too large")]
+    PacketTooLarge(u64),
+    /// The bucket does not carry the expected command
+    #[error("unexpected command {found}, expected {expected}")]
+    UnexpectedCommand {
+        /// Expected command
+        expected: u32,
+        /// Command of the bucket
+        found: u32,
+    },
+    /// The bucket body is not a valid portable storage message
+    #[error("portable storage error: {0}")]
+    Storage(#[from] portable_storage::Error),
+}
+
+/// Return the network id sent in handshakes by the nodes of a network
+///
+/// **Same as** `NETWORK_ID` in [`cryptonote_config.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_config.h)
+pub fn network_id(network: Network) -> [u8; 16] {
+    let last = match network {
+        Network::Mainnet => 0x10,
+        Network::Testnet => 0x11,
+        Network::Stagenet => 0x12,
+    };
+    [
+        0x12, 0x30, 0xf1, 0x71, 0x61, 0x04, 0x41, 0x61, 0x17, 0x31, 0x00, 0x82, 0x16, 0xa1, 0xa1,
+        last,
+    ]
+}
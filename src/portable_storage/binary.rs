@@ -0,0 +1,357 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Binary reader and writer of portable storages
+//!
+//! **Same as** [`portable_storage_from_bin.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/contrib/epee/include/storages/portable_storage_from_bin.h)
+//! and [`portable_storage_to_bin.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/contrib/epee/include/storages/portable_storage_to_bin.h)
+//!
+
+use std::io::{Cursor, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::*;
+
+/// Maximum number of elements preallocated when reading an array, larger arrays grow as
+/// elements are read so a forged size cannot exhaust memory
+const MAX_PREALLOCATED: u64 = 4096;
+
+/// Read a portable storage, i.e. the signature header and the root section, from the reader
+pub fn read_section<R: Read>(r: &mut R) -> Result<Section, Error> {
+    let signature_a = r.read_u32::<LittleEndian>()?;
+    let signature_b = r.read_u32::<LittleEndian>()?;
+    if signature_a != SIGNATURE_A || signature_b != SIGNATURE_B {
+        return Err(Error::InvalidSignature);
+    }
+    let version = r.read_u8()?;
+    if version != FORMAT_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    read_entries(r, 0)
+}
+
+/// Read a portable storage from a slice of bytes, the slice must be consumed entirely
+pub fn from_bytes(bytes: &[u8]) -> Result<Section, Error> {
+    let mut cursor = Cursor::new(bytes);
+    let section = read_section(&mut cursor)?;
+    match bytes.len() - cursor.position() as usize {
+        0 => Ok(section),
+        trailing => Err(Error::TrailingBytes(trailing)),
+    }
+}
+
+/// Write a portable storage, i.e. the signature header and the root section, into the writer
+pub fn write_section<W: Write>(w: &mut W, section: &Section) -> Result<(), Error> {
+    w.write_u32::<LittleEndian>(SIGNATURE_A)?;
+    w.write_u32::<LittleEndian>(SIGNATURE_B)?;
+    w.write_u8(FORMAT_VERSION)?;
+    write_entries(w, section)
+}
+
+/// Serialize a portable storage into a vector of bytes
+pub fn to_bytes(section: &Section) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    write_section(&mut bytes, section)?;
+    Ok(bytes)
+}
+
+/// Read a size, the two least significant bits of the first byte give the number of bytes used
+fn read_varint<R: Read>(r: &mut R) -> Result<u64, Error> {
+    let mut buf = [0u8; 8];
+    buf[0] = r.read_u8()?;
+    let size = 1 << (buf[0] & 0x03);
+    r.read_exact(&mut buf[1..size])?;
+    Ok(u64::from_le_bytes(buf) >> 2)
+}
+
+fn write_varint<W: Write>(w: &mut W, value: u64) -> Result<(), Error> {
+    match value {
+        0..=0x3f => w.write_u8((value as u8) << 2)?,
+        0x40..=0x3fff => w.write_u16::<LittleEndian>(((value as u16) << 2) | 1)?,
+        0x4000..=0x3fff_ffff => w.write_u32::<LittleEndian>(((value as u32) << 2) | 2)?,
+        0x4000_0000..=MAX_SIZE => w.write_u64::<LittleEndian>((value << 2) | 3)?,
+        _ => return Err(Error::SizeTooLarge(value)),
+    }
+    Ok(())
+}
+
+fn read_entries<R: Read>(r: &mut R, depth: usize) -> Result<Section, Error> {
+    if depth >= MAX_DEPTH {
+        return Err(Error::DepthLimitExceeded);
+    }
+    let count = read_varint(r)?;
+    let mut section = Section::new();
+    for _ in 0..count {
+        let mut name = vec![0u8; r.read_u8()? as usize];
+        r.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| Error::InvalidName)?;
+        let value = read_value(r, depth)?;
+        section.insert(name, value);
+    }
+    Ok(section)
+}
+
+fn read_bytes<R: Read>(r: &mut R) -> Result<Vec<u8>, Error> {
+    let len = read_varint(r)?;
+    let mut bytes = Vec::with_capacity(len.min(MAX_PREALLOCATED) as usize);
+    let read = r.take(len).read_to_end(&mut bytes)?;
+    if (read as u64) < len {
+        return Err(Error::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(bytes)
+}
+
+fn read_value<R: Read>(r: &mut R, depth: usize) -> Result<Value, Error> {
+    let code = r.read_u8()?;
+    if code & FLAG_ARRAY != 0 {
+        return Ok(Value::Array(read_array(r, code & !FLAG_ARRAY, depth + 1)?));
+    }
+    let value = match code {
+        TYPE_INT64 => Value::I64(r.read_i64::<LittleEndian>()?),
+        TYPE_INT32 => Value::I32(r.read_i32::<LittleEndian>()?),
+        TYPE_INT16 => Value::I16(r.read_i16::<LittleEndian>()?),
+        TYPE_INT8 => Value::I8(r.read_i8()?),
+        TYPE_UINT64 => Value::U64(r.read_u64::<LittleEndian>()?),
+        TYPE_UINT32 => Value::U32(r.read_u32::<LittleEndian>()?),
+        TYPE_UINT16 => Value::U16(r.read_u16::<LittleEndian>()?),
+        TYPE_UINT8 => Value::U8(r.read_u8()?),
+        TYPE_DOUBLE => Value::F64(r.read_f64::<LittleEndian>()?),
+        TYPE_STRING => Value::Bytes(read_bytes(r)?),
+        TYPE_BOOL => Value::Bool(r.read_u8()? != 0),
+        TYPE_OBJECT => Value::Section(read_entries(r, depth + 1)?),
+        // An array without the array flag is followed by the flagged array type
+        TYPE_ARRAY => Value::Array(read_nested_array(r, depth + 1)?),
+        _ => return Err(Error::UnknownType(code)),
+    };
+    Ok(value)
+}
+
+fn read_nested_array<R: Read>(r: &mut R, depth: usize) -> Result<Array, Error> {
+    let code = r.read_u8()?;
+    if code & FLAG_ARRAY == 0 {
+        return Err(Error::UnknownType(code));
+    }
+    read_array(r, code & !FLAG_ARRAY, depth)
+}
+
+fn read_array<R: Read>(r: &mut R, code: u8, depth: usize) -> Result<Array, Error> {
+    if depth >= MAX_DEPTH {
+        return Err(Error::DepthLimitExceeded);
+    }
+    let count = read_varint(r)?;
+
+    macro_rules! read_elements {
+        ($variant:ident, $read:expr) => {{
+            let mut elements = Vec::with_capacity(count.min(MAX_PREALLOCATED) as usize);
+            for _ in 0..count {
+                elements.push($read);
+            }
+            Array::$variant(elements)
+        }};
+    }
+
+    let array = match code {
+        TYPE_INT64 => read_elements!(I64, r.read_i64::<LittleEndian>()?),
+        TYPE_INT32 => read_elements!(I32, r.read_i32::<LittleEndian>()?),
+        TYPE_INT16 => read_elements!(I16, r.read_i16::<LittleEndian>()?),
+        TYPE_INT8 => read_elements!(I8, r.read_i8()?),
+        TYPE_UINT64 => read_elements!(U64, r.read_u64::<LittleEndian>()?),
+        TYPE_UINT32 => read_elements!(U32, r.read_u32::<LittleEndian>()?),
+        TYPE_UINT16 => read_elements!(U16, r.read_u16::<LittleEndian>()?),
+        TYPE_UINT8 => read_elements!(U8, r.read_u8()?),
+        TYPE_DOUBLE => read_elements!(F64, r.read_f64::<LittleEndian>()?),
+        TYPE_STRING => read_elements!(Bytes, read_bytes(r)?),
+        TYPE_BOOL => read_elements!(Bool, r.read_u8()? != 0),
+        TYPE_OBJECT => read_elements!(Section, read_entries(r, depth + 1)?),
+        TYPE_ARRAY => read_elements!(Array, read_nested_array(r, depth + 1)?),
+        _ => return Err(Error::UnknownType(code)),
+    };
+    Ok(array)
+}
+
+fn write_entries<W: Write>(w: &mut W, section: &Section) -> Result<(), Error> {
+    write_varint(w, section.len() as u64)?;
+    for (name, value) in section {
+        if name.len() > u8::MAX as usize {
+            return Err(Error::NameTooLong(name.len()));
+        }
+        w.write_u8(name.len() as u8)?;
+        w.write_all(name.as_bytes())?;
+        write_value(w, value)?;
+    }
+    Ok(())
+}
+
+fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    write_varint(w, bytes.len() as u64)?;
+    w.write_all(bytes)?;
+    Ok(())
+}
+
+fn write_value<W: Write>(w: &mut W, value: &Value) -> Result<(), Error> {
+    if let Value::Array(array) = value {
+        w.write_u8(FLAG_ARRAY | array.type_code())?;
+        return write_array(w, array);
+    }
+    w.write_u8(value.type_code())?;
+    match value {
+        Value::I64(v) => w.write_i64::<LittleEndian>(*v)?,
+        Value::I32(v) => w.write_i32::<LittleEndian>(*v)?,
+        Value::I16(v) => w.write_i16::<LittleEndian>(*v)?,
+        Value::I8(v) => w.write_i8(*v)?,
+        Value::U64(v) => w.write_u64::<LittleEndian>(*v)?,
+        Value::U32(v) => w.write_u32::<LittleEndian>(*v)?,
+        Value::U16(v) => w.write_u16::<LittleEndian>(*v)?,
+        Value::U8(v) => w.write_u8(*v)?,
+        Value::F64(v) => w.write_f64::<LittleEndian>(*v)?,
+        Value::Bytes(v) => write_bytes(w, v)?,
+        Value::Bool(v) => w.write_u8(*v as u8)?,
+        Value::Section(v) => write_entries(w, v)?,
+        Value::Array(_) => unreachable!("arrays are written above"),
+    }
+    Ok(())
+}
+
+fn write_array<W: Write>(w: &mut W, array: &Array) -> Result<(), Error> {
+    write_varint(w, array.len() as u64)?;
+
+    macro_rules! write_elements {
+        ($elements:expr, $write:expr) => {
+            for element in $elements {
+                $write(element)?;
+            }
+        };
+    }
+
+    match array {
+        Array::I64(v) => write_elements!(v, |e: &i64| w.write_i64::<LittleEndian>(*e)),
+        Array::I32(v) => write_elements!(v, |e: &i32| w.write_i32::<LittleEndian>(*e)),
+        Array::I16(v) => write_elements!(v, |e: &i16| w.write_i16::<LittleEndian>(*e)),
+        Array::I8(v) => write_elements!(v, |e: &i8| w.write_i8(*e)),
+        Array::U64(v) => write_elements!(v, |e: &u64| w.write_u64::<LittleEndian>(*e)),
+        Array::U32(v) => write_elements!(v, |e: &u32| w.write_u32::<LittleEndian>(*e)),
+        Array::U16(v) => write_elements!(v, |e: &u16| w.write_u16::<LittleEndian>(*e)),
+        Array::U8(v) => w.write_all(v)?,
+        Array::F64(v) => write_elements!(v, |e: &f64| w.write_f64::<LittleEndian>(*e)),
+        Array::Bytes(v) => write_elements!(v, |e: &Vec<u8>| write_bytes(w, e)),
+        Array::Bool(v) => write_elements!(v, |e: &bool| w.write_u8(*e as u8)),
+        Array::Section(v) => write_elements!(v, |e: &Section| write_entries(w, e)),
+        Array::Array(v) => {
+            for nested in v {
+                w.write_u8(FLAG_ARRAY | nested.type_code())?;
+                write_array(w, nested)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_bytes, read_varint, to_bytes, write_varint, Array, Error, Section, Value};
+
+    #[test]
+    fn varint_sizes() {
+        for (value, hex) in &[
+            (0u64, "00"),
+            (63, "fc"),
+            (64, "0101"),
+            (16383, "fdff"),
+            (16384, "02000100"),
+            (1 << 30, "0300000001000000"),
+        ] {
+            let mut bytes = vec![];
+            write_varint(&mut bytes, *value).unwrap();
+            assert_eq!(*hex, hex::encode(&bytes));
+            assert_eq!(*value, read_varint(&mut &bytes[..]).unwrap());
+        }
+        assert!(matches!(
+            write_varint(&mut vec![], 1 << 62),
+            Err(Error::SizeTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn simple_storage() {
+        // { "a": u8 1, "b": "ok" }
+        let bytes = hex::decode("011101010101020101080161080101620a086f6b").unwrap();
+        let section = from_bytes(&bytes).unwrap();
+        let mut expected = Section::new();
+        expected.insert("a".into(), Value::U8(1));
+        expected.insert("b".into(), "ok".into());
+        assert_eq!(expected, section);
+        assert_eq!(bytes, to_bytes(&section).unwrap());
+    }
+
+    #[test]
+    fn nested_storage() {
+        let mut inner = Section::new();
+        inner.insert("height".into(), Value::U64(2_200_000));
+        inner.insert("hash".into(), Value::Bytes(vec![0xab; 32]));
+        let mut section = Section::new();
+        section.insert("inner".into(), Value::Section(inner.clone()));
+        section.insert(
+            "list".into(),
+            Value::Array(Array::Section(vec![inner.clone(), inner])),
+        );
+        section.insert(
+            "matrix".into(),
+            Value::Array(Array::Array(vec![
+                Array::U32(vec![1, 2, 3]),
+                Array::Bytes(vec![b"x".to_vec()]),
+            ])),
+        );
+        section.insert("flag".into(), Value::Bool(true));
+        section.insert("ratio".into(), Value::F64(0.5));
+        section.insert("offset".into(), Value::I16(-42));
+
+        let bytes = to_bytes(&section).unwrap();
+        assert_eq!(section, from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn invalid_storages() {
+        assert!(matches!(
+            from_bytes(&hex::decode("0111010101010201020400").unwrap()),
+            Err(Error::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            from_bytes(&hex::decode("0111010101010200010400").unwrap()),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            from_bytes(&hex::decode("01110101010102010104016111").unwrap()),
+            Err(Error::UnknownType(0x11))
+        ));
+        assert!(matches!(
+            from_bytes(&hex::decode("0111010101010201010000").unwrap()),
+            Err(Error::TrailingBytes(1))
+        ));
+        // A string announcing more bytes than available
+        assert!(matches!(
+            from_bytes(&hex::decode("01110101010102010104016a0afeffffff0061").unwrap()),
+            Err(Error::Io(_))
+        ));
+
+        // Sections nested deeper than the limit
+        let mut bytes = hex::decode("011101010101020101").unwrap();
+        for _ in 0..200 {
+            bytes.extend_from_slice(&[0x04, 0x01, b'a', 0x0c]);
+        }
+        bytes.push(0x00);
+        assert!(matches!(from_bytes(&bytes), Err(Error::DepthLimitExceeded)));
+    }
+}
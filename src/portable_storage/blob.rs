@@ -0,0 +1,257 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Serde helpers for blobs
+//!
+//! Epee messages carry blocks and transactions as consensus encoded strings and lists of hashes
+//! or integers as a single string of concatenated values. These modules are meant to be used
+//! with `#[serde(with = "...")]`:
+//!
+//! * this module for a single consensus encoded value, e.g. a `Block`
+//! * [`vec`] for a list of consensus encoded values, e.g. a `Vec<Transaction>`
+//! * [`pod`] for a list of fixed-size values as a single string, e.g. a `Vec<Hash>`
+//! * [`bytes`] for raw bytes as a string
+//!
+//! ```rust
+//! use monero::cryptonote::hash::Hash;
+//! use monero::portable_storage::{self, blob};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct RequestChain {
+//!     #[serde(with = "blob::pod", default)]
+//!     block_ids: Vec<Hash>,
+//! }
+//!
+//! let request = RequestChain { block_ids: vec![Hash::null_hash(); 2] };
+//! let bytes = portable_storage::to_vec(&request).unwrap();
+//! assert_eq!(request, portable_storage::from_slice(&bytes).unwrap());
+//! ```
+//!
+
+use std::fmt;
+use std::io::Cursor;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+
+use crate::consensus::encode::{self, BoundedDecoder, Decodable, Encodable};
+use crate::cryptonote::hash::Hash;
+
+/// Visitor of a byte string, also accepting a sequence of bytes
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = vec![];
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+fn decode<T, E>(bytes: &[u8]) -> Result<T, E>
+where
+    T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+    E: de::Error,
+{
+    encode::deserialize(bytes).map_err(|e| E::custom(format!("invalid blob: {:?}", e)))
+}
+
+/// Serialize a consensus encodable value as a string
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Encodable<Cursor<Vec<u8>>>,
+    S: Serializer,
+{
+    serializer.serialize_bytes(&encode::serialize(value))
+}
+
+/// Deserialize a consensus decodable value from a string
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+    D: Deserializer<'de>,
+{
+    decode(&deserializer.deserialize_byte_buf(BytesVisitor)?)
+}
+
+/// Serde helpers for a list of consensus encoded values, each value is a string
+pub mod vec {
+    use super::*;
+
+    /// Serialize consensus encodable values as a list of strings
+    pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Encodable<Cursor<Vec<u8>>>,
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values {
+            seq.serialize_element(&Blob(encode::serialize(value)))?;
+        }
+        seq.end()
+    }
+
+    /// Deserialize consensus decodable values from a list of strings
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+        D: Deserializer<'de>,
+    {
+        struct BlobsVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for BlobsVisitor<T>
+        where
+            T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+        {
+            type Value = Vec<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a list of blobs")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+                let mut values = vec![];
+                while let Some(Blob(bytes)) = seq.next_element()? {
+                    values.push(decode(&bytes)?);
+                }
+                Ok(values)
+            }
+        }
+
+        deserializer.deserialize_seq(BlobsVisitor(PhantomData))
+    }
+
+    /// Raw blob serialized and deserialized as a string
+    struct Blob(Vec<u8>);
+
+    impl serde::Serialize for Blob {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for Blob {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Blob, D::Error> {
+            deserializer.deserialize_byte_buf(BytesVisitor).map(Blob)
+        }
+    }
+}
+
+/// Fixed-size value stored as raw bytes in a concatenated blob
+pub trait Pod: Sized {
+    /// Size of the value in bytes
+    const SIZE: usize;
+
+    /// Append the bytes of the value
+    fn write_bytes(&self, bytes: &mut Vec<u8>);
+
+    /// Read the value from exactly `SIZE` bytes
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+impl Pod for Hash {
+    const SIZE: usize = 32;
+
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self.as_bytes());
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Hash::from_slice(bytes)
+    }
+}
+
+impl Pod for u64 {
+    const SIZE: usize = 8;
+
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(bytes);
+        u64::from_le_bytes(buf)
+    }
+}
+
+/// Serde helpers for a list of fixed-size values stored as a single string
+///
+/// **Same as** `KV_SERIALIZE_CONTAINER_POD_AS_BLOB` in epee
+pub mod pod {
+    use super::*;
+
+    /// Serialize values as a single string of concatenated bytes
+    pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Pod,
+        S: Serializer,
+    {
+        let mut bytes = Vec::with_capacity(values.len() * T::SIZE);
+        for value in values {
+            value.write_bytes(&mut bytes);
+        }
+        serializer.serialize_bytes(&bytes)
+    }
+
+    /// Deserialize values from a single string of concatenated bytes
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: Pod,
+        D: Deserializer<'de>,
+    {
+        let bytes = deserializer.deserialize_byte_buf(BytesVisitor)?;
+        if bytes.len() % T::SIZE != 0 {
+            return Err(de::Error::invalid_length(
+                bytes.len(),
+                &"a multiple of the value size",
+            ));
+        }
+        Ok(bytes.chunks(T::SIZE).map(T::from_bytes).collect())
+    }
+}
+
+/// Serde helpers for raw bytes stored as a string
+pub mod bytes {
+    use super::*;
+
+    /// Serialize raw bytes as a string
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    /// Deserialize raw bytes from a string
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
@@ -0,0 +1,337 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Serde deserializer from portable storage values
+//!
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use super::{from_bytes, Array, Error, Section, Value};
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+/// Deserialize a value from a portable storage section
+pub fn from_section<T: DeserializeOwned>(section: Section) -> Result<T, Error> {
+    T::deserialize(Value::Section(section))
+}
+
+/// Deserialize a value from a portable storage, the slice must be consumed entirely
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    from_section(from_bytes(bytes)?)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+/// Return the bytes of a string as a sequence of bytes values, as written for fixed-size arrays
+fn bytes_as_values(bytes: Vec<u8>) -> Vec<Value> {
+    bytes.into_iter().map(Value::U8).collect()
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::I64(v) => visitor.visit_i64(v),
+            Value::I32(v) => visitor.visit_i32(v),
+            Value::I16(v) => visitor.visit_i16(v),
+            Value::I8(v) => visitor.visit_i8(v),
+            Value::U64(v) => visitor.visit_u64(v),
+            Value::U32(v) => visitor.visit_u32(v),
+            Value::U16(v) => visitor.visit_u16(v),
+            Value::U8(v) => visitor.visit_u8(v),
+            Value::F64(v) => visitor.visit_f64(v),
+            Value::Bytes(v) => visitor.visit_byte_buf(v),
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::Section(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            Value::Array(v) => visitor.visit_seq(SeqDeserializer::new(v.into_values().into_iter())),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Array(Array::U8(v)) => visitor.visit_byte_buf(v),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Absent values are never written, a value in the storage is always some value
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Bytes(v) => {
+                visitor.visit_seq(SeqDeserializer::new(bytes_as_values(v).into_iter()))
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Value::Bytes(variant) => visitor.visit_enum(EnumDeserializer {
+                variant: String::from_utf8(variant).map_err(|_| Error::InvalidName)?,
+                value: None,
+            }),
+            Value::Section(section) if section.len() == 1 => {
+                let (variant, value) = section.into_iter().next().expect("One entry. QED");
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            _ => Err(Error::TypeMismatch(
+                "enum must be a string or a section with a single entry",
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string map struct
+        identifier
+    }
+}
+
+/// Access to an enum variant name and its optional data
+struct EnumDeserializer {
+    variant: String,
+    value: Option<Value>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), Error> {
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+/// Access to the data of an enum variant
+struct VariantDeserializer(Option<Value>);
+
+impl VariantDeserializer {
+    fn value(self) -> Result<Value, Error> {
+        self.0
+            .ok_or(Error::TypeMismatch("enum variant data is missing"))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.value()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.value()?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self.value()?, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::super::{from_bytes, to_section, to_vec, Array, Value};
+    use super::from_slice;
+    use crate::cryptonote::hash::Hash;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Ok,
+        Busy { retry: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Peer {
+        id: u64,
+        ip: u32,
+        port: u16,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Response {
+        status: String,
+        height: u64,
+        top_hash: Hash,
+        untrusted: bool,
+        peers: Vec<Peer>,
+        #[serde(default)]
+        missing: Vec<u64>,
+        offset: Option<i32>,
+        state: Status,
+        other_state: Status,
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let response = Response {
+            status: "OK".to_string(),
+            height: 2_200_000,
+            top_hash: Hash::hash(b"top"),
+            untrusted: false,
+            peers: vec![
+                Peer {
+                    id: 1,
+                    ip: 0x0100_007f,
+                    port: 18080,
+                },
+                Peer {
+                    id: 2,
+                    ip: 0x0200_007f,
+                    port: 18081,
+                },
+            ],
+            missing: vec![],
+            offset: None,
+            state: Status::Ok,
+            other_state: Status::Busy { retry: 5 },
+        };
+
+        let section = to_section(&response).unwrap();
+        // Hashes are strings, empty sequences and `None` are not written
+        assert_eq!(
+            Some(&Value::Bytes(Hash::hash(b"top").to_bytes().to_vec())),
+            section.get("top_hash")
+        );
+        assert_eq!(None, section.get("missing"));
+        assert_eq!(None, section.get("offset"));
+        assert_eq!(Some(&Value::from("Ok")), section.get("state"));
+        assert!(matches!(
+            section.get("peers"),
+            Some(Value::Array(Array::Section(peers))) if peers.len() == 2
+        ));
+
+        let bytes = to_vec(&response).unwrap();
+        assert_eq!(section, from_bytes(&bytes).unwrap());
+        assert_eq!(response, from_slice(&bytes).unwrap());
+    }
+
+    #[test]
+    fn integer_widening() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Narrow {
+            value: u32,
+            values: Vec<u8>,
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Wide {
+            value: u64,
+            values: Vec<u64>,
+        }
+
+        let bytes = to_vec(&Narrow {
+            value: 7,
+            values: vec![1, 2],
+        })
+        .unwrap();
+        assert_eq!(
+            Wide {
+                value: 7,
+                values: vec![1, 2]
+            },
+            from_slice(&bytes).unwrap()
+        );
+    }
+}
@@ -0,0 +1,357 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Epee portable storage
+//!
+//! This module implements the binary "portable storage" format of the `epee` library used by
+//! monerod for P2P messages and binary RPC endpoints such as `/get_blocks.bin`. A storage is a
+//! signature header followed by a root [`Section`], i.e. a set of named and typed entries.
+//!
+//! The dynamic [`Value`] model can be read and written with [`from_bytes`], [`read_section`],
+//! [`to_bytes`] and [`write_section`]. With the `serde_support` feature Rust types can be
+//! (de)serialized directly with [`to_vec`] and [`from_slice`].
+//!
+//! ```rust
+//! use monero::portable_storage::{self, Section, Value};
+//!
+//! let mut section = Section::new();
+//! section.insert("height".to_string(), Value::U64(2_200_000));
+//! section.insert("status".to_string(), Value::Bytes(b"OK".to_vec()));
+//!
+//! let bytes = portable_storage::to_bytes(&section).unwrap();
+//! assert_eq!(section, portable_storage::from_bytes(&bytes).unwrap());
+//! ```
+//!
+//! ## Serde mapping
+//!
+//! Structs and maps are sections, sequences are arrays and fixed-size arrays of bytes, such as
+//! hashes and keys, are strings as done by `KV_SERIALIZE_VAL_POD_AS_BLOB`. Like epee, `None`
+//! and empty sequences are not written, such fields must be deserialized with a default value.
+//!
+
+mod binary;
+#[cfg(feature = "serde_support")]
+pub mod blob;
+#[cfg(feature = "serde_support")]
+mod de;
+#[cfg(feature = "serde_support")]
+mod ser;
+
+pub use binary::{from_bytes, read_section, to_bytes, write_section};
+#[cfg(feature = "serde_support")]
+pub use de::{from_section, from_slice};
+#[cfg(feature = "serde_support")]
+pub use ser::{to_section, to_vec};
+
+use std::collections::BTreeMap;
+use std::io;
+
+use thiserror::Error;
+
+/// First signature of a portable storage
+///
+/// **Same as** `PORTABLE_STORAGE_SIGNATUREA` in `epee/include/storages/portable_storage_base.h`
+pub const SIGNATURE_A: u32 = 0x0101_1101;
+/// Second signature of a portable storage
+///
+/// **Same as** `PORTABLE_STORAGE_SIGNATUREB` in `epee/include/storages/portable_storage_base.h`
+pub const SIGNATURE_B: u32 = 0x0102_0101;
+/// Format version of a portable storage
+///
+/// **Same as** `PORTABLE_STORAGE_FORMAT_VER` in `epee/include/storages/portable_storage_base.h`
+pub const FORMAT_VERSION: u8 = 1;
+/// Maximum nesting of sections and arrays accepted when reading a storage
+///
+/// **Same as** `EPEE_PORTABLE_STORAGE_RECURSION_LIMIT_INTERNAL` in `epee`
+pub const MAX_DEPTH: usize = 100;
+/// Maximum value of a size encoded in a portable storage
+pub const MAX_SIZE: u64 = (1 << 62) - 1;
+
+/// Possible errors when reading or writing a portable storage
+#[derive(Error, Debug)]
+pub enum Error {
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The storage does not start with the portable storage signatures
+    #[error("invalid portable storage signature")]
+    InvalidSignature,
+    /// The storage format version is not supported
+    #[error("unsupported portable storage version: {0}")]
+    UnsupportedVersion(u8),
+    /// An entry has an unknown type
+    #[error("unknown entry type: {0:#x}")]
+    UnknownType(u8),
+    /// An entry name is not valid UTF-8
+    #[error("entry name is not valid UTF-8")]
+    InvalidName,
+    /// An entry name is longer than 255 bytes
+    #[error("entry name of {0} bytes is too long")]
+    NameTooLong(usize),
+    /// A size is too large to be encoded
+    #[error("size {0} is too large")]
+    SizeTooLarge(u64),
+    /// Sections and arrays are nested too deeply
+    #[error("maximum nesting depth exceeded")]
+    DepthLimitExceeded,
+    /// The input contains bytes after the root section
+    #[error("{0} trailing bytes after the root section")]
+    TrailingBytes(usize),
+    /// The elements of an array do not have the same type
+    #[error("array elements do not have the same type")]
+    HeterogeneousArray,
+    /// The value cannot be represented as, or converted from, the expected type
+    #[error("type mismatch: {0}")]
+    TypeMismatch(&'static str),
+    /// Custom error from serde
+    #[error("{0}")]
+    Custom(String),
+}
+
+/// A section, i.e. a set of named entries ordered by name as done by epee
+pub type Section = BTreeMap<String, Value>;
+
+/// A portable storage entry value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Signed 64 bits integer
+    I64(i64),
+    /// Signed 32 bits integer
+    I32(i32),
+    /// Signed 16 bits integer
+    I16(i16),
+    /// Signed 8 bits integer
+    I8(i8),
+    /// Unsigned 64 bits integer
+    U64(u64),
+    /// Unsigned 32 bits integer
+    U32(u32),
+    /// Unsigned 16 bits integer
+    U16(u16),
+    /// Unsigned 8 bits integer
+    U8(u8),
+    /// Double precision float
+    F64(f64),
+    /// String, epee strings are arbitrary bytes and are used for binary blobs
+    Bytes(Vec<u8>),
+    /// Boolean
+    Bool(bool),
+    /// Nested section
+    Section(Section),
+    /// Array of values of the same type
+    Array(Array),
+}
+
+/// A portable storage array, all elements have the same type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Array {
+    /// Array of signed 64 bits integers
+    I64(Vec<i64>),
+    /// Array of signed 32 bits integers
+    I32(Vec<i32>),
+    /// Array of signed 16 bits integers
+    I16(Vec<i16>),
+    /// Array of signed 8 bits integers
+    I8(Vec<i8>),
+    /// Array of unsigned 64 bits integers
+    U64(Vec<u64>),
+    /// Array of unsigned 32 bits integers
+    U32(Vec<u32>),
+    /// Array of unsigned 16 bits integers
+    U16(Vec<u16>),
+    /// Array of unsigned 8 bits integers
+    U8(Vec<u8>),
+    /// Array of double precision floats
+    F64(Vec<f64>),
+    /// Array of strings
+    Bytes(Vec<Vec<u8>>),
+    /// Array of booleans
+    Bool(Vec<bool>),
+    /// Array of sections
+    Section(Vec<Section>),
+    /// Array of arrays
+    Array(Vec<Array>),
+}
+
+// Entry types, **same as** `portable_storage_base.h`
+const TYPE_INT64: u8 = 1;
+const TYPE_INT32: u8 = 2;
+const TYPE_INT16: u8 = 3;
+const TYPE_INT8: u8 = 4;
+const TYPE_UINT64: u8 = 5;
+const TYPE_UINT32: u8 = 6;
+const TYPE_UINT16: u8 = 7;
+const TYPE_UINT8: u8 = 8;
+const TYPE_DOUBLE: u8 = 9;
+const TYPE_STRING: u8 = 10;
+const TYPE_BOOL: u8 = 11;
+const TYPE_OBJECT: u8 = 12;
+const TYPE_ARRAY: u8 = 13;
+const FLAG_ARRAY: u8 = 0x80;
+
+impl Value {
+    /// Return the epee type code of the value
+    fn type_code(&self) -> u8 {
+        match self {
+            Value::I64(_) => TYPE_INT64,
+            Value::I32(_) => TYPE_INT32,
+            Value::I16(_) => TYPE_INT16,
+            Value::I8(_) => TYPE_INT8,
+            Value::U64(_) => TYPE_UINT64,
+            Value::U32(_) => TYPE_UINT32,
+            Value::U16(_) => TYPE_UINT16,
+            Value::U8(_) => TYPE_UINT8,
+            Value::F64(_) => TYPE_DOUBLE,
+            Value::Bytes(_) => TYPE_STRING,
+            Value::Bool(_) => TYPE_BOOL,
+            Value::Section(_) => TYPE_OBJECT,
+            Value::Array(_) => TYPE_ARRAY,
+        }
+    }
+}
+
+impl Array {
+    /// Return the number of elements in the array
+    pub fn len(&self) -> usize {
+        match self {
+            Array::I64(v) => v.len(),
+            Array::I32(v) => v.len(),
+            Array::I16(v) => v.len(),
+            Array::I8(v) => v.len(),
+            Array::U64(v) => v.len(),
+            Array::U32(v) => v.len(),
+            Array::U16(v) => v.len(),
+            Array::U8(v) => v.len(),
+            Array::F64(v) => v.len(),
+            Array::Bytes(v) => v.len(),
+            Array::Bool(v) => v.len(),
+            Array::Section(v) => v.len(),
+            Array::Array(v) => v.len(),
+        }
+    }
+
+    /// Return whether the array is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the elements of the array as values
+    pub fn into_values(self) -> Vec<Value> {
+        match self {
+            Array::I64(v) => v.into_iter().map(Value::I64).collect(),
+            Array::I32(v) => v.into_iter().map(Value::I32).collect(),
+            Array::I16(v) => v.into_iter().map(Value::I16).collect(),
+            Array::I8(v) => v.into_iter().map(Value::I8).collect(),
+            Array::U64(v) => v.into_iter().map(Value::U64).collect(),
+            Array::U32(v) => v.into_iter().map(Value::U32).collect(),
+            Array::U16(v) => v.into_iter().map(Value::U16).collect(),
+            Array::U8(v) => v.into_iter().map(Value::U8).collect(),
+            Array::F64(v) => v.into_iter().map(Value::F64).collect(),
+            Array::Bytes(v) => v.into_iter().map(Value::Bytes).collect(),
+            Array::Bool(v) => v.into_iter().map(Value::Bool).collect(),
+            Array::Section(v) => v.into_iter().map(Value::Section).collect(),
+            Array::Array(v) => v.into_iter().map(Value::Array).collect(),
+        }
+    }
+
+    /// Build an array from values, all values must have the same type and at least one value is
+    /// needed to know the type of the array
+    pub fn from_values(values: Vec<Value>) -> Result<Array, Error> {
+        macro_rules! collect {
+            ($variant:ident) => {
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::$variant(v) => Ok(v),
+                        _ => Err(Error::HeterogeneousArray),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Array::$variant)
+            };
+        }
+
+        match values.first() {
+            None => Err(Error::TypeMismatch("empty array has no type")),
+            Some(Value::I64(_)) => collect!(I64),
+            Some(Value::I32(_)) => collect!(I32),
+            Some(Value::I16(_)) => collect!(I16),
+            Some(Value::I8(_)) => collect!(I8),
+            Some(Value::U64(_)) => collect!(U64),
+            Some(Value::U32(_)) => collect!(U32),
+            Some(Value::U16(_)) => collect!(U16),
+            Some(Value::U8(_)) => collect!(U8),
+            Some(Value::F64(_)) => collect!(F64),
+            Some(Value::Bytes(_)) => collect!(Bytes),
+            Some(Value::Bool(_)) => collect!(Bool),
+            Some(Value::Section(_)) => collect!(Section),
+            Some(Value::Array(_)) => collect!(Array),
+        }
+    }
+
+    /// Return the epee type code of the array elements
+    fn type_code(&self) -> u8 {
+        match self {
+            Array::I64(_) => TYPE_INT64,
+            Array::I32(_) => TYPE_INT32,
+            Array::I16(_) => TYPE_INT16,
+            Array::I8(_) => TYPE_INT8,
+            Array::U64(_) => TYPE_UINT64,
+            Array::U32(_) => TYPE_UINT32,
+            Array::U16(_) => TYPE_UINT16,
+            Array::U8(_) => TYPE_UINT8,
+            Array::F64(_) => TYPE_DOUBLE,
+            Array::Bytes(_) => TYPE_STRING,
+            Array::Bool(_) => TYPE_BOOL,
+            Array::Section(_) => TYPE_OBJECT,
+            Array::Array(_) => TYPE_ARRAY,
+        }
+    }
+}
+
+macro_rules! impl_value_from {
+    ($ty:ty, $variant:ident) => {
+        impl From<$ty> for Value {
+            fn from(v: $ty) -> Value {
+                Value::$variant(v)
+            }
+        }
+    };
+}
+
+impl_value_from!(i64, I64);
+impl_value_from!(i32, I32);
+impl_value_from!(i16, I16);
+impl_value_from!(i8, I8);
+impl_value_from!(u64, U64);
+impl_value_from!(u32, U32);
+impl_value_from!(u16, U16);
+impl_value_from!(u8, U8);
+impl_value_from!(f64, F64);
+impl_value_from!(Vec<u8>, Bytes);
+impl_value_from!(bool, Bool);
+impl_value_from!(Section, Section);
+impl_value_from!(Array, Array);
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Value {
+        Value::Bytes(v.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Value {
+        Value::Bytes(v.into_bytes())
+    }
+}
@@ -0,0 +1,806 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! JSON representation of monerod
+//!
+//! Daemon RPC methods return blocks and transactions in the JSON representation of the
+//! serialization archives of monerod, e.g. the `json` field of `get_block` or the `as_json`
+//! field of `get_transactions` with `decode_as_json`. This format differs from the one derived
+//! with the `serde_support` feature, the modules [`transaction`] and [`block`] implement it and
+//! are meant to be used with `#[serde(with = "...")]`.
+//!
+//! ```rust
+//! use monero::rpc::as_json;
+//!
+//! let json = r#"{
+//!   "major_version": 12,
+//!   "minor_version": 12,
+//!   "timestamp": 1594797844,
+//!   "prev_id": "beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b62",
+//!   "nonce": 0,
+//!   "miner_tx": {
+//!     "version": 2,
+//!     "unlock_time": 558235,
+//!     "vin": [ {
+//!         "gen": {
+//!           "height": 558175
+//!         }
+//!       }
+//!     ],
+//!     "vout": [ {
+//!         "amount": 12520250477750,
+//!         "target": {
+//!           "key": "23df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a1834940"
+//!         }
+//!       }
+//!     ],
+//!     "extra": [ 1, 40, 57, 191, 161, 155, 117, 36, 236, 116, 136, 145, 119, 20, 194, 22, 202, 37, 75, 56, 237, 4, 36, 202, 101, 174, 130, 138, 124, 0, 106, 234, 241, 2, 8, 245, 49, 106, 127, 107, 153, 204, 166
+//!     ],
+//!     "rct_signatures": {
+//!       "type": 0
+//!     }
+//!   },
+//!   "tx_hashes": [ ]
+//! }"#;
+//!
+//! let block = as_json::block_from_str(json).unwrap();
+//! assert_eq!(12, *block.header.major_version);
+//!
+//! let json: serde_json::Value = serde_json::from_str(json).unwrap();
+//! let block = as_json::block_to_string(&block).unwrap();
+//! assert_eq!(json, serde_json::from_str::<serde_json::Value>(&block).unwrap());
+//! ```
+//!
+//! **Same as** [`cryptonote_basic.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/cryptonote_basic/cryptonote_basic.h)
+//! and [`rctTypes.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/ringct/rctTypes.h)
+//! serialized with [`json_archive.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/serialization/json_archive.h).
+//!
+
+use serde::de;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::blockdata::block::{Block, BlockHeader};
+use crate::blockdata::transaction::{
+    ExtraField, KeyImage, Transaction, TransactionPrefix, TxIn, TxOut, TxOutTarget,
+};
+use crate::consensus::encode::{self, VarInt};
+use crate::cryptonote::hash::{Hash, Hash8};
+use crate::rpc::serde_hex::Hex;
+use crate::util::key::PublicKey;
+use crate::util::ringct::{
+    BoroSig, Bulletproof, CtKey, EcdhInfo, Key, Key64, MgSig, RangeSig, RctSig, RctSigBase,
+    RctSigPrunable, RctType, Signature, CLSAG,
+};
+
+/// Serde helpers for a [`Transaction`]
+///
+/// [`Transaction`]: crate::blockdata::transaction::Transaction
+pub mod transaction {
+    use super::*;
+
+    /// Serialize a transaction in the JSON representation of monerod
+    pub fn serialize<S: Serializer>(tx: &Transaction, serializer: S) -> Result<S::Ok, S::Error> {
+        TransactionRepr::from_transaction(tx).serialize(serializer)
+    }
+
+    /// Deserialize a transaction from the JSON representation of monerod
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Transaction, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        TransactionRepr::deserialize(deserializer)?.into_transaction()
+    }
+}
+
+/// Serde helpers for a [`Block`]
+///
+/// [`Block`]: crate::blockdata::block::Block
+pub mod block {
+    use super::*;
+
+    /// Serialize a block in the JSON representation of monerod
+    pub fn serialize<S: Serializer>(block: &Block, serializer: S) -> Result<S::Ok, S::Error> {
+        BlockRepr {
+            major_version: *block.header.major_version,
+            minor_version: *block.header.minor_version,
+            timestamp: *block.header.timestamp,
+            prev_id: Hex(block.header.prev_id),
+            nonce: block.header.nonce,
+            miner_tx: TransactionRepr::from_transaction(&block.miner_tx),
+            tx_hashes: block.tx_hashes.iter().map(|h| Hex(*h)).collect(),
+        }
+        .serialize(serializer)
+    }
+
+    /// Deserialize a block from the JSON representation of monerod
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Block, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let block = BlockRepr::deserialize(deserializer)?;
+        Ok(Block {
+            header: BlockHeader {
+                major_version: VarInt(block.major_version),
+                minor_version: VarInt(block.minor_version),
+                timestamp: VarInt(block.timestamp),
+                prev_id: block.prev_id.0,
+                nonce: block.nonce,
+            },
+            miner_tx: block.miner_tx.into_transaction()?,
+            tx_hashes: block.tx_hashes.into_iter().map(|h| h.0).collect(),
+        })
+    }
+}
+
+struct TransactionJson<'a>(&'a Transaction);
+
+impl Serialize for TransactionJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        transaction::serialize(self.0, serializer)
+    }
+}
+
+struct BlockJson<'a>(&'a Block);
+
+impl Serialize for BlockJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        block::serialize(self.0, serializer)
+    }
+}
+
+/// Parse a transaction from the JSON representation of monerod
+pub fn transaction_from_str(s: &str) -> Result<Transaction, serde_json::Error> {
+    transaction::deserialize(&mut serde_json::Deserializer::from_str(s))
+}
+
+/// Write a transaction in the JSON representation of monerod
+pub fn transaction_to_string(tx: &Transaction) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&TransactionJson(tx))
+}
+
+/// Parse a block from the JSON representation of monerod
+pub fn block_from_str(s: &str) -> Result<Block, serde_json::Error> {
+    block::deserialize(&mut serde_json::Deserializer::from_str(s))
+}
+
+/// Write a block in the JSON representation of monerod
+pub fn block_to_string(block: &Block) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&BlockJson(block))
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockRepr {
+    major_version: u64,
+    minor_version: u64,
+    timestamp: u64,
+    prev_id: Hex<Hash>,
+    nonce: u32,
+    miner_tx: TransactionRepr,
+    tx_hashes: Vec<Hex<Hash>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TransactionRepr {
+    version: u64,
+    unlock_time: u64,
+    vin: Vec<InputRepr>,
+    vout: Vec<OutputRepr>,
+    extra: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signatures: Option<Vec<SignaturesRepr>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rct_signatures: Option<RctSigBaseRepr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rctsig_prunable: Option<RctSigPrunableRepr>,
+}
+
+/// Signatures of an input, monerod writes them as a single blob
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SignaturesRepr {
+    Blob(String),
+    List(Vec<Hex<Signature>>),
+}
+
+/// Script inputs and outputs are not supported by the crate, their content is skipped
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Unsupported {}
+
+/// Input of a transaction, also read from the ZMQ feed which uses the names of `json_object.cpp`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum InputRepr {
+    Gen {
+        height: u64,
+    },
+    #[serde(alias = "to_key")]
+    Key {
+        amount: u64,
+        key_offsets: Vec<u64>,
+        #[serde(alias = "key_image")]
+        k_image: Hex<KeyImage>,
+    },
+    #[serde(alias = "to_script")]
+    Script(Unsupported),
+    #[serde(alias = "to_scripthash")]
+    Scripthash(Unsupported),
+}
+
+#[derive(Serialize, Deserialize)]
+struct OutputRepr {
+    amount: u64,
+    target: TargetRepr,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TargetRepr {
+    Key(Hex<PublicKey>),
+    Script {
+        keys: Vec<Hex<PublicKey>>,
+        script: Vec<u8>,
+    },
+    Scripthash {
+        hash: Hex<Hash>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct RctSigBaseRepr {
+    #[serde(rename = "type")]
+    rct_type: u8,
+    #[serde(rename = "txnFee", default, skip_serializing_if = "Option::is_none")]
+    txn_fee: Option<u64>,
+    #[serde(
+        rename = "pseudoOuts",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pseudo_outs: Option<Vec<Hex<Key>>>,
+    #[serde(rename = "ecdhInfo", default, skip_serializing_if = "Option::is_none")]
+    ecdh_info: Option<Vec<EcdhInfoRepr>>,
+    #[serde(rename = "outPk", default, skip_serializing_if = "Option::is_none")]
+    out_pk: Option<Vec<Hex<Key>>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EcdhInfoRepr {
+    Standard { mask: Hex<Key>, amount: Hex<Key> },
+    Bulletproof { amount: Hex<Hash8> },
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+struct RctSigPrunableRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nbp: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bp: Option<Vec<BulletproofRepr>>,
+    #[serde(rename = "rangeSigs", default, skip_serializing_if = "Option::is_none")]
+    range_sigs: Option<Vec<RangeSigRepr>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    CLSAGs: Option<Vec<ClsagRepr>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    MGs: Option<Vec<MgSigRepr>>,
+    #[serde(
+        rename = "pseudoOuts",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pseudo_outs: Option<Vec<Hex<Key>>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BulletproofRepr {
+    A: Hex<Key>,
+    S: Hex<Key>,
+    T1: Hex<Key>,
+    T2: Hex<Key>,
+    taux: Hex<Key>,
+    mu: Hex<Key>,
+    L: Vec<Hex<Key>>,
+    R: Vec<Hex<Key>>,
+    a: Hex<Key>,
+    b: Hex<Key>,
+    t: Hex<Key>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+struct RangeSigRepr {
+    asig: BoroSigRepr,
+    Ci: Hex<Key64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BoroSigRepr {
+    s0: Hex<Key64>,
+    s1: Hex<Key64>,
+    ee: Hex<Key>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct MgSigRepr {
+    ss: Vec<Vec<Hex<Key>>>,
+    cc: Hex<Key>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ClsagRepr {
+    s: Vec<Hex<Key>>,
+    c1: Hex<Key>,
+    D: Hex<Key>,
+}
+
+fn hex_keys(keys: &[Key]) -> Vec<Hex<Key>> {
+    keys.iter().cloned().map(Hex).collect()
+}
+
+pub(crate) fn keys(keys: Vec<Hex<Key>>) -> Vec<Key> {
+    keys.into_iter().map(|k| k.0).collect()
+}
+
+pub(crate) fn rct_type_from_u8<E: de::Error>(rct_type: u8) -> Result<RctType, E> {
+    match rct_type {
+        0 => Ok(RctType::Null),
+        1 => Ok(RctType::Full),
+        2 => Ok(RctType::Simple),
+        3 => Ok(RctType::Bulletproof),
+        4 => Ok(RctType::Bulletproof2),
+        5 => Ok(RctType::CLSAG),
+        t => Err(E::custom(format!("unsupported RingCT type {}", t))),
+    }
+}
+
+fn rct_type_to_u8(rct_type: RctType) -> u8 {
+    match rct_type {
+        RctType::Null => 0,
+        RctType::Full => 1,
+        RctType::Simple => 2,
+        RctType::Bulletproof => 3,
+        RctType::Bulletproof2 => 4,
+        RctType::CLSAG => 5,
+    }
+}
+
+/// Parse the raw bytes of the extra field, written without the length of its consensus encoding
+pub(crate) fn extra_from_bytes<E: de::Error>(extra: &[u8]) -> Result<ExtraField, E> {
+    encode::deserialize(&encode::serialize(extra))
+        .map_err(|e| E::custom(format!("invalid extra: {:?}", e)))
+}
+
+impl From<InputRepr> for TxIn {
+    fn from(input: InputRepr) -> TxIn {
+        match input {
+            InputRepr::Gen { height } => TxIn::Gen {
+                height: VarInt(height),
+            },
+            InputRepr::Key {
+                amount,
+                key_offsets,
+                k_image,
+            } => TxIn::ToKey {
+                amount: VarInt(amount),
+                key_offsets: key_offsets.into_iter().map(VarInt).collect(),
+                k_image: k_image.0,
+            },
+            InputRepr::Script(_) => TxIn::ToScript,
+            InputRepr::Scripthash(_) => TxIn::ToScriptHash,
+        }
+    }
+}
+
+impl From<&Bulletproof> for BulletproofRepr {
+    fn from(bp: &Bulletproof) -> BulletproofRepr {
+        BulletproofRepr {
+            A: Hex(bp.A.clone()),
+            S: Hex(bp.S.clone()),
+            T1: Hex(bp.T1.clone()),
+            T2: Hex(bp.T2.clone()),
+            taux: Hex(bp.taux.clone()),
+            mu: Hex(bp.mu.clone()),
+            L: hex_keys(&bp.L),
+            R: hex_keys(&bp.R),
+            a: Hex(bp.a.clone()),
+            b: Hex(bp.b.clone()),
+            t: Hex(bp.t.clone()),
+        }
+    }
+}
+
+impl From<BulletproofRepr> for Bulletproof {
+    fn from(bp: BulletproofRepr) -> Bulletproof {
+        Bulletproof {
+            A: bp.A.0,
+            S: bp.S.0,
+            T1: bp.T1.0,
+            T2: bp.T2.0,
+            taux: bp.taux.0,
+            mu: bp.mu.0,
+            L: keys(bp.L),
+            R: keys(bp.R),
+            a: bp.a.0,
+            b: bp.b.0,
+            t: bp.t.0,
+        }
+    }
+}
+
+impl From<&MgSig> for MgSigRepr {
+    fn from(mg: &MgSig) -> MgSigRepr {
+        MgSigRepr {
+            ss: mg.ss.iter().map(|ss| hex_keys(ss)).collect(),
+            cc: Hex(mg.cc.clone()),
+        }
+    }
+}
+
+impl From<MgSigRepr> for MgSig {
+    fn from(mg: MgSigRepr) -> MgSig {
+        MgSig {
+            ss: mg.ss.into_iter().map(keys).collect(),
+            cc: mg.cc.0,
+        }
+    }
+}
+
+impl From<&CLSAG> for ClsagRepr {
+    fn from(clsag: &CLSAG) -> ClsagRepr {
+        ClsagRepr {
+            s: hex_keys(&clsag.s),
+            c1: Hex(clsag.c1.clone()),
+            D: Hex(clsag.D.clone()),
+        }
+    }
+}
+
+impl From<ClsagRepr> for CLSAG {
+    fn from(clsag: ClsagRepr) -> CLSAG {
+        CLSAG {
+            s: keys(clsag.s),
+            c1: clsag.c1.0,
+            D: clsag.D.0,
+        }
+    }
+}
+
+impl TransactionRepr {
+    fn from_transaction(tx: &Transaction) -> TransactionRepr {
+        let prefix = &tx.prefix;
+        let vin = prefix
+            .inputs
+            .iter()
+            .map(|input| match input {
+                TxIn::Gen { height } => InputRepr::Gen { height: **height },
+                TxIn::ToKey {
+                    amount,
+                    key_offsets,
+                    k_image,
+                } => InputRepr::Key {
+                    amount: **amount,
+                    key_offsets: key_offsets.iter().map(|o| **o).collect(),
+                    k_image: Hex(k_image.clone()),
+                },
+                TxIn::ToScript => InputRepr::Script(Unsupported {}),
+                TxIn::ToScriptHash => InputRepr::Scripthash(Unsupported {}),
+            })
+            .collect();
+        let vout = prefix
+            .outputs
+            .iter()
+            .map(|output| OutputRepr {
+                amount: *output.amount,
+                target: match &output.target {
+                    TxOutTarget::ToKey { key } => TargetRepr::Key(Hex(*key)),
+                    TxOutTarget::ToScript { keys, script } => TargetRepr::Script {
+                        keys: keys.iter().map(|k| Hex(*k)).collect(),
+                        script: script.clone(),
+                    },
+                    TxOutTarget::ToScriptHash { hash } => {
+                        TargetRepr::Scripthash { hash: Hex(*hash) }
+                    }
+                },
+            })
+            .collect();
+        // Raw bytes of the extra field, without the length of its consensus encoding
+        let mut extra: Vec<u8> = vec![];
+        for field in prefix.extra.0.iter() {
+            extra.extend(encode::serialize(field));
+        }
+
+        let mut repr = TransactionRepr {
+            version: *prefix.version,
+            unlock_time: *prefix.unlock_time,
+            vin,
+            vout,
+            extra,
+            signatures: None,
+            rct_signatures: None,
+            rctsig_prunable: None,
+        };
+        if *prefix.version == 1 {
+            repr.signatures = Some(
+                tx.signatures
+                    .iter()
+                    .map(|sigs| {
+                        let blob: Vec<u8> = sigs.iter().flat_map(encode::serialize).collect();
+                        SignaturesRepr::Blob(hex::encode(blob))
+                    })
+                    .collect(),
+            );
+            return repr;
+        }
+        if let Some(sig) = &tx.rct_signatures.sig {
+            repr.rct_signatures = Some(RctSigBaseRepr::from_base(sig));
+            if let Some(p) = &tx.rct_signatures.p {
+                repr.rctsig_prunable = Some(RctSigPrunableRepr::from_prunable(p, sig.rct_type));
+            }
+        }
+        repr
+    }
+
+    fn into_transaction<E: de::Error>(self) -> Result<Transaction, E> {
+        let extra = extra_from_bytes(&self.extra)?;
+        let inputs = self.vin.into_iter().map(TxIn::from).collect();
+        let outputs = self
+            .vout
+            .into_iter()
+            .map(|output| TxOut {
+                amount: VarInt(output.amount),
+                target: match output.target {
+                    TargetRepr::Key(key) => TxOutTarget::ToKey { key: key.0 },
+                    TargetRepr::Script { keys, script } => TxOutTarget::ToScript {
+                        keys: keys.into_iter().map(|k| k.0).collect(),
+                        script,
+                    },
+                    TargetRepr::Scripthash { hash } => TxOutTarget::ToScriptHash { hash: hash.0 },
+                },
+            })
+            .collect();
+        let prefix = TransactionPrefix {
+            version: VarInt(self.version),
+            unlock_time: VarInt(self.unlock_time),
+            inputs,
+            outputs,
+            extra,
+        };
+
+        let signatures = self
+            .signatures
+            .unwrap_or_default()
+            .into_iter()
+            .map(|sigs| match sigs {
+                SignaturesRepr::Blob(blob) => {
+                    let bytes = hex::decode(blob).map_err(E::custom)?;
+                    if bytes.len() % 64 != 0 {
+                        return Err(E::custom("invalid signatures length"));
+                    }
+                    bytes
+                        .chunks(64)
+                        .map(|sig| {
+                            encode::deserialize(sig)
+                                .map_err(|e| E::custom(format!("invalid signature: {:?}", e)))
+                        })
+                        .collect()
+                }
+                SignaturesRepr::List(sigs) => Ok(sigs.into_iter().map(|s| s.0).collect()),
+            })
+            .collect::<Result<Vec<Vec<Signature>>, E>>()?;
+        let rct_signatures = match self.rct_signatures {
+            Some(base) => {
+                let sig = base.into_base()?;
+                let p = match self.rctsig_prunable {
+                    Some(p) if sig.rct_type != RctType::Null => Some(p.into_prunable()),
+                    _ => None,
+                };
+                RctSig { sig: Some(sig), p }
+            }
+            None => RctSig { sig: None, p: None },
+        };
+        Ok(Transaction {
+            prefix,
+            signatures,
+            rct_signatures,
+        })
+    }
+}
+
+impl RctSigBaseRepr {
+    fn from_base(sig: &RctSigBase) -> RctSigBaseRepr {
+        let rct_type = rct_type_to_u8(sig.rct_type);
+        if sig.rct_type == RctType::Null {
+            return RctSigBaseRepr {
+                rct_type,
+                txn_fee: None,
+                pseudo_outs: None,
+                ecdh_info: None,
+                out_pk: None,
+            };
+        }
+        RctSigBaseRepr {
+            rct_type,
+            txn_fee: Some(*sig.txn_fee),
+            pseudo_outs: match sig.rct_type {
+                RctType::Simple => Some(hex_keys(&sig.pseudo_outs)),
+                _ => None,
+            },
+            ecdh_info: Some(
+                sig.ecdh_info
+                    .iter()
+                    .map(|ecdh| match ecdh {
+                        EcdhInfo::Standard { mask, amount } => EcdhInfoRepr::Standard {
+                            mask: Hex(mask.clone()),
+                            amount: Hex(amount.clone()),
+                        },
+                        EcdhInfo::Bulletproof { amount } => EcdhInfoRepr::Bulletproof {
+                            amount: Hex(*amount),
+                        },
+                    })
+                    .collect(),
+            ),
+            out_pk: Some(
+                sig.out_pk
+                    .iter()
+                    .map(|out_pk| Hex(out_pk.mask.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn into_base<E: de::Error>(self) -> Result<RctSigBase, E> {
+        let rct_type = rct_type_from_u8(self.rct_type)?;
+        let compact = matches!(rct_type, RctType::Bulletproof2 | RctType::CLSAG);
+        let ecdh_info = self
+            .ecdh_info
+            .unwrap_or_default()
+            .into_iter()
+            .map(|ecdh| match ecdh {
+                EcdhInfoRepr::Standard { mask, amount } if !compact => Ok(EcdhInfo::Standard {
+                    mask: mask.0,
+                    amount: amount.0,
+                }),
+                EcdhInfoRepr::Bulletproof { amount } if compact => {
+                    Ok(EcdhInfo::Bulletproof { amount: amount.0 })
+                }
+                _ => Err(E::custom("ecdhInfo does not match the RingCT type")),
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+        Ok(RctSigBase {
+            rct_type,
+            txn_fee: VarInt(self.txn_fee.unwrap_or_default()),
+            pseudo_outs: keys(self.pseudo_outs.unwrap_or_default()),
+            ecdh_info,
+            out_pk: self
+                .out_pk
+                .unwrap_or_default()
+                .into_iter()
+                .map(|mask| CtKey { mask: mask.0 })
+                .collect(),
+        })
+    }
+}
+
+impl RctSigPrunableRepr {
+    fn from_prunable(p: &RctSigPrunable, rct_type: RctType) -> RctSigPrunableRepr {
+        let is_bp = rct_type.is_rct_bp();
+        RctSigPrunableRepr {
+            nbp: if is_bp {
+                Some(p.bulletproofs.len() as u32)
+            } else {
+                None
+            },
+            bp: if is_bp {
+                Some(p.bulletproofs.iter().map(BulletproofRepr::from).collect())
+            } else {
+                None
+            },
+            range_sigs: if is_bp {
+                None
+            } else {
+                Some(
+                    p.range_sigs
+                        .iter()
+                        .map(|sig| RangeSigRepr {
+                            asig: BoroSigRepr {
+                                s0: Hex(sig.asig.s0.clone()),
+                                s1: Hex(sig.asig.s1.clone()),
+                                ee: Hex(sig.asig.ee.clone()),
+                            },
+                            Ci: Hex(sig.Ci.clone()),
+                        })
+                        .collect(),
+                )
+            },
+            CLSAGs: match rct_type {
+                RctType::CLSAG => Some(p.CLSAGs.iter().map(ClsagRepr::from).collect()),
+                _ => None,
+            },
+            MGs: match rct_type {
+                RctType::CLSAG => None,
+                _ => Some(p.MGs.iter().map(MgSigRepr::from).collect()),
+            },
+            pseudo_outs: if is_bp {
+                Some(hex_keys(&p.pseudo_outs))
+            } else {
+                None
+            },
+        }
+    }
+
+    fn into_prunable(self) -> RctSigPrunable {
+        RctSigPrunable {
+            range_sigs: self
+                .range_sigs
+                .unwrap_or_default()
+                .into_iter()
+                .map(|sig| RangeSig {
+                    asig: BoroSig {
+                        s0: sig.asig.s0.0,
+                        s1: sig.asig.s1.0,
+                        ee: sig.asig.ee.0,
+                    },
+                    Ci: sig.Ci.0,
+                })
+                .collect(),
+            bulletproofs: self
+                .bp
+                .unwrap_or_default()
+                .into_iter()
+                .map(Bulletproof::from)
+                .collect(),
+            MGs: self
+                .MGs
+                .unwrap_or_default()
+                .into_iter()
+                .map(MgSig::from)
+                .collect(),
+            CLSAGs: self
+                .CLSAGs
+                .unwrap_or_default()
+                .into_iter()
+                .map(CLSAG::from)
+                .collect(),
+            pseudo_outs: keys(self.pseudo_outs.unwrap_or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transaction_from_str, transaction_to_string};
+    use crate::cryptonote::hash::Hashable;
+    use serde_json::Value;
+
+    #[test]
+    fn transaction_round_trip() {
+        let json = r#"{"version":2,"unlock_time":0,"vin":[{"key":{"amount":0,"key_offsets":[2306482,29961,40489,6565,382,937,5,765,64,116,95],"k_image":"59f49374365304f8b4d5da63b444b2d74a40f8007ea44940c15cbbc80c9d1068"}}],"vout":[{"amount":0,"target":{"key":"67f0f669ead579c1067cbffdf67c4af80b0287c549a10463122b4860fe215f49"}},{"amount":0,"target":{"key":"b6a2e2f35a93d637ff7d25e20da326cee8e92005d3b18b3c425dabe833656899"}}],"extra":[1,214,199,92,248,199,106,196,88,18,63,42,73,133,18,235,101,187,60,236,186,52,108,143,207,197,22,220,12,136,81,139,185,2,9,1,111,130,53,158,177,254,113,214],"rct_signatures":{"type":4,"txnFee":150630000,"ecdhInfo":[{"amount":"0ed5fd4624bb9fce"},{"amount":"349a0e8317eabf41"}],"outPk":["72f78a8b27dec6ea1a46da10ed8620fa8367c6391eaa8aabf4ebf660d9fe0eb7","e9dfa08365a089ad2df7bce7ef776467898d5ca8947152923c54a1c5030e0c2f"]},"rctsig_prunable":{"nbp":1,"bp":[{"A":"035c555ff4285dcc44dfadd6bc37ec8b9354c045c6590446a81c7f53d8f199ca","S":"ce3faa7f17b3b8302a7cbb3881e8fdc23cca0275c9245fdc2a394b8d3ae73911","T1":"e3541b10e7725cdeef5e0307bc218caefaafe97c102f39c8ce78f62cccf23c69","T2":"baf0af55933c9d384ceaf07488f2f1ac7343a593449afd54d1065f6a1a465884","taux":"5817e4b0e810afc4ca249096e463f9f368625fa37d5bbcbe87af68ce3c4d630f","mu":"93a66defa4205b178f4e9fa04107bd535c7a4b2251df2dad255e470b611ffe00","L":["8c2916fc1eb2af1273e0df30dd1c74b6987b9885e7916b6ca711cbd4b7b50576","e51af1439e9ed9e33eb97d8faba4e3bd46066a5026a1940b852d965c1db455d1","401687ccaccc524e000b05966763564b7deb8fd64c7fb3d649897c94583dca15","58893b071f5e6700dad139f3c6f973c7a43b207ee3e67dc7f7f18b52df442258","200c7fe6d16685127da1df9b0d93d764c2659599bc6d300ae33bf8b7c2a50431","7da90ea2f0bb2af09bd531feae57cb4a0273d8add62fadfc6d43402372e5caf8","54e112b88417936f1a9c4045d48b5b0b7703d96801b35ff66c716cddbee1b924"],"R":["aa069a162c163071710e28ccddf6fb560feea32485f2c54a477ae23fd8210427","eabe4288cbe0ecbef4ed19ca049ceded424d9f839da957f56ffeb73060ea1549","8fcbc2d73606e85e963a667dafdb2641fb91862c07b98c1fdae8fadf51460022","5036dd63c22cdadb57d2125ebf30bc77f7ea0bc0dafb484bf01434954c5053b9","c8a143f06972f80fa66788ea1e3425dc0104a9e3674729967b9819552ebb1724","18da0e4b3778ad4b3d6acd8f354ba09e54bbc8604540010e1e1e4d3066515aed","457bd3399c0ce787236dbcd3923de4fb8faded10199b33c1251191612ab5526c"],"a":"1cf0cd55a0aeaed3f7a955ceced16dabdbeb0a2a19a9fdb5aa8c4fc8767cf70e","b":"4ad1838518bc6b9de7c420c1f57636579a14a5a8bdacd24e61a68adede8a2e07","t":"416c25409dd91ab78905bc99bab4ab4fb9e4ea628e09a271837769c4e67e580d"}],"MGs":[{"cc":"10aae1bb50728ec9b8880d6011b366a850798f6d7fe07103695dded3f371ca09","ss":[["cd5485e12e4e308cb4509686a7484a71f7dfe334499808c7122f07d45d89230b","1f19ed86f675b7fec44ef5f3b178ae0af92ff114bd96baa264604fea5a762307"],["bdce6cb483b7bc780d32ed5343fcc3aa306997f211dc075f6dfd66035c1db10b","ef8656fefbb45645264d401682e42fe3e05906f79d65481b87508f1a4c434e0d"],["1dfc247d4276306f801a6b57e4e4a525177bae24e0bd88a216597d9db44f2604","c29d8a5f74e7b934f55048690b5dcefd6489a81aa64c1edb49b320faab94130e"],["603d99e455cfd828bca782176192ece95e9b967fe3dd698574cf0c0b6926970b","156e1134658de657de42c4930e72b49c0d94da66c330ab188c10f0d2f578590f"],["31bcac6fcff7e21f9ff67ae1a40d5a03b19301dcbbadc1aa9392795cf81f1401","ec16d986a7f96fbb9e8e12ce04a2226e26b78117a4dfb757c6a44481ff68bb09"],["09e7010988cd37146fb45d4cca4ba490aae323bb51a12b6864f88ea6897aa700","ee9142eaf0880844083026f044a5e3dba4aae08578cb057976001beb27b5110c"],["41fe336bf7879733739ce22fb31a1a6ac2c900d6d6c6facdbc60085e5c93d502","542cfea90dbc62d4e061b7106f09f9c4f6c1b5506dd0550eb8b2bf17678b140d"],["e33a10ba676829092e6a13445d1857d06c715eea4492ff864f0b34d178a75a0f","1353078f83cfee1440b0a20e64abbd0cab5c6e7083486002970a4904f8371805"],["d1a0ee4aea8524168f0f39d2dfc55f545a98a031841a740e8422a62e123c8303","021fb81afbb76d1120c0fbc4d3d97ba69f4e2fe086822ece2047c9ccea507008"],["654c199238a5d17f009aa2dd081f7901d0688aa15311865a319ccba8de402302","7235b5725353561c5f1185f6a063fb32fc65ef6e90339d406a6884d66be49d03"],["daaf116ee4b65ef80dd3052a13157b929f98640c0bbe99c8323ce3419a136403","dc3f7a95178c3966d2d7bdecf516a28eb2cf8cddb3a0463dc7a6248883f7be0a"]]}],"pseudoOuts":["7c1d3596967320071d7f548938afe287cb9b8fae761fa592425623dcbf653028"]}}"#;
+        let tx = transaction_from_str(json).unwrap();
+        assert_eq!(
+            "4c9c4f6b3d298c5ffa31139929b34ae53d727adf3de70ed2b8c32835d4513e25",
+            hex::encode(tx.hash().as_bytes())
+        );
+
+        let expected: Value = serde_json::from_str(json).unwrap();
+        let json = transaction_to_string(&tx).unwrap();
+        assert_eq!(expected, serde_json::from_str::<Value>(&json).unwrap());
+
+        // Compact ecdhInfo are only valid with Bulletproof2 and CLSAG types
+        let json = json.replace(r#""type":4"#, r#""type":3"#);
+        assert!(transaction_from_str(&json).is_err());
+    }
+}
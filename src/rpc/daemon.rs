@@ -0,0 +1,716 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Daemon RPC methods
+//!
+//! Requests and responses of the monerod methods, responses only hold the fields needed to use
+//! the embedded blocks and transactions, unknown fields are ignored.
+//!
+//! **Same as** [`core_rpc_server_commands_defs.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/rpc/core_rpc_server_commands_defs.h)
+//!
+
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use super::{serde_hex, Endpoint, Request};
+use crate::blockdata::block::{Block, BlockHeader};
+use crate::blockdata::transaction::Transaction;
+use crate::consensus::encode::VarInt;
+use crate::cryptonote::hash::Hash;
+use crate::p2p::messages::TxBlobEntry;
+use crate::util::key::PublicKey;
+use crate::util::ringct::Key;
+
+/// Block header as returned by the daemon, with the block metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeaderResponse {
+    /// Major version, defines the consensus rules
+    pub major_version: u8,
+    /// Minor version, also used to vote
+    pub minor_version: u8,
+    /// Block timestamp
+    pub timestamp: u64,
+    /// Previous block hash
+    #[serde(with = "serde_hex")]
+    pub prev_hash: Hash,
+    /// Nonce
+    pub nonce: u32,
+    /// Whether the block is not in the main chain
+    #[serde(default)]
+    pub orphan_status: bool,
+    /// Height of the block
+    pub height: u64,
+    /// Number of blocks on top of the block
+    #[serde(default)]
+    pub depth: u64,
+    /// Block hash
+    #[serde(with = "serde_hex")]
+    pub hash: Hash,
+    /// Low 64 bits of the block difficulty
+    pub difficulty: u64,
+    /// High 64 bits of the block difficulty
+    #[serde(default)]
+    pub difficulty_top64: u64,
+    /// Low 64 bits of the cumulative difficulty
+    #[serde(default)]
+    pub cumulative_difficulty: u64,
+    /// High 64 bits of the cumulative difficulty
+    #[serde(default)]
+    pub cumulative_difficulty_top64: u64,
+    /// Reward of the block, in atomic units
+    pub reward: u64,
+    /// Weight of the block
+    #[serde(default)]
+    pub block_weight: u64,
+    /// Number of transactions, without the miner transaction
+    pub num_txes: u64,
+    /// Hash of the miner transaction
+    #[serde(default, with = "serde_hex::option")]
+    pub miner_tx_hash: Option<Hash>,
+}
+
+impl BlockHeaderResponse {
+    /// Return the consensus block header
+    pub fn block_header(&self) -> BlockHeader {
+        BlockHeader {
+            major_version: VarInt(self.major_version as u64),
+            minor_version: VarInt(self.minor_version as u64),
+            timestamp: VarInt(self.timestamp),
+            prev_id: self.prev_hash,
+            nonce: self.nonce,
+        }
+    }
+
+    /// Return the full 128 bits difficulty of the block
+    pub fn difficulty(&self) -> u128 {
+        (self.difficulty_top64 as u128) << 64 | self.difficulty as u128
+    }
+
+    /// Return the full 128 bits cumulative difficulty
+    pub fn cumulative_difficulty(&self) -> u128 {
+        (self.cumulative_difficulty_top64 as u128) << 64 | self.cumulative_difficulty as u128
+    }
+}
+
+/// Get a block by height or hash, `get_block`
+#[derive(Debug, Clone, Serialize)]
+pub struct GetBlock {
+    /// Height of the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// Hash of the block, used if set
+    #[serde(skip_serializing_if = "Option::is_none", with = "serde_hex::option")]
+    pub hash: Option<Hash>,
+}
+
+impl GetBlock {
+    /// Get the block at the height
+    pub fn by_height(height: u64) -> GetBlock {
+        GetBlock {
+            height: Some(height),
+            hash: None,
+        }
+    }
+
+    /// Get the block with the hash
+    pub fn by_hash(hash: Hash) -> GetBlock {
+        GetBlock {
+            height: None,
+            hash: Some(hash),
+        }
+    }
+}
+
+/// Response of [`GetBlock`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBlockResponse {
+    /// The block
+    #[serde(rename = "blob", with = "serde_hex")]
+    pub block: Block,
+    /// Header and metadata of the block
+    pub block_header: BlockHeaderResponse,
+    /// Hash of the miner transaction
+    #[serde(with = "serde_hex")]
+    pub miner_tx_hash: Hash,
+    /// Hashes of the transactions, without the miner transaction
+    #[serde(default, with = "serde_hex::vec")]
+    pub tx_hashes: Vec<Hash>,
+    /// Whether the daemon is not trusted, e.g. while bootstrapping
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl Request for GetBlock {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("get_block");
+    type Response = GetBlockResponse;
+}
+
+/// Get the header of the block at a height, `get_block_header_by_height`
+#[derive(Debug, Clone, Serialize)]
+pub struct GetBlockHeaderByHeight {
+    /// Height of the block
+    pub height: u64,
+}
+
+/// Response of [`GetBlockHeaderByHeight`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBlockHeaderResponse {
+    /// Header and metadata of the block
+    pub block_header: BlockHeaderResponse,
+    /// Whether the daemon is not trusted, e.g. while bootstrapping
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl Request for GetBlockHeaderByHeight {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("get_block_header_by_height");
+    type Response = GetBlockHeaderResponse;
+}
+
+/// Get general information about the daemon and the chain, `get_info`
+#[derive(Debug, Clone, Serialize)]
+pub struct GetInfo {}
+
+/// Response of [`GetInfo`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetInfoResponse {
+    /// Height of the chain
+    pub height: u64,
+    /// Height of the chain being synchronized, 0 if synchronized
+    #[serde(default)]
+    pub target_height: u64,
+    /// Low 64 bits of the difficulty of the next block
+    pub difficulty: u64,
+    /// High 64 bits of the difficulty of the next block
+    #[serde(default)]
+    pub difficulty_top64: u64,
+    /// Low 64 bits of the cumulative difficulty
+    #[serde(default)]
+    pub cumulative_difficulty: u64,
+    /// High 64 bits of the cumulative difficulty
+    #[serde(default)]
+    pub cumulative_difficulty_top64: u64,
+    /// Hash of the top block
+    #[serde(with = "serde_hex")]
+    pub top_block_hash: Hash,
+    /// Number of transactions in the chain
+    #[serde(default)]
+    pub tx_count: u64,
+    /// Number of transactions in the pool
+    #[serde(default)]
+    pub tx_pool_size: u64,
+    /// Network type, `mainnet`, `testnet` or `stagenet`
+    #[serde(default)]
+    pub nettype: String,
+    /// Whether the daemon is synchronized with the network
+    #[serde(default)]
+    pub synchronized: bool,
+    /// Maximum block weight
+    #[serde(default)]
+    pub block_weight_limit: u64,
+    /// Version of the daemon
+    #[serde(default)]
+    pub version: String,
+    /// Whether the daemon is not trusted, e.g. while bootstrapping
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl GetInfoResponse {
+    /// Return the full 128 bits difficulty of the next block
+    pub fn difficulty(&self) -> u128 {
+        (self.difficulty_top64 as u128) << 64 | self.difficulty as u128
+    }
+}
+
+impl Request for GetInfo {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("get_info");
+    type Response = GetInfoResponse;
+}
+
+/// Get the distribution of the outputs of amounts per block, `get_output_distribution`
+///
+/// The distribution is always requested as JSON numbers.
+#[derive(Debug, Clone)]
+pub struct GetOutputDistribution {
+    /// Amounts of the outputs, 0 for RingCT outputs
+    pub amounts: Vec<u64>,
+    /// First height of the distribution
+    pub from_height: u64,
+    /// Last height of the distribution, 0 for the top block
+    pub to_height: u64,
+    /// Whether the distribution is cumulative
+    pub cumulative: bool,
+}
+
+impl Serialize for GetOutputDistribution {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            amounts: &'a [u64],
+            from_height: u64,
+            to_height: u64,
+            cumulative: bool,
+            binary: bool,
+        }
+
+        Params {
+            amounts: &self.amounts,
+            from_height: self.from_height,
+            to_height: self.to_height,
+            cumulative: self.cumulative,
+            binary: false,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Distribution of the outputs of an amount
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputDistribution {
+    /// Amount of the outputs
+    pub amount: u64,
+    /// Height of the first value of the distribution
+    pub start_height: u64,
+    /// Number of outputs per block, or cumulated
+    #[serde(default)]
+    pub distribution: Vec<u64>,
+    /// Number of outputs before the start height
+    pub base: u64,
+}
+
+/// Response of [`GetOutputDistribution`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetOutputDistributionResponse {
+    /// Distributions, one per requested amount
+    #[serde(default)]
+    pub distributions: Vec<OutputDistribution>,
+}
+
+impl Request for GetOutputDistribution {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("get_output_distribution");
+    type Response = GetOutputDistributionResponse;
+}
+
+/// Get transactions by hash, `/get_transactions`
+#[derive(Debug, Clone, Serialize)]
+pub struct GetTransactions {
+    /// Hashes of the transactions
+    #[serde(with = "serde_hex::vec")]
+    pub txs_hashes: Vec<Hash>,
+}
+
+/// Transaction returned by [`GetTransactions`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionEntry {
+    /// Hash of the transaction
+    #[serde(with = "serde_hex")]
+    pub tx_hash: Hash,
+    /// The transaction, pruned nodes only return the pruned transaction and its prunable hash
+    #[serde(flatten, with = "tx_blob")]
+    pub transaction: TxBlobEntry,
+    /// Height of the block including the transaction
+    #[serde(default)]
+    pub block_height: u64,
+    /// Timestamp of the block including the transaction
+    #[serde(default)]
+    pub block_timestamp: u64,
+    /// Whether the transaction is in the pool
+    #[serde(default)]
+    pub in_pool: bool,
+    /// Whether a double spend of the transaction was seen
+    #[serde(default)]
+    pub double_spend_seen: bool,
+    /// Global indices of the outputs
+    #[serde(default)]
+    pub output_indices: Vec<u64>,
+}
+
+/// Serde helpers for the transaction of a [`TransactionEntry`], written in `as_hex` or, when
+/// pruned, in `pruned_as_hex` with its `prunable_hash`
+mod tx_blob {
+    use serde::de::{self, Deserializer};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+
+    use super::serde_hex;
+    use crate::blockdata::transaction::PrunedTransaction;
+    use crate::cryptonote::hash::Hash;
+    use crate::p2p::messages::TxBlobEntry;
+
+    #[derive(Serialize, Deserialize)]
+    struct Blobs {
+        #[serde(default)]
+        as_hex: String,
+        #[serde(default)]
+        pruned_as_hex: String,
+        #[serde(default, with = "serde_hex::option")]
+        prunable_hash: Option<Hash>,
+    }
+
+    pub fn serialize<S: Serializer>(tx: &TxBlobEntry, serializer: S) -> Result<S::Ok, S::Error> {
+        match tx {
+            TxBlobEntry::Full(tx) => Blobs {
+                as_hex: serde_hex::to_hex(tx),
+                pruned_as_hex: String::new(),
+                prunable_hash: None,
+            },
+            TxBlobEntry::Pruned(tx) => Blobs {
+                as_hex: String::new(),
+                pruned_as_hex: serde_hex::to_hex(tx),
+                prunable_hash: Some(tx.prunable_hash),
+            },
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TxBlobEntry, D::Error> {
+        let blobs = Blobs::deserialize(deserializer)?;
+        if !blobs.as_hex.is_empty() {
+            return serde_hex::from_hex(&blobs.as_hex).map(TxBlobEntry::Full);
+        }
+        let prunable_hash = blobs
+            .prunable_hash
+            .ok_or_else(|| de::Error::missing_field("prunable_hash"))?;
+        let blob = hex::decode(&blobs.pruned_as_hex).map_err(de::Error::custom)?;
+        PrunedTransaction::from_pruned_blob(&blob, prunable_hash)
+            .map(TxBlobEntry::Pruned)
+            .map_err(|e| de::Error::custom(format!("invalid blob: {:?}", e)))
+    }
+}
+
+/// Response of [`GetTransactions`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTransactionsResponse {
+    /// Found transactions
+    #[serde(default)]
+    pub txs: Vec<TransactionEntry>,
+    /// Hashes of the transactions not found
+    #[serde(default, with = "serde_hex::vec")]
+    pub missed_tx: Vec<Hash>,
+    /// Whether the daemon is not trusted, e.g. while bootstrapping
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl Request for GetTransactions {
+    const ENDPOINT: Endpoint = Endpoint::Path("/get_transactions");
+    type Response = GetTransactionsResponse;
+}
+
+/// Submit a transaction to the daemon, `/send_raw_transaction`
+///
+/// A rejected transaction returns an [`Error::Status`](super::Error::Status) with the reason.
+#[derive(Debug, Clone, Serialize)]
+pub struct SendRawTransaction {
+    /// The transaction
+    #[serde(rename = "tx_as_hex", with = "serde_hex")]
+    pub transaction: Transaction,
+    /// Whether the daemon must not relay the transaction
+    pub do_not_relay: bool,
+}
+
+/// Response of [`SendRawTransaction`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendRawTransactionResponse {
+    /// Whether the transaction was not relayed
+    #[serde(default)]
+    pub not_relayed: bool,
+    /// Whether the daemon is not trusted, e.g. while bootstrapping
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl Request for SendRawTransaction {
+    const ENDPOINT: Endpoint = Endpoint::Path("/send_raw_transaction");
+    type Response = SendRawTransactionResponse;
+}
+
+/// Output identified by its amount and global index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputIndex {
+    /// Amount of the output, 0 for RingCT outputs
+    pub amount: u64,
+    /// Global index of the output for its amount
+    pub index: u64,
+}
+
+/// Get outputs by global index, `/get_outs`
+#[derive(Debug, Clone, Serialize)]
+pub struct GetOuts {
+    /// Requested outputs
+    pub outputs: Vec<OutputIndex>,
+    /// Whether the hashes of the transactions are returned
+    pub get_txid: bool,
+}
+
+/// Output returned by [`GetOuts`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutKey {
+    /// One-time public key of the output
+    #[serde(with = "serde_hex")]
+    pub key: PublicKey,
+    /// Commitment of the output amount
+    #[serde(with = "serde_hex")]
+    pub mask: Key,
+    /// Whether the output is unlocked
+    pub unlocked: bool,
+    /// Height of the block including the output
+    pub height: u64,
+    /// Hash of the transaction, null if not requested
+    #[serde(with = "serde_hex")]
+    pub txid: Hash,
+}
+
+/// Response of [`GetOuts`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetOutsResponse {
+    /// Outputs, in the requested order
+    #[serde(default)]
+    pub outs: Vec<OutKey>,
+    /// Whether the daemon is not trusted, e.g. while bootstrapping
+    #[serde(default)]
+    pub untrusted: bool,
+}
+
+impl Request for GetOuts {
+    const ENDPOINT: Endpoint = Endpoint::Path("/get_outs");
+    type Response = GetOutsResponse;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::consensus::encode::serialize;
+    use crate::cryptonote::hash::Hashable;
+    use crate::rpc::mock::{block_on, MockTransport};
+    use crate::rpc::{Client, Error};
+
+    // stagenet block 558175
+    const BLOCK: &str = "0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000";
+
+    fn block_header_json(block: &Block) -> Value {
+        json!({
+            "major_version": 12,
+            "minor_version": 12,
+            "timestamp": *block.header.timestamp,
+            "prev_hash": format!("{:x}", block.header.prev_id),
+            "nonce": block.header.nonce,
+            "orphan_status": false,
+            "height": 558_175,
+            "depth": 3,
+            "hash": format!("{:x}", block.hash()),
+            "difficulty": 301_011,
+            "wide_difficulty": "0x497d3",
+            "difficulty_top64": 0,
+            "cumulative_difficulty": 3_000_000_000u64,
+            "reward": 5_000_000_000u64,
+            "block_weight": 95,
+            "num_txes": 0,
+            "pow_hash": "",
+            "miner_tx_hash": format!("{:x}", block.miner_tx.hash()),
+        })
+    }
+
+    #[test]
+    fn get_block() {
+        let block: Block =
+            crate::consensus::encode::deserialize(&hex::decode(BLOCK).unwrap()).unwrap();
+        let transport = MockTransport::new(vec![json!({
+            "id": "0",
+            "jsonrpc": "2.0",
+            "result": {
+                "blob": BLOCK,
+                "block_header": block_header_json(&block),
+                "json": "{}",
+                "miner_tx_hash": format!("{:x}", block.miner_tx.hash()),
+                "status": "OK",
+                "untrusted": false,
+            }
+        })]);
+        let client = Client::new(transport);
+
+        let response = client.call(&GetBlock::by_height(558_175)).unwrap();
+        assert_eq!(BLOCK, hex::encode(serialize(&response.block)));
+        assert_eq!(block.hash(), response.block_header.hash);
+        assert_eq!(
+            serialize(&block.header),
+            serialize(&response.block_header.block_header())
+        );
+        assert!(response.tx_hashes.is_empty());
+
+        let (path, request) = client.transport().requests.borrow()[0].clone();
+        assert_eq!("/json_rpc", path);
+        assert_eq!(
+            json!({"jsonrpc": "2.0", "id": "0", "method": "get_block", "params": {"height": 558_175}}),
+            request
+        );
+    }
+
+    #[test]
+    fn errors_and_status() {
+        let transport = MockTransport::new(vec![
+            json!({"id": "0", "jsonrpc": "2.0", "error": {"code": -2, "message": "Too big height"}}),
+            json!({"status": "Failed", "reason": "double spend", "double_spend": true}),
+        ]);
+        let client = Client::new(transport);
+
+        match client.call(&GetBlockHeaderByHeight { height: 1 << 40 }) {
+            Err(Error::Rpc { code, message }) => {
+                assert_eq!(-2, code);
+                assert_eq!("Too big height", message);
+            }
+            _ => panic!("expected an RPC error"),
+        }
+
+        let block: Block =
+            crate::consensus::encode::deserialize(&hex::decode(BLOCK).unwrap()).unwrap();
+        let request = SendRawTransaction {
+            transaction: block.miner_tx,
+            do_not_relay: true,
+        };
+        match client.call(&request) {
+            Err(Error::Status { status, reason }) => {
+                assert_eq!("Failed", status);
+                assert_eq!("double spend", reason);
+            }
+            _ => panic!("expected a status error"),
+        }
+        let (path, sent) = client.transport().requests.borrow()[1].clone();
+        assert_eq!("/send_raw_transaction", path);
+        assert_eq!(json!(true), sent["do_not_relay"]);
+
+        assert!(matches!(client.call(&GetInfo {}), Err(Error::Transport(_))));
+    }
+
+    #[test]
+    fn get_outs_and_distribution_async() {
+        let mask = "e8b3a4a3b1e4c4d7b9e8e6b7d8e1f0a0b2c4d6e8f0a2b4c6d8e0f2a4b6c8d0e2";
+        let transport = MockTransport::new(vec![
+            json!({
+                "outs": [{
+                    "height": 10,
+                    "key": "5866666666666666666666666666666666666666666666666666666666666666",
+                    "mask": mask,
+                    "txid": format!("{:x}", Hash::null_hash()),
+                    "unlocked": true,
+                }],
+                "status": "OK",
+                "untrusted": false,
+            }),
+            json!({
+                "id": "0",
+                "jsonrpc": "2.0",
+                "result": {
+                    "distributions": [{
+                        "amount": 0,
+                        "base": 0,
+                        "distribution": [1, 3, 6],
+                        "start_height": 0,
+                        "binary": false,
+                        "compress": false,
+                    }],
+                    "status": "OK",
+                }
+            }),
+        ]);
+        let client = Client::new(transport);
+
+        let request = GetOuts {
+            outputs: vec![OutputIndex {
+                amount: 0,
+                index: 7,
+            }],
+            get_txid: false,
+        };
+        let response = block_on(client.call_async(&request)).unwrap();
+        assert_eq!(1, response.outs.len());
+        assert_eq!(mask, response.outs[0].mask.to_string());
+        assert_eq!(Hash::null_hash(), response.outs[0].txid);
+        let (path, sent) = client.transport().requests.borrow()[0].clone();
+        assert_eq!("/get_outs", path);
+        assert_eq!(json!([{"amount": 0, "index": 7}]), sent["outputs"]);
+
+        let request = GetOutputDistribution {
+            amounts: vec![0],
+            from_height: 0,
+            to_height: 0,
+            cumulative: true,
+        };
+        let response = block_on(client.call_async(&request)).unwrap();
+        assert_eq!(vec![1, 3, 6], response.distributions[0].distribution);
+        let (_, sent) = client.transport().requests.borrow()[1].clone();
+        assert_eq!(json!(false), sent["params"]["binary"]);
+    }
+
+    #[test]
+    fn get_transactions_pruned() {
+        let block: Block =
+            crate::consensus::encode::deserialize(&hex::decode(BLOCK).unwrap()).unwrap();
+        let tx = block.miner_tx;
+        let txid = tx.hash();
+        let (pruned, _) = tx.prune();
+        let transport = MockTransport::new(vec![json!({
+            "txs": [{
+                "as_hex": hex::encode(serialize(&tx)),
+                "block_height": 558_175,
+                "in_pool": false,
+                "tx_hash": format!("{:x}", txid),
+            }, {
+                // A pruned node only returns the pruned blob
+                "as_hex": "",
+                "block_height": 558_175,
+                "in_pool": false,
+                "prunable_as_hex": "",
+                "prunable_hash": format!("{:x}", pruned.prunable_hash),
+                "pruned_as_hex": hex::encode(serialize(&pruned)),
+                "tx_hash": format!("{:x}", txid),
+            }],
+            "status": "OK",
+            "untrusted": false,
+        })]);
+        let client = Client::new(transport);
+
+        let request = GetTransactions {
+            txs_hashes: vec![txid, txid],
+        };
+        let response = client.call(&request).unwrap();
+        assert_eq!(2, response.txs.len());
+        match &response.txs[0].transaction {
+            TxBlobEntry::Full(full) => assert_eq!(txid, full.hash()),
+            _ => panic!("expected a full transaction"),
+        }
+        match &response.txs[1].transaction {
+            TxBlobEntry::Pruned(pruned) => assert_eq!(Some(txid), pruned.hash()),
+            _ => panic!("expected a pruned transaction"),
+        }
+        assert_eq!(558_175, response.txs[1].block_height);
+
+        // The entries are written back in the fields of monerod
+        let entry = serde_json::to_value(&response.txs[1]).unwrap();
+        assert_eq!(json!(""), entry["as_hex"]);
+        assert_eq!(
+            json!(format!("{:x}", pruned.prunable_hash)),
+            entry["prunable_hash"]
+        );
+
+        let missing = json!({"as_hex": "", "pruned_as_hex": "", "tx_hash": format!("{:x}", txid)});
+        assert!(serde_json::from_value::<TransactionEntry>(missing).is_err());
+    }
+}
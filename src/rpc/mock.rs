@@ -0,0 +1,83 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Canned-response transport for tests
+//!
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{ready, Future, Ready};
+use std::io;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use serde_json::Value;
+
+use super::{AsyncTransport, Transport};
+
+/// Transport returning canned responses and recording the requests
+#[derive(Default)]
+pub struct MockTransport {
+    responses: RefCell<VecDeque<Value>>,
+    pub requests: RefCell<Vec<(String, Value)>>,
+}
+
+impl MockTransport {
+    pub fn new(responses: Vec<Value>) -> MockTransport {
+        MockTransport {
+            responses: RefCell::new(responses.into()),
+            ..Default::default()
+        }
+    }
+
+    fn respond(&self, path: &str, body: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+        self.requests
+            .borrow_mut()
+            .push((path.to_string(), serde_json::from_slice(&body).unwrap()));
+        match self.responses.borrow_mut().pop_front() {
+            Some(response) => Ok(serde_json::to_vec(&response).unwrap()),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "no response")),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    type Error = io::Error;
+
+    fn post(&self, path: &str, body: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+        self.respond(path, body)
+    }
+}
+
+impl AsyncTransport for MockTransport {
+    type Error = io::Error;
+    type Future = Ready<Result<Vec<u8>, io::Error>>;
+
+    fn post(&self, path: &str, body: Vec<u8>) -> Self::Future {
+        ready(self.respond(path, body))
+    }
+}
+
+/// Poll a future that is always ready
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    match future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the mock transport is always ready"),
+    }
+}
@@ -0,0 +1,247 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Typed RPC layer
+//!
+//! Requests are plain structures implementing [`Request`], they know their endpoint and the type
+//! of their response. Responses embed the crate's types, e.g. a [`Block`] or a [`Transaction`],
+//! decoded from their hex blobs.
+//!
+//! The [`Client`] does not implement HTTP itself, it is generic over a [`Transport`], a blocking
+//! HTTP client, or an [`AsyncTransport`]. Requests can also be encoded and decoded without a
+//! client with [`encode_request`] and [`decode_response`].
+//!
+//! ```rust
+//! use monero::rpc::daemon::GetBlockHeaderByHeight;
+//! use monero::rpc::{Client, Transport};
+//!
+//! struct Mock;
+//!
+//! impl Transport for Mock {
+//!     type Error = std::io::Error;
+//!
+//!     fn post(&self, path: &str, _body: Vec<u8>) -> Result<Vec<u8>, Self::Error> {
+//!         assert_eq!("/json_rpc", path);
+//!         Ok(br#"{"id": "0", "jsonrpc": "2.0", "error": {"code": -2, "message": "Too big height"}}"#.to_vec())
+//!     }
+//! }
+//!
+//! let client = Client::new(Mock);
+//! assert!(client.call(&GetBlockHeaderByHeight { height: 1 << 40 }).is_err());
+//! ```
+//!
+//! [`Block`]: crate::blockdata::block::Block
+//! [`Transaction`]: crate::blockdata::transaction::Transaction
+//!
+
+pub mod as_json;
+pub mod daemon;
+#[cfg(test)]
+mod mock;
+pub mod serde_hex;
+pub mod wallet;
+pub mod zmq;
+
+use std::error::Error as StdError;
+use std::future::Future;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Path of the JSON-RPC endpoint
+pub const JSON_RPC_PATH: &str = "/json_rpc";
+/// Status of a successful call
+pub const STATUS_OK: &str = "OK";
+
+/// Possible errors when calling an RPC method
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The transport failed to send the request or receive the response
+    #[error("transport error: {0}")]
+    Transport(Box<dyn StdError + Send + Sync>),
+    /// The request or the response is not valid JSON for the method
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The JSON-RPC call failed
+    #[error("RPC error {code}: {message}")]
+    Rpc {
+        /// Error code
+        code: i64,
+        /// Error message
+        message: String,
+    },
+    /// The call did not return a successful status
+    #[error("RPC call failed with status {status}: {reason}")]
+    Status {
+        /// Returned status, e.g. `BUSY`
+        status: String,
+        /// Reason of the failure, empty if not given
+        reason: String,
+    },
+}
+
+/// Endpoint of a method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    /// Method called through the JSON-RPC endpoint
+    JsonRpc(&'static str),
+    /// Method with its own path, e.g. `/get_transactions`
+    Path(&'static str),
+}
+
+impl Endpoint {
+    /// Return the path of the endpoint
+    pub fn path(&self) -> &'static str {
+        match self {
+            Endpoint::JsonRpc(_) => JSON_RPC_PATH,
+            Endpoint::Path(path) => path,
+        }
+    }
+}
+
+/// A request of an RPC method, the structure is serialized as the method parameters
+pub trait Request: Serialize {
+    /// Endpoint of the method
+    const ENDPOINT: Endpoint;
+    /// Result of the method
+    type Response: DeserializeOwned;
+}
+
+/// A blocking HTTP client
+pub trait Transport {
+    /// Error of the transport
+    type Error: Into<Box<dyn StdError + Send + Sync>>;
+
+    /// Post the JSON body to the path of the RPC server and return the body of the response
+    fn post(&self, path: &str, body: Vec<u8>) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// An asynchronous HTTP client
+pub trait AsyncTransport {
+    /// Error of the transport
+    type Error: Into<Box<dyn StdError + Send + Sync>>;
+    /// Future resolving to the body of the response
+    type Future: Future<Output = Result<Vec<u8>, Self::Error>>;
+
+    /// Post the JSON body to the path of the RPC server
+    fn post(&self, path: &str, body: Vec<u8>) -> Self::Future;
+}
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a, P> {
+    jsonrpc: &'static str,
+    id: &'static str,
+    method: &'static str,
+    params: &'a P,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Return the path and the JSON body of a request
+pub fn encode_request<R: Request>(request: &R) -> Result<(&'static str, Vec<u8>), Error> {
+    let body = match R::ENDPOINT {
+        Endpoint::JsonRpc(method) => serde_json::to_vec(&JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: "0",
+            method,
+            params: request,
+        })?,
+        Endpoint::Path(_) => serde_json::to_vec(request)?,
+    };
+    Ok((R::ENDPOINT.path(), body))
+}
+
+/// Decode the JSON body of the response to a request
+///
+/// Responses with a status other than [`STATUS_OK`] are errors.
+pub fn decode_response<R: Request>(body: &[u8]) -> Result<R::Response, Error> {
+    let result = match R::ENDPOINT {
+        Endpoint::JsonRpc(_) => {
+            let response: JsonRpcResponse = serde_json::from_slice(body)?;
+            if let Some(JsonRpcError { code, message }) = response.error {
+                return Err(Error::Rpc { code, message });
+            }
+            response.result.unwrap_or(Value::Null)
+        }
+        Endpoint::Path(_) => serde_json::from_slice(body)?,
+    };
+    if let Some(status) = result.get("status").and_then(Value::as_str) {
+        if status != STATUS_OK {
+            let reason = result.get("reason").and_then(Value::as_str).unwrap_or("");
+            return Err(Error::Status {
+                status: status.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+    Ok(serde_json::from_value(result)?)
+}
+
+/// RPC client over a transport
+#[derive(Debug, Clone)]
+pub struct Client<T> {
+    transport: T,
+}
+
+impl<T> Client<T> {
+    /// Create a client over the transport
+    pub fn new(transport: T) -> Client<T> {
+        Client { transport }
+    }
+
+    /// Return the transport of the client
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Client<T> {
+    /// Call the method of the request and return its response
+    pub fn call<R: Request>(&self, request: &R) -> Result<R::Response, Error> {
+        let (path, body) = encode_request(request)?;
+        let response = self
+            .transport
+            .post(path, body)
+            .map_err(|e| Error::Transport(e.into()))?;
+        decode_response::<R>(&response)
+    }
+}
+
+impl<T: AsyncTransport> Client<T> {
+    /// Call the method of the request and return its response
+    pub async fn call_async<R: Request>(&self, request: &R) -> Result<R::Response, Error> {
+        let (path, body) = encode_request(request)?;
+        let response = self
+            .transport
+            .post(path, body)
+            .await
+            .map_err(|e| Error::Transport(e.into()))?;
+        decode_response::<R>(&response)
+    }
+}
@@ -0,0 +1,141 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Serde helpers for hex blobs
+//!
+//! RPC servers write hashes, keys, blocks and transactions as hex strings of their consensus
+//! encoding. These modules are meant to be used with `#[serde(with = "...")]`: this module for a
+//! single value, [`vec`] for a list of values and [`option`] for an optional value, servers
+//! write absent values as empty strings.
+//!
+
+use std::io::Cursor;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use crate::consensus::encode::{self, BoundedDecoder, Decodable, Encodable};
+
+pub(crate) fn to_hex<T: Encodable<Cursor<Vec<u8>>>>(value: &T) -> String {
+    hex::encode(encode::serialize(value))
+}
+
+pub(crate) fn from_hex<T, E>(s: &str) -> Result<T, E>
+where
+    T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+    E: de::Error,
+{
+    let bytes = hex::decode(s).map_err(E::custom)?;
+    encode::deserialize(&bytes).map_err(|e| E::custom(format!("invalid blob: {:?}", e)))
+}
+
+/// Serialize a consensus encodable value as a hex string
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Encodable<Cursor<Vec<u8>>>,
+    S: Serializer,
+{
+    serializer.serialize_str(&to_hex(value))
+}
+
+/// Deserialize a consensus decodable value from a hex string
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+    D: Deserializer<'de>,
+{
+    from_hex(&String::deserialize(deserializer)?)
+}
+
+/// Serde helpers for a list of hex blobs
+pub mod vec {
+    use super::*;
+
+    /// Serialize consensus encodable values as a list of hex strings
+    pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Encodable<Cursor<Vec<u8>>>,
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values {
+            seq.serialize_element(&to_hex(value))?;
+        }
+        seq.end()
+    }
+
+    /// Deserialize consensus decodable values from a list of hex strings
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| from_hex(s))
+            .collect()
+    }
+}
+
+/// Serde helpers for an optional hex blob
+pub mod option {
+    use super::*;
+
+    /// Serialize an optional consensus encodable value as a hex string or null
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Encodable<Cursor<Vec<u8>>>,
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.serialize_some(&to_hex(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserialize an optional consensus decodable value from a hex string, null or an empty
+    /// string
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .filter(|s| !s.is_empty())
+            .map(|s| from_hex(&s))
+            .transpose()
+    }
+}
+
+/// A value written as a hex string, for values nested in other containers
+pub(crate) struct Hex<T>(pub T);
+
+impl<T> Serialize for Hex<T>
+where
+    T: Encodable<Cursor<Vec<u8>>>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Hex<T>
+where
+    T: for<'a> Decodable<BoundedDecoder<Cursor<&'a [u8]>>>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Hex)
+    }
+}
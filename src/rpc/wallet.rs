@@ -0,0 +1,643 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Wallet RPC methods
+//!
+//! Requests and responses of the `monero-wallet-rpc` methods, all called through the JSON-RPC
+//! endpoint. Amounts are [`Amount`]s, addresses are [`Address`]es and payment ids, subaddress
+//! indexes and key images use the crate's types. Payment ids returned by the wallet can be short
+//! or long, see [`WalletPaymentId`].
+//!
+//! **Same as** [`wallet_rpc_server_commands_defs.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet_rpc_server_commands_defs.h)
+//!
+
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use super::{serde_hex, Endpoint, Request};
+use crate::blockdata::transaction::{KeyImage, Transaction};
+use crate::cryptonote::hash::Hash;
+use crate::cryptonote::subaddress::Index;
+use crate::util::address::{Address, PaymentId};
+use crate::util::amount::Amount;
+use crate::util::key::PrivateKey;
+use crate::util::ringct::Signature;
+
+/// Destination of a transfer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Destination {
+    /// Amount sent to the address
+    pub amount: Amount,
+    /// Receiving address
+    pub address: Address,
+}
+
+/// Priority of a transfer, higher priorities pay higher fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransferPriority {
+    /// Priority chosen by the wallet
+    #[default]
+    Default = 0,
+    /// Lowest fee
+    Unimportant = 1,
+    /// Normal fee
+    Normal = 2,
+    /// Highest fee
+    Elevated = 3,
+}
+
+impl Serialize for TransferPriority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*self as u32)
+    }
+}
+
+/// Payment id returned by the wallet, short or long
+///
+/// Transfers using deprecated long payment ids are still reported with their 32 bytes id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletPaymentId {
+    /// Short payment id, 8 bytes
+    Short(PaymentId),
+    /// Long payment id, 32 bytes
+    Long(Hash),
+}
+
+impl From<PaymentId> for WalletPaymentId {
+    fn from(payment_id: PaymentId) -> Self {
+        WalletPaymentId::Short(payment_id)
+    }
+}
+
+impl Serialize for WalletPaymentId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            WalletPaymentId::Short(payment_id) => serde_hex::serialize(payment_id, serializer),
+            WalletPaymentId::Long(payment_id) => serde_hex::serialize(payment_id, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WalletPaymentId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = hex::decode(String::deserialize(deserializer)?).map_err(de::Error::custom)?;
+        match bytes.len() {
+            8 => Ok(WalletPaymentId::Short(PaymentId::from_slice(&bytes))),
+            32 => Ok(WalletPaymentId::Long(Hash::from_slice(&bytes))),
+            len => Err(de::Error::invalid_length(len, &"8 or 32 bytes")),
+        }
+    }
+}
+
+/// Send monero to destinations, `transfer`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Transfer {
+    /// Destinations of the transfer
+    pub destinations: Vec<Destination>,
+    /// Account spending the funds
+    pub account_index: u32,
+    /// Subaddresses spending the funds, all subaddresses of the account if empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subaddr_indices: Vec<u32>,
+    /// Priority of the transfer
+    pub priority: TransferPriority,
+    /// Height or timestamp before which the outputs are locked
+    pub unlock_time: u64,
+    /// Whether the transaction secret key is returned
+    pub get_tx_key: bool,
+    /// Whether the transaction is created but not relayed
+    pub do_not_relay: bool,
+    /// Whether the transaction is returned
+    pub get_tx_hex: bool,
+}
+
+/// Response of [`Transfer`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferResponse {
+    /// Amount sent, without the fee
+    pub amount: Amount,
+    /// Fee of the transaction
+    pub fee: Amount,
+    /// Hash of the transaction
+    #[serde(with = "serde_hex")]
+    pub tx_hash: Hash,
+    /// Transaction secret key, if requested
+    #[serde(default, with = "serde_hex::option")]
+    pub tx_key: Option<PrivateKey>,
+    /// The transaction, if requested
+    #[serde(rename = "tx_blob", default, with = "serde_hex::option")]
+    pub transaction: Option<Transaction>,
+    /// Weight of the transaction
+    #[serde(default)]
+    pub weight: u64,
+}
+
+impl Request for Transfer {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("transfer");
+    type Response = TransferResponse;
+}
+
+/// Get the transfers of an account, `get_transfers`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GetTransfers {
+    /// Whether incoming transfers are returned
+    #[serde(rename = "in")]
+    pub incoming: bool,
+    /// Whether outgoing transfers are returned
+    #[serde(rename = "out")]
+    pub outgoing: bool,
+    /// Whether pending transfers are returned
+    pub pending: bool,
+    /// Whether failed transfers are returned
+    pub failed: bool,
+    /// Whether incoming transfers in the pool are returned
+    pub pool: bool,
+    /// Whether transfers are filtered by height
+    pub filter_by_height: bool,
+    /// Minimum height of the transfers, exclusive
+    pub min_height: u64,
+    /// Maximum height of the transfers, inclusive
+    pub max_height: u64,
+    /// Account of the transfers
+    pub account_index: u32,
+    /// Subaddresses of the transfers, all subaddresses of the account if empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subaddr_indices: Vec<u32>,
+    /// Whether the transfers of all accounts are returned
+    pub all_accounts: bool,
+}
+
+/// Kind of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferKind {
+    /// Incoming transfer
+    In,
+    /// Outgoing transfer
+    Out,
+    /// Outgoing transfer not yet mined
+    Pending,
+    /// Outgoing transfer that failed
+    Failed,
+    /// Incoming transfer in the pool
+    Pool,
+    /// Block reward
+    Block,
+}
+
+/// Transfer returned by [`GetTransfers`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferEntry {
+    /// Hash of the transaction
+    #[serde(rename = "txid", with = "serde_hex")]
+    pub tx_hash: Hash,
+    /// Kind of transfer
+    #[serde(rename = "type")]
+    pub kind: TransferKind,
+    /// Address receiving, or sending, the funds
+    pub address: Address,
+    /// Amount of the transfer
+    pub amount: Amount,
+    /// Fee of the transaction
+    #[serde(default)]
+    pub fee: Amount,
+    /// Payment id, null if none
+    pub payment_id: WalletPaymentId,
+    /// Subaddress receiving, or sending, the funds
+    pub subaddr_index: Index,
+    /// Height of the block including the transaction, 0 if not mined
+    #[serde(default)]
+    pub height: u64,
+    /// Timestamp of the transaction
+    #[serde(default)]
+    pub timestamp: u64,
+    /// Number of confirmations
+    #[serde(default)]
+    pub confirmations: u64,
+    /// Height or timestamp before which the outputs are locked
+    #[serde(default)]
+    pub unlock_time: u64,
+    /// Whether a double spend of the transaction was seen
+    #[serde(default)]
+    pub double_spend_seen: bool,
+    /// Note attached to the transaction
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Response of [`GetTransfers`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTransfersResponse {
+    /// Incoming transfers
+    #[serde(rename = "in", default)]
+    pub incoming: Vec<TransferEntry>,
+    /// Outgoing transfers
+    #[serde(rename = "out", default)]
+    pub outgoing: Vec<TransferEntry>,
+    /// Pending transfers
+    #[serde(default)]
+    pub pending: Vec<TransferEntry>,
+    /// Failed transfers
+    #[serde(default)]
+    pub failed: Vec<TransferEntry>,
+    /// Incoming transfers in the pool
+    #[serde(default)]
+    pub pool: Vec<TransferEntry>,
+}
+
+impl Request for GetTransfers {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("get_transfers");
+    type Response = GetTransfersResponse;
+}
+
+/// Create a subaddress in an account, `create_address`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateAddress {
+    /// Account of the subaddress
+    pub account_index: u32,
+    /// Label of the subaddress
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Response of [`CreateAddress`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAddressResponse {
+    /// The new subaddress
+    pub address: Address,
+    /// Minor index of the subaddress
+    pub address_index: u32,
+}
+
+impl Request for CreateAddress {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("create_address");
+    type Response = CreateAddressResponse;
+}
+
+/// Outputs returned by [`IncomingTransfers`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferType {
+    /// All outputs
+    #[default]
+    All,
+    /// Unspent outputs
+    Available,
+    /// Spent outputs
+    Unavailable,
+}
+
+/// Get the outputs received by an account, `incoming_transfers`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IncomingTransfers {
+    /// Returned outputs
+    pub transfer_type: TransferType,
+    /// Account receiving the outputs
+    pub account_index: u32,
+    /// Subaddresses receiving the outputs, all subaddresses of the account if empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subaddr_indices: Vec<u32>,
+}
+
+/// Output returned by [`IncomingTransfers`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomingTransfer {
+    /// Amount of the output
+    pub amount: Amount,
+    /// Global index of the output for its amount
+    pub global_index: u64,
+    /// Key image of the output, unknown to view-only wallets
+    #[serde(default, with = "serde_hex::option")]
+    pub key_image: Option<KeyImage>,
+    /// Whether the output is spent
+    pub spent: bool,
+    /// Subaddress receiving the output
+    pub subaddr_index: Index,
+    /// Hash of the transaction
+    #[serde(with = "serde_hex")]
+    pub tx_hash: Hash,
+    /// Whether the output is frozen
+    #[serde(default)]
+    pub frozen: bool,
+    /// Whether the output is unlocked
+    #[serde(default)]
+    pub unlocked: bool,
+}
+
+/// Response of [`IncomingTransfers`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncomingTransfersResponse {
+    /// Outputs of the account
+    #[serde(default)]
+    pub transfers: Vec<IncomingTransfer>,
+}
+
+impl Request for IncomingTransfers {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("incoming_transfers");
+    type Response = IncomingTransfersResponse;
+}
+
+/// Sign data with the wallet spend key, `sign`
+#[derive(Debug, Clone, Serialize)]
+pub struct Sign {
+    /// Signed data
+    pub data: String,
+}
+
+/// Response of [`Sign`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignResponse {
+    /// Signature, starting with `SigV`
+    pub signature: String,
+}
+
+impl Request for Sign {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("sign");
+    type Response = SignResponse;
+}
+
+/// Verify the signature of data by an address, `verify`
+#[derive(Debug, Clone, Serialize)]
+pub struct Verify {
+    /// Signed data
+    pub data: String,
+    /// Address of the signer
+    pub address: Address,
+    /// Signature returned by [`Sign`]
+    pub signature: String,
+}
+
+/// Response of [`Verify`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    /// Whether the signature is valid
+    pub good: bool,
+}
+
+impl Request for Verify {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("verify");
+    type Response = VerifyResponse;
+}
+
+/// Create an integrated address, `make_integrated_address`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MakeIntegratedAddress {
+    /// Standard address, the wallet primary address if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub standard_address: Option<Address>,
+    /// Payment id, random if not set
+    #[serde(skip_serializing_if = "Option::is_none", with = "serde_hex::option")]
+    pub payment_id: Option<PaymentId>,
+}
+
+/// Response of [`MakeIntegratedAddress`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MakeIntegratedAddressResponse {
+    /// The integrated address
+    pub integrated_address: Address,
+    /// Payment id of the integrated address
+    pub payment_id: WalletPaymentId,
+}
+
+impl Request for MakeIntegratedAddress {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("make_integrated_address");
+    type Response = MakeIntegratedAddressResponse;
+}
+
+/// Export the signed key images of the wallet outputs, `export_key_images`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportKeyImages {
+    /// Whether all key images are exported, or only the ones not exported yet
+    pub all: bool,
+}
+
+/// Key image with the signature proving the ownership of its output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedKeyImage {
+    /// The key image
+    #[serde(with = "serde_hex")]
+    pub key_image: KeyImage,
+    /// Signature of the key image
+    #[serde(with = "serde_hex")]
+    pub signature: Signature,
+}
+
+/// Response of [`ExportKeyImages`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportKeyImagesResponse {
+    /// Index of the output of the first key image
+    #[serde(default)]
+    pub offset: u32,
+    /// Signed key images
+    #[serde(default)]
+    pub signed_key_images: Vec<SignedKeyImage>,
+}
+
+impl Request for ExportKeyImages {
+    const ENDPOINT: Endpoint = Endpoint::JsonRpc("export_key_images");
+    type Response = ExportKeyImagesResponse;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::rpc::mock::MockTransport;
+    use crate::rpc::Client;
+
+    const ADDRESS: &str = "4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra";
+
+    fn result(result: serde_json::Value) -> serde_json::Value {
+        json!({"id": "0", "jsonrpc": "2.0", "result": result})
+    }
+
+    #[test]
+    fn transfer() {
+        let tx_hash = Hash::hash(b"tx");
+        let client = Client::new(MockTransport::new(vec![result(json!({
+            "amount": 1_500_000_000_000u64,
+            "fee": 30_000_000u64,
+            "multisig_txset": "",
+            "tx_blob": "",
+            "tx_hash": format!("{:x}", tx_hash),
+            "tx_key": "",
+            "tx_metadata": "",
+            "unsigned_txset": "",
+            "weight": 1_500,
+        }))]));
+
+        let request = Transfer {
+            destinations: vec![Destination {
+                amount: "1.5 xmr".parse().unwrap(),
+                address: Address::from_str(ADDRESS).unwrap(),
+            }],
+            priority: TransferPriority::Normal,
+            ..Default::default()
+        };
+        let response = client.call(&request).unwrap();
+        assert_eq!(Amount::ONE_XMR * 3 / 2, response.amount);
+        assert_eq!(tx_hash, response.tx_hash);
+        assert!(response.tx_key.is_none());
+        assert!(response.transaction.is_none());
+
+        let (_, sent) = client.transport().requests.borrow()[0].clone();
+        assert_eq!(
+            json!([{"amount": 1_500_000_000_000u64, "address": ADDRESS}]),
+            sent["params"]["destinations"]
+        );
+        assert_eq!(json!(2), sent["params"]["priority"]);
+        assert!(sent["params"].get("subaddr_indices").is_none());
+    }
+
+    #[test]
+    fn transfers_and_outputs() {
+        let key_image = Hash::hash(b"key image");
+        let client = Client::new(MockTransport::new(vec![
+            result(json!({
+                "in": [{
+                    "address": ADDRESS,
+                    "amount": 42,
+                    "confirmations": 10,
+                    "double_spend_seen": false,
+                    "fee": 1,
+                    "height": 2_000_000,
+                    "note": "",
+                    "payment_id": "0102030405060708",
+                    "subaddr_index": {"major": 0, "minor": 1},
+                    "subaddr_indices": [{"major": 0, "minor": 1}],
+                    "suggested_confirmations_threshold": 1,
+                    "timestamp": 1_600_000_000,
+                    "txid": format!("{:x}", Hash::hash(b"tx")),
+                    "type": "in",
+                    "unlock_time": 0,
+                }]
+            })),
+            result(json!({
+                "transfers": [{
+                    "amount": 42,
+                    "global_index": 7,
+                    "key_image": format!("{:x}", key_image),
+                    "spent": false,
+                    "subaddr_index": {"major": 0, "minor": 1},
+                    "tx_hash": format!("{:x}", Hash::hash(b"tx")),
+                }, {
+                    "amount": 43,
+                    "global_index": 8,
+                    "key_image": "",
+                    "spent": true,
+                    "subaddr_index": {"major": 1, "minor": 0},
+                    "tx_hash": format!("{:x}", Hash::hash(b"tx")),
+                }]
+            })),
+        ]));
+
+        let request = GetTransfers {
+            incoming: true,
+            ..Default::default()
+        };
+        let response = client.call(&request).unwrap();
+        assert!(response.outgoing.is_empty());
+        let transfer = &response.incoming[0];
+        assert_eq!(TransferKind::In, transfer.kind);
+        assert_eq!(Amount::from_pico(42), transfer.amount);
+        assert_eq!(
+            WalletPaymentId::Short(PaymentId([1, 2, 3, 4, 5, 6, 7, 8])),
+            transfer.payment_id
+        );
+        assert_eq!(Index { major: 0, minor: 1 }, transfer.subaddr_index);
+        let (_, sent) = client.transport().requests.borrow()[0].clone();
+        assert_eq!(json!(true), sent["params"]["in"]);
+        assert_eq!(json!(false), sent["params"]["out"]);
+
+        let response = client.call(&IncomingTransfers::default()).unwrap();
+        assert_eq!(
+            Some(KeyImage { image: key_image }),
+            response.transfers[0].key_image
+        );
+        assert_eq!(None, response.transfers[1].key_image);
+        let (_, sent) = client.transport().requests.borrow()[1].clone();
+        assert_eq!(json!("all"), sent["params"]["transfer_type"]);
+    }
+
+    #[test]
+    fn long_payment_id() {
+        let payment_id = Hash::hash(b"payment id");
+        let transfer: TransferEntry = serde_json::from_value(json!({
+            "address": ADDRESS,
+            "amount": 42,
+            "payment_id": format!("{:x}", payment_id),
+            "subaddr_index": {"major": 0, "minor": 0},
+            "txid": format!("{:x}", Hash::hash(b"tx")),
+            "type": "in",
+        }))
+        .unwrap();
+        assert_eq!(WalletPaymentId::Long(payment_id), transfer.payment_id);
+        assert_eq!(
+            json!(format!("{:x}", payment_id)),
+            serde_json::to_value(&transfer).unwrap()["payment_id"]
+        );
+
+        let short = serde_json::from_value::<WalletPaymentId>(json!("0000000000000000"));
+        assert_eq!(WalletPaymentId::Short(PaymentId([0; 8])), short.unwrap());
+        assert!(serde_json::from_value::<WalletPaymentId>(json!("00000000")).is_err());
+    }
+
+    #[test]
+    fn integrated_address_and_key_images() {
+        let address = Address::from_str(ADDRESS).unwrap();
+        let payment_id = PaymentId([0xff; 8]);
+        let integrated = Address::integrated(
+            address.network,
+            address.public_spend,
+            address.public_view,
+            payment_id,
+        );
+        let client = Client::new(MockTransport::new(vec![
+            result(json!({
+                "integrated_address": integrated.to_string(),
+                "payment_id": "ffffffffffffffff",
+            })),
+            result(json!({
+                "offset": 0,
+                "signed_key_images": [{
+                    "key_image": format!("{:x}", Hash::hash(b"key image")),
+                    "signature": hex::encode([7u8; 64]),
+                }]
+            })),
+        ]));
+
+        let request = MakeIntegratedAddress {
+            standard_address: Some(address),
+            payment_id: Some(payment_id),
+        };
+        let response = client.call(&request).unwrap();
+        assert_eq!(integrated, response.integrated_address);
+        assert_eq!(WalletPaymentId::Short(payment_id), response.payment_id);
+        let (_, sent) = client.transport().requests.borrow()[0].clone();
+        assert_eq!(
+            json!({"standard_address": ADDRESS, "payment_id": "ffffffffffffffff"}),
+            sent["params"]
+        );
+
+        let response = client.call(&ExportKeyImages { all: true }).unwrap();
+        let signed = &response.signed_key_images[0];
+        assert_eq!(Hash::hash(b"key image"), signed.key_image.image);
+        assert_eq!([7u8; 32], signed.signature.c.key);
+    }
+}
@@ -0,0 +1,526 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! ZMQ publish feed
+//!
+//! With `--zmq-pub` monerod publishes chain and transaction pool events. Each message is the
+//! topic, a colon and a JSON payload, e.g. `json-minimal-txpool_add:[...]`. The `json-full-*`
+//! topics carry whole blocks and transactions in the JSON representation of monerod, they are
+//! parsed into [`Block`]s and [`Transaction`]s, see [`transaction`] and [`block`] to use this
+//! representation in other structures.
+//!
+//! ```rust
+//! use monero::rpc::zmq::{self, Event, Topic};
+//!
+//! let message = br#"json-minimal-chain_main:{"first_height":558175,"first_prev_id":"beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b62","ids":["250b860e12d169416f033712f50904100d08f42251f5ec44bc3e87b72a56c9d4"]}"#;
+//!
+//! match zmq::parse(message).unwrap() {
+//!     Event::MinimalChainMain(chain) => assert_eq!(558175, chain.first_height),
+//!     event => panic!("unexpected {} event", event.topic()),
+//! }
+//! ```
+//!
+//! **Same as** [`zmq_pub.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/rpc/zmq_pub.cpp)
+//! and [`json_object.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/serialization/json_object.cpp).
+//!
+//! [`Block`]: crate::blockdata::block::Block
+//! [`Transaction`]: crate::blockdata::transaction::Transaction
+//!
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, IgnoredAny};
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+use crate::blockdata::block::{Block, BlockHeader};
+use crate::blockdata::transaction::{Transaction, TransactionPrefix, TxIn, TxOut, TxOutTarget};
+use crate::consensus::encode::VarInt;
+use crate::cryptonote::hash::{Hash, Hash8};
+use crate::rpc::as_json::{
+    extra_from_bytes, keys, rct_type_from_u8, BulletproofRepr, ClsagRepr, InputRepr, MgSigRepr,
+};
+use crate::rpc::serde_hex::{self, Hex};
+use crate::util::key::PublicKey;
+use crate::util::ringct::{
+    Bulletproof, CtKey, EcdhInfo, Key, MgSig, RctSig, RctSigBase, RctSigPrunable, RctType,
+    Signature, CLSAG,
+};
+
+/// Possible errors when parsing a message of the feed
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The message has no topic separator
+    #[error("missing topic separator")]
+    MissingSeparator,
+    /// The topic is not supported
+    #[error("unknown topic: {0}")]
+    UnknownTopic(String),
+    /// The payload is not valid for the topic
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Topics published by monerod
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    /// Blocks added to the main chain
+    FullChainMain,
+    /// Transactions added to the pool
+    FullTxPoolAdd,
+    /// Ids of the blocks added to the main chain
+    MinimalChainMain,
+    /// Summaries of the transactions added to the pool
+    MinimalTxPoolAdd,
+}
+
+impl Topic {
+    /// Return the name of the topic, also used to subscribe to it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::FullChainMain => "json-full-chain_main",
+            Topic::FullTxPoolAdd => "json-full-txpool_add",
+            Topic::MinimalChainMain => "json-minimal-chain_main",
+            Topic::MinimalTxPoolAdd => "json-minimal-txpool_add",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Topic {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Topic, Error> {
+        match s {
+            "json-full-chain_main" => Ok(Topic::FullChainMain),
+            "json-full-txpool_add" => Ok(Topic::FullTxPoolAdd),
+            "json-minimal-chain_main" => Ok(Topic::MinimalChainMain),
+            "json-minimal-txpool_add" => Ok(Topic::MinimalTxPoolAdd),
+            _ => Err(Error::UnknownTopic(s.to_string())),
+        }
+    }
+}
+
+/// Blocks added to the main chain, published on `json-minimal-chain_main`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinimalChain {
+    /// Height of the first block
+    pub first_height: u64,
+    /// Previous block of the first block
+    #[serde(with = "serde_hex")]
+    pub first_prev_id: Hash,
+    /// Ids of the blocks
+    #[serde(with = "serde_hex::vec")]
+    pub ids: Vec<Hash>,
+}
+
+/// Transaction added to the pool, published on `json-minimal-txpool_add`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinimalTxPoolEntry {
+    /// Transaction id
+    #[serde(with = "serde_hex")]
+    pub id: Hash,
+    /// Size of the transaction in bytes
+    pub blob_size: u64,
+    /// Weight of the transaction
+    pub weight: u64,
+    /// Fee of the transaction
+    pub fee: u64,
+}
+
+/// A message of the feed
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Blocks added to the main chain
+    FullChainMain(Vec<Block>),
+    /// Transactions added to the pool
+    FullTxPoolAdd(Vec<Transaction>),
+    /// Ids of the blocks added to the main chain
+    MinimalChainMain(MinimalChain),
+    /// Summaries of the transactions added to the pool
+    MinimalTxPoolAdd(Vec<MinimalTxPoolEntry>),
+}
+
+impl Event {
+    /// Return the topic of the event
+    pub fn topic(&self) -> Topic {
+        match self {
+            Event::FullChainMain(_) => Topic::FullChainMain,
+            Event::FullTxPoolAdd(_) => Topic::FullTxPoolAdd,
+            Event::MinimalChainMain(_) => Topic::MinimalChainMain,
+            Event::MinimalTxPoolAdd(_) => Topic::MinimalTxPoolAdd,
+        }
+    }
+}
+
+/// Split a message in its topic and its JSON payload
+pub fn split_topic(message: &[u8]) -> Result<(Topic, &[u8]), Error> {
+    let separator = message
+        .iter()
+        .position(|&b| b == b':')
+        .ok_or(Error::MissingSeparator)?;
+    let topic = String::from_utf8_lossy(&message[..separator]).parse()?;
+    Ok((topic, &message[separator + 1..]))
+}
+
+/// Parse a message of the feed
+pub fn parse(message: &[u8]) -> Result<Event, Error> {
+    let (topic, payload) = split_topic(message)?;
+    Ok(match topic {
+        Topic::FullChainMain => {
+            let blocks: Vec<JsonBlock> = serde_json::from_slice(payload)?;
+            Event::FullChainMain(blocks.into_iter().map(|b| b.0).collect())
+        }
+        Topic::FullTxPoolAdd => {
+            let txs: Vec<JsonTransaction> = serde_json::from_slice(payload)?;
+            Event::FullTxPoolAdd(txs.into_iter().map(|tx| tx.0).collect())
+        }
+        Topic::MinimalChainMain => Event::MinimalChainMain(serde_json::from_slice(payload)?),
+        Topic::MinimalTxPoolAdd => Event::MinimalTxPoolAdd(serde_json::from_slice(payload)?),
+    })
+}
+
+/// Deserialize a [`Transaction`] from the JSON representation of monerod
+///
+/// [`Transaction`]: crate::blockdata::transaction::Transaction
+pub mod transaction {
+    use super::*;
+
+    /// Deserialize a transaction, use with `#[serde(deserialize_with = "...")]`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Transaction, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        TransactionRepr::deserialize(deserializer)?.into_transaction()
+    }
+}
+
+/// Deserialize a [`Block`] from the JSON representation of monerod
+///
+/// [`Block`]: crate::blockdata::block::Block
+pub mod block {
+    use super::*;
+
+    /// Deserialize a block, use with `#[serde(deserialize_with = "...")]`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Block, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let block = BlockRepr::deserialize(deserializer)?;
+        Ok(Block {
+            header: BlockHeader {
+                major_version: VarInt(block.major_version),
+                minor_version: VarInt(block.minor_version),
+                timestamp: VarInt(block.timestamp),
+                prev_id: block.prev_id.0,
+                nonce: block.nonce,
+            },
+            miner_tx: block.miner_tx.into_transaction()?,
+            tx_hashes: block.tx_hashes.into_iter().map(|h| h.0).collect(),
+        })
+    }
+}
+
+struct JsonTransaction(Transaction);
+
+impl<'de> Deserialize<'de> for JsonTransaction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        transaction::deserialize(deserializer).map(JsonTransaction)
+    }
+}
+
+struct JsonBlock(Block);
+
+impl<'de> Deserialize<'de> for JsonBlock {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        block::deserialize(deserializer).map(JsonBlock)
+    }
+}
+
+/// Raw bytes written as a hex string or, by older versions, as an array of numbers
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Bytes {
+    Hex(String),
+    Array(Vec<u8>),
+}
+
+impl Bytes {
+    fn into_vec<E: de::Error>(self) -> Result<Vec<u8>, E> {
+        match self {
+            Bytes::Hex(s) => hex::decode(s).map_err(E::custom),
+            Bytes::Array(bytes) => Ok(bytes),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BlockRepr {
+    major_version: u64,
+    minor_version: u64,
+    timestamp: u64,
+    prev_id: Hex<Hash>,
+    nonce: u32,
+    miner_tx: TransactionRepr,
+    tx_hashes: Vec<Hex<Hash>>,
+}
+
+#[derive(Deserialize)]
+struct TransactionRepr {
+    version: u64,
+    unlock_time: u64,
+    inputs: Vec<InputRepr>,
+    outputs: Vec<OutputRepr>,
+    extra: Bytes,
+    #[serde(default)]
+    signatures: Vec<Vec<Hex<Signature>>>,
+    #[serde(default)]
+    ringct: Option<RctSigRepr>,
+}
+
+#[derive(Deserialize)]
+struct OutputRepr {
+    amount: u64,
+    #[serde(flatten)]
+    target: TargetRepr,
+}
+
+#[derive(Deserialize)]
+enum TargetRepr {
+    #[serde(rename = "to_key")]
+    Key { key: Hex<PublicKey> },
+    #[serde(rename = "to_script")]
+    Script {
+        keys: Vec<Hex<PublicKey>>,
+        script: Bytes,
+    },
+    #[serde(rename = "to_scripthash")]
+    ScriptHash { hash: Hex<Hash> },
+}
+
+#[derive(Deserialize)]
+struct RctSigRepr {
+    #[serde(rename = "type")]
+    rct_type: u8,
+    #[serde(default)]
+    encrypted: Vec<EcdhInfoRepr>,
+    #[serde(default)]
+    commitments: Vec<Hex<Key>>,
+    #[serde(default)]
+    fee: u64,
+    #[serde(default)]
+    prunable: Option<RctSigPrunableRepr>,
+}
+
+#[derive(Deserialize)]
+struct EcdhInfoRepr {
+    mask: Hex<Key>,
+    amount: Hex<Key>,
+}
+
+#[derive(Deserialize, Default)]
+struct RctSigPrunableRepr {
+    #[serde(default)]
+    range_proofs: Vec<IgnoredAny>,
+    #[serde(default)]
+    bulletproofs: Vec<BulletproofRepr>,
+    #[serde(default)]
+    mlsags: Vec<MgSigRepr>,
+    #[serde(default)]
+    clsags: Vec<ClsagRepr>,
+    #[serde(default)]
+    pseudo_outs: Vec<Hex<Key>>,
+}
+
+impl TransactionRepr {
+    fn into_transaction<E: de::Error>(self) -> Result<Transaction, E> {
+        let extra = extra_from_bytes(&self.extra.into_vec()?)?;
+        let inputs = self.inputs.into_iter().map(TxIn::from).collect::<Vec<_>>();
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|output| {
+                let target = match output.target {
+                    TargetRepr::Key { key } => TxOutTarget::ToKey { key: key.0 },
+                    TargetRepr::Script { keys, script } => TxOutTarget::ToScript {
+                        keys: keys.into_iter().map(|k| k.0).collect(),
+                        script: script.into_vec()?,
+                    },
+                    TargetRepr::ScriptHash { hash } => TxOutTarget::ToScriptHash { hash: hash.0 },
+                };
+                Ok(TxOut {
+                    amount: VarInt(output.amount),
+                    target,
+                })
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+
+        let has_inputs = !inputs.is_empty();
+        let prefix = TransactionPrefix {
+            version: VarInt(self.version),
+            unlock_time: VarInt(self.unlock_time),
+            inputs,
+            outputs,
+            extra,
+        };
+        if self.version == 1 {
+            let signatures = self
+                .signatures
+                .into_iter()
+                .map(|sigs| sigs.into_iter().map(|s| s.0).collect())
+                .collect();
+            return Ok(Transaction {
+                prefix,
+                signatures,
+                rct_signatures: RctSig { sig: None, p: None },
+            });
+        }
+        let rct_signatures = match self.ringct {
+            Some(ringct) if has_inputs => ringct.into_rct_sig()?,
+            _ => RctSig { sig: None, p: None },
+        };
+        Ok(Transaction {
+            prefix,
+            signatures: vec![],
+            rct_signatures,
+        })
+    }
+}
+
+impl RctSigRepr {
+    fn into_rct_sig<E: de::Error>(self) -> Result<RctSig, E> {
+        let rct_type = rct_type_from_u8(self.rct_type)?;
+        let prunable = self.prunable.unwrap_or_default();
+        if !prunable.range_proofs.is_empty() {
+            return Err(E::custom("borromean range signatures are not supported"));
+        }
+        // Only simple signatures store the pseudo outs in the base part
+        let pseudo_outs = keys(prunable.pseudo_outs);
+        let (base_pseudo_outs, pseudo_outs) = match rct_type {
+            RctType::Simple => (pseudo_outs, vec![]),
+            _ => (vec![], pseudo_outs),
+        };
+        let ecdh_info = self
+            .encrypted
+            .into_iter()
+            .map(|ecdh| match rct_type {
+                RctType::Bulletproof2 | RctType::CLSAG => EcdhInfo::Bulletproof {
+                    amount: Hash8::from_slice(&ecdh.amount.0.key[..8]),
+                },
+                _ => EcdhInfo::Standard {
+                    mask: ecdh.mask.0,
+                    amount: ecdh.amount.0,
+                },
+            })
+            .collect();
+        let sig = RctSigBase {
+            rct_type,
+            txn_fee: VarInt(self.fee),
+            pseudo_outs: base_pseudo_outs,
+            ecdh_info,
+            out_pk: self
+                .commitments
+                .into_iter()
+                .map(|mask| CtKey { mask: mask.0 })
+                .collect(),
+        };
+        if rct_type == RctType::Null {
+            return Ok(RctSig {
+                sig: Some(sig),
+                p: None,
+            });
+        }
+        let p = RctSigPrunable {
+            range_sigs: vec![],
+            bulletproofs: prunable
+                .bulletproofs
+                .into_iter()
+                .map(Bulletproof::from)
+                .collect(),
+            MGs: prunable.mlsags.into_iter().map(MgSig::from).collect(),
+            CLSAGs: prunable.clsags.into_iter().map(CLSAG::from).collect(),
+            pseudo_outs,
+        };
+        Ok(RctSig {
+            sig: Some(sig),
+            p: Some(p),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Error, Event, Topic};
+    use crate::consensus::encode::serialize;
+    use crate::cryptonote::hash::Hashable;
+
+    #[test]
+    fn parse_full_txpool_add() {
+        let message = br#"json-full-txpool_add:[{"version":2,"unlock_time":0,"inputs":[{"to_key":{"amount":0,"key_offsets":[2306482,29961,40489,6565,382,937,5,765,64,116,95],"key_image":"59f49374365304f8b4d5da63b444b2d74a40f8007ea44940c15cbbc80c9d1068"}}],"outputs":[{"amount":0,"to_key":{"key":"67f0f669ead579c1067cbffdf67c4af80b0287c549a10463122b4860fe215f49"}},{"amount":0,"to_key":{"key":"b6a2e2f35a93d637ff7d25e20da326cee8e92005d3b18b3c425dabe833656899"}}],"extra":"01d6c75cf8c76ac458123f2a498512eb65bb3cecba346c8fcfc516dc0c88518bb90209016f82359eb1fe71d6","signatures":[],"ringct":{"type":4,"encrypted":[{"mask":"0000000000000000000000000000000000000000000000000000000000000000","amount":"0ed5fd4624bb9fce000000000000000000000000000000000000000000000000"},{"mask":"0000000000000000000000000000000000000000000000000000000000000000","amount":"349a0e8317eabf41000000000000000000000000000000000000000000000000"}],"commitments":["72f78a8b27dec6ea1a46da10ed8620fa8367c6391eaa8aabf4ebf660d9fe0eb7","e9dfa08365a089ad2df7bce7ef776467898d5ca8947152923c54a1c5030e0c2f"],"fee":150630000,"prunable":{"range_proofs":[],"bulletproofs":[{"A":"035c555ff4285dcc44dfadd6bc37ec8b9354c045c6590446a81c7f53d8f199ca","S":"ce3faa7f17b3b8302a7cbb3881e8fdc23cca0275c9245fdc2a394b8d3ae73911","T1":"e3541b10e7725cdeef5e0307bc218caefaafe97c102f39c8ce78f62cccf23c69","T2":"baf0af55933c9d384ceaf07488f2f1ac7343a593449afd54d1065f6a1a465884","taux":"5817e4b0e810afc4ca249096e463f9f368625fa37d5bbcbe87af68ce3c4d630f","mu":"93a66defa4205b178f4e9fa04107bd535c7a4b2251df2dad255e470b611ffe00","L":["8c2916fc1eb2af1273e0df30dd1c74b6987b9885e7916b6ca711cbd4b7b50576","e51af1439e9ed9e33eb97d8faba4e3bd46066a5026a1940b852d965c1db455d1","401687ccaccc524e000b05966763564b7deb8fd64c7fb3d649897c94583dca15","58893b071f5e6700dad139f3c6f973c7a43b207ee3e67dc7f7f18b52df442258","200c7fe6d16685127da1df9b0d93d764c2659599bc6d300ae33bf8b7c2a50431","7da90ea2f0bb2af09bd531feae57cb4a0273d8add62fadfc6d43402372e5caf8","54e112b88417936f1a9c4045d48b5b0b7703d96801b35ff66c716cddbee1b924"],"R":["aa069a162c163071710e28ccddf6fb560feea32485f2c54a477ae23fd8210427","eabe4288cbe0ecbef4ed19ca049ceded424d9f839da957f56ffeb73060ea1549","8fcbc2d73606e85e963a667dafdb2641fb91862c07b98c1fdae8fadf51460022","5036dd63c22cdadb57d2125ebf30bc77f7ea0bc0dafb484bf01434954c5053b9","c8a143f06972f80fa66788ea1e3425dc0104a9e3674729967b9819552ebb1724","18da0e4b3778ad4b3d6acd8f354ba09e54bbc8604540010e1e1e4d3066515aed","457bd3399c0ce787236dbcd3923de4fb8faded10199b33c1251191612ab5526c"],"a":"1cf0cd55a0aeaed3f7a955ceced16dabdbeb0a2a19a9fdb5aa8c4fc8767cf70e","b":"4ad1838518bc6b9de7c420c1f57636579a14a5a8bdacd24e61a68adede8a2e07","t":"416c25409dd91ab78905bc99bab4ab4fb9e4ea628e09a271837769c4e67e580d"}],"mlsags":[{"cc":"10aae1bb50728ec9b8880d6011b366a850798f6d7fe07103695dded3f371ca09","ss":[["cd5485e12e4e308cb4509686a7484a71f7dfe334499808c7122f07d45d89230b","1f19ed86f675b7fec44ef5f3b178ae0af92ff114bd96baa264604fea5a762307"],["bdce6cb483b7bc780d32ed5343fcc3aa306997f211dc075f6dfd66035c1db10b","ef8656fefbb45645264d401682e42fe3e05906f79d65481b87508f1a4c434e0d"],["1dfc247d4276306f801a6b57e4e4a525177bae24e0bd88a216597d9db44f2604","c29d8a5f74e7b934f55048690b5dcefd6489a81aa64c1edb49b320faab94130e"],["603d99e455cfd828bca782176192ece95e9b967fe3dd698574cf0c0b6926970b","156e1134658de657de42c4930e72b49c0d94da66c330ab188c10f0d2f578590f"],["31bcac6fcff7e21f9ff67ae1a40d5a03b19301dcbbadc1aa9392795cf81f1401","ec16d986a7f96fbb9e8e12ce04a2226e26b78117a4dfb757c6a44481ff68bb09"],["09e7010988cd37146fb45d4cca4ba490aae323bb51a12b6864f88ea6897aa700","ee9142eaf0880844083026f044a5e3dba4aae08578cb057976001beb27b5110c"],["41fe336bf7879733739ce22fb31a1a6ac2c900d6d6c6facdbc60085e5c93d502","542cfea90dbc62d4e061b7106f09f9c4f6c1b5506dd0550eb8b2bf17678b140d"],["e33a10ba676829092e6a13445d1857d06c715eea4492ff864f0b34d178a75a0f","1353078f83cfee1440b0a20e64abbd0cab5c6e7083486002970a4904f8371805"],["d1a0ee4aea8524168f0f39d2dfc55f545a98a031841a740e8422a62e123c8303","021fb81afbb76d1120c0fbc4d3d97ba69f4e2fe086822ece2047c9ccea507008"],["654c199238a5d17f009aa2dd081f7901d0688aa15311865a319ccba8de402302","7235b5725353561c5f1185f6a063fb32fc65ef6e90339d406a6884d66be49d03"],["daaf116ee4b65ef80dd3052a13157b929f98640c0bbe99c8323ce3419a136403","dc3f7a95178c3966d2d7bdecf516a28eb2cf8cddb3a0463dc7a6248883f7be0a"]]}],"clsags":[],"pseudo_outs":["7c1d3596967320071d7f548938afe287cb9b8fae761fa592425623dcbf653028"]}}}]"#;
+        let txs = match parse(message).unwrap() {
+            Event::FullTxPoolAdd(txs) => txs,
+            event => panic!("unexpected {} event", event.topic()),
+        };
+        assert_eq!(1, txs.len());
+        assert_eq!(1, txs[0].prefix.key_images().count());
+        assert_eq!(
+            "4c9c4f6b3d298c5ffa31139929b34ae53d727adf3de70ed2b8c32835d4513e25",
+            hex::encode(txs[0].hash().as_bytes())
+        );
+    }
+
+    #[test]
+    fn parse_full_chain_main() {
+        let message = br#"json-full-chain_main:[{"major_version":12,"minor_version":12,"timestamp":1594797844,"prev_id":"beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b62","nonce":0,"miner_tx":{"version":2,"unlock_time":558235,"inputs":[{"gen":{"height":558175}}],"outputs":[{"amount":12520250477750,"to_key":{"key":"23df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a1834940"}}],"extra":"012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca6","signatures":[],"ringct":{"type":0,"encrypted":[],"commitments":[],"fee":0}},"tx_hashes":[]}]"#;
+        let blocks = match parse(message).unwrap() {
+            Event::FullChainMain(blocks) => blocks,
+            event => panic!("unexpected {} event", event.topic()),
+        };
+        assert_eq!(1, blocks.len());
+        assert_eq!("0c0c94debaf805beb3489c722a285c092a32e7c6893abfc7d069699c8326fc3445a749c5276b6200000000029b892201ffdf882201b699d4c8b1ec020223df524af2a2ef5f870adb6e1ceb03a475c39f8b9ef76aa50b46ddd2a18349402b012839bfa19b7524ec7488917714c216ca254b38ed0424ca65ae828a7c006aeaf10208f5316a7f6b99cca60000", hex::encode(serialize(&blocks[0])));
+        assert_eq!(
+            "250b860e12d169416f033712f50904100d08f42251f5ec44bc3e87b72a56c9d4",
+            hex::encode(blocks[0].hash().as_bytes())
+        );
+    }
+
+    #[test]
+    fn parse_minimal_txpool_add() {
+        let message = br#"json-minimal-txpool_add:[{"id":"4c9c4f6b3d298c5ffa31139929b34ae53d727adf3de70ed2b8c32835d4513e25","blob_size":1767,"weight":1767,"fee":150630000}]"#;
+        let entries = match parse(message).unwrap() {
+            Event::MinimalTxPoolAdd(entries) => entries,
+            event => panic!("unexpected {} event", event.topic()),
+        };
+        assert_eq!(1, entries.len());
+        assert_eq!(1767, entries[0].blob_size);
+        assert_eq!(150630000, entries[0].fee);
+
+        assert_eq!(
+            Topic::MinimalTxPoolAdd,
+            "json-minimal-txpool_add".parse().unwrap()
+        );
+        match parse(b"json-minimal-txpool_remove:[]") {
+            Err(Error::UnknownTopic(topic)) => assert_eq!("json-minimal-txpool_remove", topic),
+            _ => panic!("topic should be unknown"),
+        }
+        assert!(matches!(parse(b"[]"), Err(Error::MissingSeparator)));
+    }
+}
@@ -0,0 +1,46 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Keys and transactions shared by tests
+//!
+
+use std::str::FromStr;
+
+use crate::blockdata::coinbase::{MinerTx, MinerTxBuilder};
+use crate::cryptonote::hash::Hash;
+use crate::util::address::Address;
+use crate::util::key::{KeyPair, PrivateKey};
+
+/// Key pair of the test wallet
+pub fn keys() -> KeyPair {
+    KeyPair {
+        view: PrivateKey::from_str(
+            "77916d0cd56ed1920aef6ca56d8a41bac915b68e4c46a589e0956e27a7b77404",
+        )
+        .unwrap(),
+        spend: PrivateKey::from_str(
+            "8163466f1883598e6dd14027b8da727057165da91485834314f5500a65846f09",
+        )
+        .unwrap(),
+    }
+}
+
+/// Miner transaction paying the reward to the address, its key is derived from the height
+pub fn miner_tx(address: Address, height: u64, reward: u64) -> MinerTx {
+    let tx_key = Hash::hash(&height.to_le_bytes()).as_scalar();
+    MinerTxBuilder::new(height, reward, address, tx_key)
+        .build()
+        .unwrap()
+}
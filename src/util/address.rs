@@ -52,8 +52,11 @@ use std::str::FromStr;
 use base58_monero::base58;
 use keccak_hash::keccak_256;
 
+use crate::consensus::encode::{self, Decodable, Decoder, Encodable, Encoder};
+use crate::cryptonote::hash::Hash;
+use crate::cryptonote::onetime_key::MONERO_MUL_FACTOR;
 use crate::network::{self, Network};
-use crate::util::key::{KeyPair, PublicKey, ViewPair};
+use crate::util::key::{KeyPair, PrivateKey, PublicKey, ViewPair};
 
 use thiserror::Error;
 
@@ -72,6 +75,28 @@ pub enum Error {
     /// Invalid format
     #[error("invalid format")]
     InvalidFormat,
+    /// Invalid length for the address type
+    #[error("invalid length: expected {expected} bytes, found {found}")]
+    InvalidLength {
+        /// Length of the address type
+        expected: usize,
+        /// Length of the input
+        found: usize,
+    },
+    /// The address is not valid on the expected network
+    #[error("wrong network: expected {expected:?}, found {found:?}")]
+    WrongNetwork {
+        /// Expected network
+        expected: Network,
+        /// Network of the address
+        found: Network,
+    },
+    /// Public key which is not a valid point
+    #[error("invalid key")]
+    InvalidKey,
+    /// Public key which is not the canonical encoding of its point
+    #[error("non-canonical key")]
+    NonCanonicalKey,
     /// Monero base58 error
     #[error("Base58 error: {0}")]
     Base58(#[from] base58::Error),
@@ -94,39 +119,57 @@ pub enum AddressType {
 impl AddressType {
     /// Recover the address type given an address bytes and the network
     pub fn from_slice(bytes: &[u8], net: Network) -> Result<AddressType, Error> {
-        let byte = bytes[0];
+        let byte = *bytes.first().ok_or(Error::InvalidFormat)?;
         use AddressType::*;
         use Network::*;
         match net {
             Mainnet => match byte {
                 18 => Ok(Standard),
-                19 => {
-                    let payment_id = PaymentId::from_slice(&bytes[65..73]);
-                    Ok(Integrated(payment_id))
-                }
+                19 => Ok(Integrated(payment_id_from_slice(bytes)?)),
                 42 => Ok(SubAddress),
                 _ => Err(Error::InvalidMagicByte),
             },
             Testnet => match byte {
                 53 => Ok(Standard),
-                54 => {
-                    let payment_id = PaymentId::from_slice(&bytes[65..73]);
-                    Ok(Integrated(payment_id))
-                }
+                54 => Ok(Integrated(payment_id_from_slice(bytes)?)),
                 63 => Ok(SubAddress),
                 _ => Err(Error::InvalidMagicByte),
             },
             Stagenet => match byte {
                 24 => Ok(Standard),
-                25 => {
-                    let payment_id = PaymentId::from_slice(&bytes[65..73]);
-                    Ok(Integrated(payment_id))
-                }
+                25 => Ok(Integrated(payment_id_from_slice(bytes)?)),
                 36 => Ok(SubAddress),
                 _ => Err(Error::InvalidMagicByte),
             },
         }
     }
+
+    /// Return the length in bytes of an address of this type, checksum included
+    pub fn address_length(&self) -> usize {
+        match self {
+            AddressType::Standard | AddressType::SubAddress => 69,
+            AddressType::Integrated(_) => 77,
+        }
+    }
+}
+
+fn payment_id_from_slice(bytes: &[u8]) -> Result<PaymentId, Error> {
+    match bytes.get(65..73) {
+        Some(payment_id) => Ok(PaymentId::from_slice(payment_id)),
+        None => Err(Error::InvalidLength {
+            expected: 77,
+            found: bytes.len(),
+        }),
+    }
+}
+
+/// Parse a public key of an address, which must be the canonical encoding of a valid point
+fn key_from_slice(bytes: &[u8]) -> Result<PublicKey, Error> {
+    let key = PublicKey::from_slice(bytes).map_err(|_| Error::InvalidKey)?;
+    match key.point.decompress() {
+        Some(point) if point.compress() == key.point => Ok(key),
+        _ => Err(Error::NonCanonicalKey),
+    }
 }
 
 impl Default for AddressType {
@@ -150,6 +193,53 @@ fixed_hash::construct_fixed_hash! {
     pub struct PaymentId(8);
 }
 
+impl<D: Decoder> Decodable<D> for PaymentId {
+    fn consensus_decode(d: &mut D) -> Result<PaymentId, encode::Error> {
+        Ok(PaymentId(Decodable::consensus_decode(d)?))
+    }
+}
+
+impl<S: Encoder> Encodable<S> for PaymentId {
+    fn consensus_encode(&self, s: &mut S) -> Result<(), encode::Error> {
+        self.0.consensus_encode(s)
+    }
+}
+
+impl_hex_serde!(PaymentId, 8, |bytes: &[u8]| match bytes.len() {
+    8 => Ok(PaymentId::from_slice(bytes)),
+    _ => Err("invalid length"),
+});
+
+/// Tail byte appended to the key derivation when hashing the payment id encryption key
+const ENCRYPTED_PAYMENT_ID_TAIL: u8 = 0x8d;
+
+impl PaymentId {
+    /// Encrypt the payment id with the recipient view public key `V` and the transaction secret
+    /// key `r`, as stored in the transaction extra nonce
+    pub fn encrypt(&self, view: &PublicKey, tx_secret: &PrivateKey) -> PaymentId {
+        self.xor_derivation(*tx_secret * MONERO_MUL_FACTOR * view)
+    }
+
+    /// Decrypt the payment id with the transaction public key `R` and the recipient view secret
+    /// key `v`
+    pub fn decrypt(&self, tx_pubkey: &PublicKey, view: &PrivateKey) -> PaymentId {
+        self.xor_derivation(*view * MONERO_MUL_FACTOR * tx_pubkey)
+    }
+
+    // XOR the payment id with the first 8 bytes of `H(8*r*V || 0x8d)`, encryption and decryption
+    // are the same operation
+    fn xor_derivation(&self, derivation: PublicKey) -> PaymentId {
+        let mut data = derivation.as_bytes().to_vec();
+        data.push(ENCRYPTED_PAYMENT_ID_TAIL);
+        let key = Hash::hash(&data);
+        let mut payment_id = *self;
+        for (b, k) in payment_id.0.iter_mut().zip(key.as_bytes()) {
+            *b ^= k;
+        }
+        payment_id
+    }
+}
+
 /// A generic Monero address
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub struct Address {
@@ -226,21 +316,24 @@ impl Address {
         }
     }
 
-    /// Parse an address from a vector of bytes, fail if the magic byte is incorrect, if public
-    /// keys are not valid points, if payment id is invalid, and if checksums missmatch
+    /// Parse an address from a vector of bytes, fail if the magic byte is incorrect, if the length
+    /// does not match the address type, if public keys are not canonical encodings of valid
+    /// points, and if checksums missmatch
     pub fn from_bytes(bytes: &[u8]) -> Result<Address, Error> {
-        let network = Network::from_u8(bytes[0])?;
+        let network = Network::from_u8(*bytes.first().ok_or(Error::InvalidFormat)?)?;
         let addr_type = AddressType::from_slice(&bytes, network)?;
-        let public_spend =
-            PublicKey::from_slice(&bytes[1..33]).map_err(|_| Error::InvalidFormat)?;
-        let public_view =
-            PublicKey::from_slice(&bytes[33..65]).map_err(|_| Error::InvalidFormat)?;
+        let length = addr_type.address_length();
+        if bytes.len() != length {
+            return Err(Error::InvalidLength {
+                expected: length,
+                found: bytes.len(),
+            });
+        }
+        let public_spend = key_from_slice(&bytes[1..33])?;
+        let public_view = key_from_slice(&bytes[33..65])?;
 
         let mut verify_checksum = [0u8; 32];
-        let (checksum_bytes, checksum) = match addr_type {
-            AddressType::Standard | AddressType::SubAddress => (&bytes[0..65], &bytes[65..69]),
-            AddressType::Integrated(_) => (&bytes[0..73], &bytes[73..77]),
-        };
+        let (checksum_bytes, checksum) = bytes.split_at(length - 4);
         keccak_256(checksum_bytes, &mut verify_checksum);
         if &verify_checksum[0..4] != checksum {
             return Err(Error::InvalidChecksum);
@@ -254,6 +347,25 @@ impl Address {
         })
     }
 
+    /// Parse an address from a vector of bytes like [`Address::from_bytes`], fail if the address
+    /// is not valid on the expected network
+    pub fn from_bytes_with_network(bytes: &[u8], network: Network) -> Result<Address, Error> {
+        let address = Address::from_bytes(bytes)?;
+        if address.network != network {
+            return Err(Error::WrongNetwork {
+                expected: network,
+                found: address.network,
+            });
+        }
+        Ok(address)
+    }
+
+    /// Parse an address from its base58 string, fail if the address is not valid on the expected
+    /// network
+    pub fn from_str_with_network(s: &str, network: Network) -> Result<Address, Error> {
+        Address::from_bytes_with_network(&base58::decode(s)?, network)
+    }
+
     /// Serialize the address as a vector of bytes
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![self.network.as_u8(&self.addr_type)];
@@ -294,6 +406,7 @@ mod serde_impl {
 
     use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
+    /// Addresses are written as their base58 string in human-readable and binary formats
     impl Serialize for Address {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
@@ -318,7 +431,10 @@ mod serde_impl {
 mod tests {
     use std::str::FromStr;
 
-    use super::{base58, Address, Network, PaymentId, PublicKey};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{base58, Address, AddressType, Error, Network, PaymentId, PublicKey};
 
     #[test]
     fn deserialize_address() {
@@ -418,4 +534,109 @@ mod tests {
         let add = Address::from_str(address).unwrap();
         assert_eq!(address, add.to_string());
     }
+
+    #[test]
+    fn invalid_addresses() {
+        let address = "4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra";
+        let bytes = base58::decode(address).unwrap();
+
+        assert_eq!(Err(Error::InvalidFormat), Address::from_bytes(&[]));
+        assert_eq!(
+            Err(Error::InvalidLength {
+                expected: 69,
+                found: 68
+            }),
+            Address::from_bytes(&bytes[..68])
+        );
+        assert_eq!(
+            Err(Error::InvalidLength {
+                expected: 69,
+                found: 70
+            }),
+            Address::from_bytes(&[&bytes[..], &[0]].concat())
+        );
+
+        // Integrated address magic byte without a payment id
+        let mut integrated = bytes.clone();
+        integrated[0] = 19;
+        assert_eq!(
+            Err(Error::InvalidLength {
+                expected: 77,
+                found: 69
+            }),
+            Address::from_bytes(&integrated)
+        );
+        assert_eq!(
+            Err(Error::InvalidLength {
+                expected: 77,
+                found: 1
+            }),
+            AddressType::from_slice(&integrated[..1], Network::Mainnet)
+        );
+
+        assert_eq!(
+            Err(Error::WrongNetwork {
+                expected: Network::Stagenet,
+                found: Network::Mainnet
+            }),
+            Address::from_str_with_network(address, Network::Stagenet)
+        );
+        assert!(Address::from_str_with_network(address, Network::Mainnet).is_ok());
+
+        // y = 2 is not on the curve
+        let mut invalid = bytes.clone();
+        invalid[1..33].copy_from_slice(&[&[2][..], &[0; 31][..]].concat());
+        assert_eq!(Err(Error::InvalidKey), Address::from_bytes(&invalid));
+
+        // y = p + 1 is a non-canonical encoding of y = 1
+        let mut non_canonical = bytes;
+        non_canonical[33..65].copy_from_slice(&[&[0xee][..], &[0xff; 30][..], &[0x7f]].concat());
+        assert_eq!(
+            Err(Error::NonCanonicalKey),
+            Address::from_bytes(&non_canonical)
+        );
+    }
+
+    #[test]
+    fn fuzz_address_parsing() {
+        const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+        let mut rng = StdRng::seed_from_u64(18);
+
+        for _ in 0..10_000 {
+            let len = rng.gen_range(0, 100);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let _ = Address::from_bytes(&bytes);
+            for network in &[Network::Mainnet, Network::Testnet, Network::Stagenet] {
+                let _ = AddressType::from_slice(&bytes, *network);
+            }
+            let s: String = (0..len)
+                .map(|_| ALPHABET[rng.gen_range(0, ALPHABET.len())] as char)
+                .collect();
+            let _ = s.parse::<Address>();
+        }
+
+        // Any corruption of a valid address is an error
+        let address = "4Byr22j9M2878Mtyb3fEPcBNwBZf5EXqn1Yi6VzR46618SFBrYysab2Cs1474CVDbsh94AJq7vuV3Z2DRq4zLcY3LHzo1Nbv3d8J6VhvCV";
+        let bytes = base58::decode(address).unwrap();
+        for _ in 0..1_000 {
+            let mut corrupted = bytes.clone();
+            let i = rng.gen_range(0, corrupted.len());
+            corrupted[i] ^= rng.gen_range(1, 256) as u8;
+            assert!(Address::from_bytes(&corrupted).is_err());
+            let len = rng.gen_range(0, bytes.len());
+            assert!(Address::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn serde_address() {
+        use serde_test::{assert_tokens, Configure, Token};
+
+        let address = "4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra";
+        let add = Address::from_str(address).unwrap();
+        assert_tokens(&add.readable(), &[Token::Str(address)]);
+        // Binary formats keep the string form
+        assert_tokens(&add.compact(), &[Token::Str(address)]);
+    }
 }
@@ -0,0 +1,411 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Amounts of monero
+//!
+//! An [`Amount`] is a number of piconero, the atomic unit, and is parsed from and displayed in
+//! any [`Denomination`] without going through floating point numbers.
+//!
+//! ```rust
+//! use monero::util::amount::{Amount, Denomination};
+//!
+//! let amount = Amount::from_str_in("1.5", Denomination::Monero).unwrap();
+//! assert_eq!(1_500_000_000_000, amount.as_pico());
+//! assert_eq!("1.5 xmr", amount.to_string());
+//! assert_eq!(amount, "1500 millinero".parse().unwrap());
+//! ```
+//!
+
+use std::fmt;
+use std::ops;
+use std::str::FromStr;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Denominations in which amounts can be expressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Denomination {
+    /// xmr, 10^12 piconero
+    Monero,
+    /// millinero, 10^9 piconero
+    Millinero,
+    /// micronero, 10^6 piconero
+    Micronero,
+    /// nanonero, 10^3 piconero
+    Nanonero,
+    /// piconero, the atomic unit
+    Piconero,
+}
+
+impl Denomination {
+    /// Number of decimal places of a piconero in the denomination
+    fn precision(self) -> usize {
+        match self {
+            Denomination::Monero => 12,
+            Denomination::Millinero => 9,
+            Denomination::Micronero => 6,
+            Denomination::Nanonero => 3,
+            Denomination::Piconero => 0,
+        }
+    }
+}
+
+impl fmt::Display for Denomination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Denomination::Monero => "xmr",
+            Denomination::Millinero => "millinero",
+            Denomination::Micronero => "micronero",
+            Denomination::Nanonero => "nanonero",
+            Denomination::Piconero => "piconero",
+        })
+    }
+}
+
+impl FromStr for Denomination {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xmr" | "XMR" | "monero" => Ok(Denomination::Monero),
+            "millinero" | "mXMR" => Ok(Denomination::Millinero),
+            "micronero" | "µXMR" => Ok(Denomination::Micronero),
+            "nanonero" | "nXMR" => Ok(Denomination::Nanonero),
+            "piconero" | "pXMR" => Ok(Denomination::Piconero),
+            d => Err(ParsingError::UnknownDenomination(d.to_string())),
+        }
+    }
+}
+
+/// Possible errors when parsing an amount
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParsingError {
+    /// The amount is negative
+    #[error("amount is negative")]
+    Negative,
+    /// The amount does not fit in 64 bits of piconero
+    #[error("amount is too big")]
+    TooBig,
+    /// The amount is more precise than a piconero
+    #[error("amount is more precise than a piconero")]
+    TooPrecise,
+    /// The amount is not a decimal number
+    #[error("invalid amount format")]
+    InvalidFormat,
+    /// The amount contains an invalid character
+    #[error("invalid character in amount: {0}")]
+    InvalidCharacter(char),
+    /// The denomination is unknown
+    #[error("unknown denomination: {0}")]
+    UnknownDenomination(String),
+}
+
+/// Parse a decimal number in a denomination into piconero
+fn parse_piconero(s: &str, denom: Denomination) -> Result<u64, ParsingError> {
+    if s.starts_with('-') {
+        return Err(ParsingError::Negative);
+    }
+    let (integer, fraction) = match s.find('.') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(ParsingError::InvalidFormat);
+    }
+    if let Some(c) = integer
+        .chars()
+        .chain(fraction.chars())
+        .find(|c| !c.is_ascii_digit())
+    {
+        return Err(match c {
+            '.' => ParsingError::InvalidFormat,
+            c => ParsingError::InvalidCharacter(c),
+        });
+    }
+
+    let precision = denom.precision();
+    // Trailing zeros beyond a piconero do not change the value
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > precision {
+        return Err(ParsingError::TooPrecise);
+    }
+
+    let mut value: u64 = 0;
+    let digits = integer
+        .bytes()
+        .chain(fraction.bytes())
+        .chain(std::iter::repeat_n(b'0', precision - fraction.len()));
+    for digit in digits {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((digit - b'0') as u64))
+            .ok_or(ParsingError::TooBig)?;
+    }
+    Ok(value)
+}
+
+/// An amount of monero, stored as piconero
+///
+/// Amounts are serialized as a number of piconero, as done by the RPC servers.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct Amount(u64);
+
+impl Amount {
+    /// No amount
+    pub const ZERO: Amount = Amount(0);
+    /// One piconero
+    pub const ONE_PICO: Amount = Amount(1);
+    /// One monero
+    pub const ONE_XMR: Amount = Amount(1_000_000_000_000);
+
+    /// Create an amount from a number of piconero
+    pub fn from_pico(piconero: u64) -> Amount {
+        Amount(piconero)
+    }
+
+    /// Return the number of piconero
+    pub fn as_pico(self) -> u64 {
+        self.0
+    }
+
+    /// Parse a decimal number in the denomination, e.g. `"0.1"` in [`Denomination::Monero`]
+    pub fn from_str_in(s: &str, denom: Denomination) -> Result<Amount, ParsingError> {
+        parse_piconero(s, denom).map(Amount)
+    }
+
+    /// Parse a decimal number followed by a space and the denomination, e.g. `"0.1 xmr"`
+    pub fn from_str_with_denomination(s: &str) -> Result<Amount, ParsingError> {
+        let mut split = s.splitn(3, ' ');
+        match (split.next(), split.next(), split.next()) {
+            (Some(amount), Some(denom), None) => Amount::from_str_in(amount, denom.parse()?),
+            _ => Err(ParsingError::InvalidFormat),
+        }
+    }
+
+    /// Format the amount as a decimal number in the denomination, without trailing zeros
+    pub fn to_string_in(self, denom: Denomination) -> String {
+        let precision = denom.precision();
+        let digits = format!("{:0>width$}", self.0, width = precision + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - precision);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            integer.to_string()
+        } else {
+            format!("{}.{}", integer, fraction)
+        }
+    }
+
+    /// Format the amount in the denomination followed by the denomination, e.g. `"0.1 xmr"`
+    pub fn to_string_with_denomination(self, denom: Denomination) -> String {
+        format!("{} {}", self.to_string_in(denom), denom)
+    }
+
+    /// Checked addition, `None` on overflow
+    pub fn checked_add(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_add(rhs.0).map(Amount)
+    }
+
+    /// Checked subtraction, `None` if the result is negative
+    pub fn checked_sub(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_sub(rhs.0).map(Amount)
+    }
+
+    /// Checked multiplication, `None` on overflow
+    pub fn checked_mul(self, rhs: u64) -> Option<Amount> {
+        self.0.checked_mul(rhs).map(Amount)
+    }
+
+    /// Checked integer division, `None` if `rhs` is zero
+    pub fn checked_div(self, rhs: u64) -> Option<Amount> {
+        self.0.checked_div(rhs).map(Amount)
+    }
+}
+
+impl fmt::Debug for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Amount({} piconero)", self.0)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_string_with_denomination(Denomination::Monero))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Amount::from_str_with_denomination(s)
+    }
+}
+
+impl ops::Add for Amount {
+    type Output = Amount;
+
+    fn add(self, rhs: Amount) -> Amount {
+        self.checked_add(rhs).expect("Amount addition overflow")
+    }
+}
+
+impl ops::AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Amount) {
+        *self = *self + rhs
+    }
+}
+
+impl ops::Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, rhs: Amount) -> Amount {
+        self.checked_sub(rhs).expect("Amount subtraction underflow")
+    }
+}
+
+impl ops::SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Amount) {
+        *self = *self - rhs
+    }
+}
+
+impl ops::Mul<u64> for Amount {
+    type Output = Amount;
+
+    fn mul(self, rhs: u64) -> Amount {
+        self.checked_mul(rhs)
+            .expect("Amount multiplication overflow")
+    }
+}
+
+impl ops::Div<u64> for Amount {
+    type Output = Amount;
+
+    fn div(self, rhs: u64) -> Amount {
+        self.checked_div(rhs).expect("Amount division by zero")
+    }
+}
+
+impl std::iter::Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, |acc, amount| acc + amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Amount, Denomination, ParsingError};
+
+    #[test]
+    fn parse_amounts() {
+        use Denomination::*;
+
+        assert_eq!(Ok(Amount::ONE_XMR), Amount::from_str_in("1", Monero));
+        assert_eq!(Ok(Amount::ONE_XMR), Amount::from_str_in("1.000", Monero));
+        assert_eq!(
+            Ok(Amount::from_pico(1)),
+            Amount::from_str_in(".000000000001", Monero)
+        );
+        assert_eq!(
+            Ok(Amount::from_pico(1_500)),
+            Amount::from_str_in("1.5", Nanonero)
+        );
+        assert_eq!(
+            Ok(Amount::from_pico(42)),
+            Amount::from_str_in("42", Piconero)
+        );
+        assert_eq!(
+            Ok(Amount::from_pico(u64::MAX)),
+            Amount::from_str_in("18446744.073709551615", Monero)
+        );
+
+        assert_eq!(
+            Err(ParsingError::TooPrecise),
+            Amount::from_str_in("0.0000000000001", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::TooPrecise),
+            Amount::from_str_in("1.5", Piconero)
+        );
+        assert_eq!(
+            Err(ParsingError::TooBig),
+            Amount::from_str_in("18446744.073709551616", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::Negative),
+            Amount::from_str_in("-1", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::InvalidFormat),
+            Amount::from_str_in("", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::InvalidFormat),
+            Amount::from_str_in(".", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::InvalidFormat),
+            Amount::from_str_in("1.2.3", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::InvalidCharacter('e')),
+            Amount::from_str_in("1e3", Monero)
+        );
+        assert_eq!(
+            Err(ParsingError::UnknownDenomination("btc".to_string())),
+            "1 btc".parse::<Amount>()
+        );
+    }
+
+    #[test]
+    fn display_amounts() {
+        assert_eq!("0 xmr", Amount::ZERO.to_string());
+        assert_eq!("0.000000000001 xmr", Amount::ONE_PICO.to_string());
+        assert_eq!("1.25 xmr", (Amount::ONE_XMR * 5 / 4).to_string());
+        assert_eq!(
+            "1250 millinero",
+            (Amount::ONE_XMR * 5 / 4).to_string_with_denomination(Denomination::Millinero)
+        );
+
+        for s in &[
+            "0 xmr",
+            "12.345 xmr",
+            "18446744.073709551615 xmr",
+            "7 piconero",
+        ] {
+            assert_eq!(
+                *s,
+                s.parse::<Amount>()
+                    .unwrap()
+                    .to_string_with_denomination(s.split(' ').nth(1).unwrap().parse().unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Amount::from_pico(10);
+        assert_eq!(Amount::from_pico(15), a + Amount::from_pico(5));
+        assert_eq!(None, a.checked_sub(Amount::from_pico(11)));
+        assert_eq!(
+            None,
+            Amount::from_pico(u64::MAX).checked_add(Amount::ONE_PICO)
+        );
+        assert_eq!(Amount::from_pico(30), vec![a, a, a].into_iter().sum());
+    }
+}
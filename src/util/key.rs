@@ -73,9 +73,6 @@ use crate::cryptonote::hash;
 
 use thiserror::Error;
 
-#[cfg(feature = "serde_support")]
-use serde::{Deserialize, Serialize};
-
 /// Errors that might occur during key decoding
 #[derive(Error, Debug, PartialEq)]
 pub enum Error {
@@ -246,9 +243,10 @@ impl<S: Encoder> Encodable<S> for PrivateKey {
     }
 }
 
+impl_hex_serde!(PrivateKey, 32, PrivateKey::from_slice);
+
 /// Monero public key
 #[derive(PartialEq, Eq, Copy, Clone)]
-#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct PublicKey {
     /// The actual Ed25519 point
     pub point: CompressedEdwardsY,
@@ -442,6 +440,8 @@ impl<S: Encoder> Encodable<S> for PublicKey {
     }
 }
 
+impl_hex_serde!(PublicKey, 32, PublicKey::from_slice);
+
 impl hash::Hashable for PublicKey {
     fn hash(&self) -> hash::Hash {
         hash::Hash::hash(self.as_bytes())
@@ -540,4 +540,26 @@ mod tests {
         let pubkey = PublicKey::from_private_key(&priv_res);
         assert_eq!(pubkey, pub_res);
     }
+
+    #[test]
+    #[cfg(feature = "serde_support")]
+    fn serde_keys() {
+        use serde_test::{assert_tokens, Configure, Token};
+
+        let hex = "77916d0cd56ed1920aef6ca56d8a41bac915b68e4c46a589e0956e27a7b77404";
+        let privkey = PrivateKey::from_str(hex).unwrap();
+        let pubkey = PublicKey::from_private_key(&privkey);
+        assert_tokens(&privkey.readable(), &[Token::Str(hex)]);
+        assert_tokens(
+            &pubkey.readable(),
+            &[Token::Str(
+                "eac2cc96e0ae684388e3185d5277e51313bff98b9ad4a12dcd9205f20d37f1a3",
+            )],
+        );
+
+        let mut tokens = vec![Token::Tuple { len: 32 }];
+        tokens.extend(pubkey.as_bytes().iter().map(|b| Token::U8(*b)));
+        tokens.push(Token::TupleEnd);
+        assert_tokens(&pubkey.compact(), &tokens);
+    }
 }
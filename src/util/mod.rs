@@ -19,8 +19,11 @@
 //!
 
 pub mod address;
+pub mod amount;
 pub mod key;
+pub mod openalias;
 pub mod ringct;
+pub mod uri;
 
 use super::network;
 use thiserror::Error;
@@ -38,4 +41,7 @@ pub enum Error {
     /// Monero key error
     #[error("Key error: {0}")]
     Key(#[from] key::Error),
+    /// Monero amount parsing error
+    #[error("Amount parsing error: {0}")]
+    AmountParsing(#[from] amount::ParsingError),
 }
@@ -0,0 +1,274 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! OpenAlias
+//!
+//! An OpenAlias maps a domain name, e.g. `donate.example.org`, to an address with a DNS TXT
+//! record `oa1:xmr recipient_address=<address>; recipient_name=<name>; tx_description=<text>;`.
+//! The DNS lookup is done by a [`Resolver`] provided by the caller.
+//!
+//! ```rust
+//! use monero::network::Network;
+//! use monero::util::openalias::{self, Resolver, TxtRecords};
+//!
+//! struct Local;
+//!
+//! impl Resolver for Local {
+//!     type Error = std::io::Error;
+//!
+//!     fn resolve_txt(&self, name: &str) -> Result<TxtRecords, Self::Error> {
+//!         assert_eq!("donate.example.org", name);
+//!         Ok(TxtRecords {
+//!             records: vec!["oa1:xmr recipient_address=4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra; recipient_name=Example;".to_string()],
+//!             dnssec_valid: true,
+//!         })
+//!     }
+//! }
+//!
+//! let records = openalias::resolve(&Local, "donate@example.org", Network::Mainnet).unwrap();
+//! assert_eq!(Some("Example"), records[0].name.as_deref());
+//! ```
+//!
+//! **Same as** `address_from_txt_record` and `addresses_from_url` in [`dns_utils.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/common/dns_utils.cpp)
+//!
+
+use std::error::Error as StdError;
+
+use thiserror::Error;
+
+use crate::network::Network;
+use crate::util::address::{self, Address};
+
+/// Prefix of the OpenAlias records of Monero addresses
+pub const RECORD_PREFIX: &str = "oa1:xmr";
+
+/// Possible errors when parsing or resolving OpenAlias records
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The record is not an OpenAlias record of a Monero address
+    #[error("not a Monero OpenAlias record")]
+    NotMoneroRecord,
+    /// The record has no `recipient_address`
+    #[error("missing recipient address")]
+    MissingAddress,
+    /// Invalid recipient address
+    #[error("invalid recipient address: {0}")]
+    Address(#[from] address::Error),
+    /// The resolver failed to look up the records
+    #[error("resolver error: {0}")]
+    Resolver(Box<dyn StdError + Send + Sync>),
+    /// The records are not validated by DNSSEC
+    #[error("records are not validated by DNSSEC")]
+    DnssecInvalid,
+    /// No valid Monero record found for the alias
+    #[error("no Monero OpenAlias record found")]
+    NoRecord,
+}
+
+/// A Monero OpenAlias record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenAliasRecord {
+    /// The recipient address
+    pub address: Address,
+    /// The name of the recipient
+    pub name: Option<String>,
+    /// The description of the payment
+    pub description: Option<String>,
+}
+
+impl OpenAliasRecord {
+    /// Parse a TXT record string, the address must be valid on the network
+    ///
+    /// Unknown keys, e.g. `tx_payment_id` or `checksum`, are ignored.
+    pub fn parse(txt: &str, network: Network) -> Result<OpenAliasRecord, Error> {
+        let start = txt.find(RECORD_PREFIX).ok_or(Error::NotMoneroRecord)?;
+        let mut address = None;
+        let mut name = None;
+        let mut description = None;
+        for field in txt[start + RECORD_PREFIX.len()..].split(';') {
+            let (key, value) = match field.find('=') {
+                Some(i) => (field[..i].trim(), field[i + 1..].trim()),
+                None => continue,
+            };
+            match key {
+                "recipient_address" => {
+                    address = Some(Address::from_str_with_network(value, network)?)
+                }
+                "recipient_name" => name = Some(value.to_string()),
+                "tx_description" => description = Some(value.to_string()),
+                _ => (),
+            }
+        }
+        Ok(OpenAliasRecord {
+            address: address.ok_or(Error::MissingAddress)?,
+            name,
+            description,
+        })
+    }
+}
+
+/// TXT records of a domain name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxtRecords {
+    /// The records
+    pub records: Vec<String>,
+    /// Whether the records are validated by DNSSEC
+    pub dnssec_valid: bool,
+}
+
+/// A DNS resolver of TXT records
+pub trait Resolver {
+    /// Error of the resolver
+    type Error: Into<Box<dyn StdError + Send + Sync>>;
+
+    /// Look up the TXT records of the domain name
+    fn resolve_txt(&self, name: &str) -> Result<TxtRecords, Self::Error>;
+}
+
+/// Return the domain name of an alias, `user@domain` becomes `user.domain`
+pub fn dns_name(alias: &str) -> String {
+    alias.replacen('@', ".", 1)
+}
+
+/// Resolve the Monero records of an alias, records must be validated by DNSSEC and addresses
+/// valid on the network
+///
+/// Records of other currencies and invalid records are skipped, it is an error if no valid Monero
+/// record remains.
+pub fn resolve<R: Resolver>(
+    resolver: &R,
+    alias: &str,
+    network: Network,
+) -> Result<Vec<OpenAliasRecord>, Error> {
+    let txt = resolver
+        .resolve_txt(&dns_name(alias))
+        .map_err(|e| Error::Resolver(e.into()))?;
+    if !txt.dnssec_valid {
+        return Err(Error::DnssecInvalid);
+    }
+    let records: Vec<OpenAliasRecord> = txt
+        .records
+        .iter()
+        .filter_map(|record| OpenAliasRecord::parse(record, network).ok())
+        .collect();
+    if records.is_empty() {
+        return Err(Error::NoRecord);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io;
+
+    use super::{dns_name, resolve, Error, OpenAliasRecord, Resolver, TxtRecords};
+    use crate::network::Network;
+    use crate::util::address;
+
+    const ADDRESS: &str = "4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra";
+
+    struct Local(HashMap<&'static str, TxtRecords>);
+
+    impl Resolver for Local {
+        type Error = io::Error;
+
+        fn resolve_txt(&self, name: &str) -> Result<TxtRecords, io::Error> {
+            self.0
+                .get(name)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "NXDOMAIN"))
+        }
+    }
+
+    #[test]
+    fn parse_record() {
+        let txt = format!(
+            "oa1:xmr recipient_address={}; recipient_name=Monero Development; tx_description=Donation to the General Fund;",
+            ADDRESS
+        );
+        let record = OpenAliasRecord::parse(&txt, Network::Mainnet).unwrap();
+        assert_eq!(ADDRESS, record.address.to_string());
+        assert_eq!(Some("Monero Development"), record.name.as_deref());
+        assert_eq!(
+            Some("Donation to the General Fund"),
+            record.description.as_deref()
+        );
+
+        assert!(matches!(
+            OpenAliasRecord::parse("oa1:btc recipient_address=1abc;", Network::Mainnet),
+            Err(Error::NotMoneroRecord)
+        ));
+        assert!(matches!(
+            OpenAliasRecord::parse("oa1:xmr recipient_name=x;", Network::Mainnet),
+            Err(Error::MissingAddress)
+        ));
+        assert!(matches!(
+            OpenAliasRecord::parse(&txt, Network::Stagenet),
+            Err(Error::Address(address::Error::WrongNetwork { .. }))
+        ));
+    }
+
+    #[test]
+    fn resolve_alias() {
+        assert_eq!("donate.example.org", dns_name("donate@example.org"));
+        assert_eq!("donate.example.org", dns_name("donate.example.org"));
+
+        let mut zone = HashMap::new();
+        zone.insert(
+            "donate.example.org",
+            TxtRecords {
+                records: vec![
+                    "v=spf1 -all".to_string(),
+                    "oa1:btc recipient_address=1abc;".to_string(),
+                    format!("oa1:xmr recipient_address={};", ADDRESS),
+                ],
+                dnssec_valid: true,
+            },
+        );
+        zone.insert(
+            "insecure.example.org",
+            TxtRecords {
+                records: vec![format!("oa1:xmr recipient_address={};", ADDRESS)],
+                dnssec_valid: false,
+            },
+        );
+        zone.insert(
+            "empty.example.org",
+            TxtRecords {
+                records: vec!["oa1:btc recipient_address=1abc;".to_string()],
+                dnssec_valid: true,
+            },
+        );
+        let resolver = Local(zone);
+
+        let records = resolve(&resolver, "donate@example.org", Network::Mainnet).unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(ADDRESS, records[0].address.to_string());
+
+        assert!(matches!(
+            resolve(&resolver, "insecure.example.org", Network::Mainnet),
+            Err(Error::DnssecInvalid)
+        ));
+        assert!(matches!(
+            resolve(&resolver, "empty.example.org", Network::Mainnet),
+            Err(Error::NoRecord)
+        ));
+        assert!(matches!(
+            resolve(&resolver, "unknown.example.org", Network::Mainnet),
+            Err(Error::Resolver(_))
+        ));
+    }
+}
@@ -23,6 +23,11 @@ use std::fmt::{Display, Error as FmtError, Formatter};
 
 use crate::consensus::encode::{self, serialize, Decodable, Decoder, Encodable, Encoder, VarInt};
 use crate::cryptonote::hash;
+use crate::util::key::PrivateKey;
+
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde_support")]
@@ -171,6 +176,41 @@ impl EcdhInfo {
             }),
         }
     }
+
+    /// Decrypt the amount and the mask of the output with the output shared key `Hs(8*a*R || n)`,
+    /// the result must be checked against the output commitment, see [`RctSigBase::open_output`]
+    ///
+    /// **Same as** `ecdhDecode` in [`rctOps.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/ringct/rctOps.cpp)
+    pub fn open(&self, shared_key: &PrivateKey) -> Opening {
+        match self {
+            EcdhInfo::Standard { mask, amount } => {
+                let shared_mask = hash::Hash::hash_to_scalar(shared_key.as_bytes());
+                let shared_amount = hash::Hash::hash_to_scalar(shared_mask.as_bytes());
+                let mask = Scalar::from_bytes_mod_order(mask.key) - shared_mask.scalar;
+                let amount = Scalar::from_bytes_mod_order(amount.key) - shared_amount.scalar;
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&amount.as_bytes()[..8]);
+                Opening {
+                    amount: u64::from_le_bytes(bytes),
+                    mask: PrivateKey::from_scalar(mask),
+                }
+            }
+            EcdhInfo::Bulletproof { amount } => {
+                let mask = hash::Hash::hash_to_scalar(
+                    &[&b"commitment_mask"[..], shared_key.as_bytes()].concat(),
+                );
+                let key = hash::Hash::hash(&[&b"amount"[..], shared_key.as_bytes()].concat());
+                let mut bytes = [0u8; 8];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = amount.0[i] ^ key.0[i];
+                }
+                Opening {
+                    amount: u64::from_le_bytes(bytes),
+                    mask,
+                }
+            }
+        }
+    }
 }
 
 impl<S: Encoder> Encodable<S> for EcdhInfo {
@@ -188,6 +228,37 @@ impl<S: Encoder> Encodable<S> for EcdhInfo {
     }
 }
 
+// ====================================================================
+/// Generator `H` of the amounts in commitments
+///
+/// **Same as** `rct::H` in [`rctTypes.h`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/ringct/rctTypes.h)
+pub const H: [u8; 32] = [
+    0x8b, 0x65, 0x59, 0x70, 0x15, 0x37, 0x99, 0xaf, 0x2a, 0xea, 0xdc, 0x9f, 0xf1, 0xad, 0xd0, 0xea,
+    0x6c, 0x72, 0x51, 0xd5, 0x41, 0x54, 0xcf, 0xa9, 0x2c, 0x17, 0x3a, 0x0d, 0xd3, 0x9c, 0x1f, 0x94,
+];
+
+/// Amount and mask opening an output commitment `C = mask*G + amount*H`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opening {
+    /// The amount
+    pub amount: u64,
+    /// The mask, or blinding factor
+    pub mask: PrivateKey,
+}
+
+impl Opening {
+    /// Compute the commitment `C = mask*G + amount*H`
+    pub fn commitment(&self) -> Key {
+        let h: EdwardsPoint = CompressedEdwardsY(H)
+            .decompress()
+            .expect("H is a valid point. QED");
+        let point = &self.mask.scalar * &ED25519_BASEPOINT_TABLE + Scalar::from(self.amount) * h;
+        Key {
+            key: point.compress().to_bytes(),
+        }
+    }
+}
+
 // ====================================================================
 /// Borromean signature for range commitment
 #[derive(Debug, Clone)]
@@ -327,6 +398,18 @@ impl Display for RctSigBase {
 }
 
 impl RctSigBase {
+    /// Decrypt the amount and the mask of the output at `index` with the output shared key
+    /// `Hs(8*a*R || n)`, return `None` if the output does not exist or the decrypted values do
+    /// not open the output commitment
+    pub fn open_output(&self, index: usize, shared_key: &PrivateKey) -> Option<Opening> {
+        let opening = self.ecdh_info.get(index)?.open(shared_key);
+        if opening.commitment().key == self.out_pk.get(index)?.mask.key {
+            Some(opening)
+        } else {
+            None
+        }
+    }
+
     /// Decode a RingCT base signature given the number of inputs and outputs of the transaction
     pub fn consensus_decode<D: Decoder>(
         d: &mut D,
@@ -0,0 +1,444 @@
+// Rust Monero Library
+// Written in 2019 by
+//   h4sh3d <h4sh3d@protonmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+
+//! Payment URIs
+//!
+//! A payment URI requests a payment to one or more addresses, e.g.
+//! `monero:<address>?tx_amount=<amount>&recipient_name=<name>&tx_description=<description>`.
+//! Multiple recipients are given as `;`-separated addresses, with as many `;`-separated amounts
+//! and names. Amounts are decimal numbers of monero, names and descriptions are percent-encoded.
+//!
+//! ```rust
+//! use monero::network::Network;
+//! use monero::util::amount::Amount;
+//! use monero::util::uri::PaymentUri;
+//!
+//! let uri = "monero:4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra?tx_amount=1.5&tx_description=Coffee%20beans";
+//! let payment = PaymentUri::parse(uri, Network::Mainnet).unwrap();
+//!
+//! assert_eq!(Some(Amount::from_pico(1_500_000_000_000)), payment.recipients[0].amount);
+//! assert_eq!(Some("Coffee beans"), payment.description.as_deref());
+//! assert_eq!(uri, payment.to_string());
+//! ```
+//!
+//! **Same as** `wallet2::parse_uri` and `wallet2::make_uri` in [`wallet2.cpp`](https://github.com/monero-project/monero/blob/159c78758af0a0af9df9a4f9ab81888f9322e9be/src/wallet/wallet2.cpp)
+//!
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::cryptonote::hash::Hash;
+use crate::network::Network;
+use crate::util::address::{self, Address, AddressType};
+use crate::util::amount::{self, Amount, Denomination};
+
+/// Scheme of the payment URIs
+pub const SCHEME: &str = "monero:";
+
+/// Possible errors when parsing a payment URI
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    /// The URI does not start with `monero:`
+    #[error("URI has wrong scheme, expected \"monero:\"")]
+    InvalidScheme,
+    /// Invalid address
+    #[error("URI has wrong address: {0}")]
+    Address(#[from] address::Error),
+    /// A parameter is not a `key=value` pair
+    #[error("URI has wrong parameter: {0}")]
+    InvalidParameter(String),
+    /// A parameter is given more than once
+    #[error("URI has more than one instance of {0}")]
+    DuplicateParameter(String),
+    /// Invalid amount
+    #[error("URI has invalid amount: {0}")]
+    Amount(#[from] amount::ParsingError),
+    /// The number of values of a parameter does not match the number of addresses
+    #[error("URI has {found} values of {parameter} for {expected} addresses")]
+    RecipientCountMismatch {
+        /// The parameter
+        parameter: &'static str,
+        /// Number of addresses
+        expected: usize,
+        /// Number of values
+        found: usize,
+    },
+    /// The payment id is not 32 bytes in hex
+    #[error("invalid payment id: {0}")]
+    InvalidPaymentId(String),
+    /// A payment id is given along an integrated address
+    #[error("separate payment id given with an integrated address")]
+    PaymentIdWithIntegratedAddress,
+    /// A parameter prefixed by `req-` is not supported
+    #[error("URI has unknown required parameter: {0}")]
+    UnknownRequiredParameter(String),
+    /// A value is not valid percent-encoded UTF-8
+    #[error("URI has invalid percent-encoding: {0}")]
+    InvalidEncoding(String),
+}
+
+/// A recipient of a payment URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    /// The address
+    pub address: Address,
+    /// The requested amount
+    pub amount: Option<Amount>,
+    /// The name of the recipient
+    pub name: Option<String>,
+}
+
+impl Recipient {
+    /// Create a recipient without amount nor name
+    pub fn new(address: Address) -> Recipient {
+        Recipient {
+            address,
+            amount: None,
+            name: None,
+        }
+    }
+}
+
+/// A payment URI
+///
+/// Recipients are displayed with an amount of `0` when only some of them have an amount, as the
+/// number of amounts must match the number of addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentUri {
+    /// Recipients of the payment, at least one
+    pub recipients: Vec<Recipient>,
+    /// Long payment id, not allowed with integrated addresses
+    pub payment_id: Option<Hash>,
+    /// Description of the payment
+    pub description: Option<String>,
+    /// Unknown parameters as `(key, value)` pairs, values are not decoded
+    pub unknown_parameters: Vec<(String, String)>,
+}
+
+impl PaymentUri {
+    /// Create a payment URI to a single recipient
+    pub fn new(recipient: Recipient) -> PaymentUri {
+        PaymentUri {
+            recipients: vec![recipient],
+            payment_id: None,
+            description: None,
+            unknown_parameters: vec![],
+        }
+    }
+
+    /// Parse a payment URI, every address must be valid on the network
+    ///
+    /// Unknown parameters are kept in [`PaymentUri::unknown_parameters`], unless they are
+    /// prefixed by `req-` in which case the URI is rejected.
+    pub fn parse(uri: &str, network: Network) -> Result<PaymentUri, Error> {
+        if !uri.starts_with(SCHEME) {
+            return Err(Error::InvalidScheme);
+        }
+        let remainder = &uri[SCHEME.len()..];
+        let (addresses, body) = match remainder.find('?') {
+            Some(i) => (&remainder[..i], &remainder[i + 1..]),
+            None => (remainder, ""),
+        };
+
+        let mut recipients = addresses
+            .split(';')
+            .map(|address| {
+                Address::from_str_with_network(address, network)
+                    .map(Recipient::new)
+                    .map_err(Error::Address)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut payment_uri = PaymentUri {
+            recipients: vec![],
+            payment_id: None,
+            description: None,
+            unknown_parameters: vec![],
+        };
+
+        let mut seen: Vec<&str> = vec![];
+        for arg in body.split('&').filter(|arg| !arg.is_empty()) {
+            let mut kv = arg.split('=');
+            let (key, value) = match (kv.next(), kv.next(), kv.next()) {
+                (Some(key), Some(value), None) => (key, value),
+                _ => return Err(Error::InvalidParameter(arg.to_string())),
+            };
+            if seen.contains(&key) {
+                return Err(Error::DuplicateParameter(key.to_string()));
+            }
+            seen.push(key);
+
+            match key {
+                "tx_amount" => {
+                    let amounts = split_values("tx_amount", value, recipients.len())?;
+                    for (recipient, amount) in recipients.iter_mut().zip(amounts) {
+                        recipient.amount = Some(Amount::from_str_in(amount, Denomination::Monero)?);
+                    }
+                }
+                "tx_payment_id" => {
+                    if recipients
+                        .iter()
+                        .any(|r| matches!(r.address.addr_type, AddressType::Integrated(_)))
+                    {
+                        return Err(Error::PaymentIdWithIntegratedAddress);
+                    }
+                    payment_uri.payment_id = Some(parse_payment_id(value)?);
+                }
+                "recipient_name" => {
+                    let names = split_values("recipient_name", value, recipients.len())?;
+                    for (recipient, name) in recipients.iter_mut().zip(names) {
+                        recipient.name = Some(percent_decode(name)?).filter(|n| !n.is_empty());
+                    }
+                }
+                "tx_description" => payment_uri.description = Some(percent_decode(value)?),
+                key if key.starts_with("req-") => {
+                    return Err(Error::UnknownRequiredParameter(key.to_string()))
+                }
+                key => payment_uri
+                    .unknown_parameters
+                    .push((key.to_string(), value.to_string())),
+            }
+        }
+
+        payment_uri.recipients = recipients;
+        Ok(payment_uri)
+    }
+}
+
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let addresses: Vec<String> = self
+            .recipients
+            .iter()
+            .map(|r| r.address.to_string())
+            .collect();
+        write!(f, "{}{}", SCHEME, addresses.join(";"))?;
+
+        let mut params: Vec<String> = vec![];
+        if self.recipients.iter().any(|r| r.amount.is_some()) {
+            let amounts: Vec<String> = self
+                .recipients
+                .iter()
+                .map(|r| {
+                    r.amount
+                        .unwrap_or_default()
+                        .to_string_in(Denomination::Monero)
+                })
+                .collect();
+            params.push(format!("tx_amount={}", amounts.join(";")));
+        }
+        if let Some(payment_id) = &self.payment_id {
+            params.push(format!("tx_payment_id={:x}", payment_id));
+        }
+        if self.recipients.iter().any(|r| r.name.is_some()) {
+            let names: Vec<String> = self
+                .recipients
+                .iter()
+                .map(|r| percent_encode(r.name.as_deref().unwrap_or("")))
+                .collect();
+            params.push(format!("recipient_name={}", names.join(";")));
+        }
+        if let Some(description) = &self.description {
+            params.push(format!("tx_description={}", percent_encode(description)));
+        }
+        for (key, value) in &self.unknown_parameters {
+            params.push(format!("{}={}", key, value));
+        }
+
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Split the `;`-separated values of a parameter, one per recipient
+fn split_values<'a>(
+    parameter: &'static str,
+    value: &'a str,
+    expected: usize,
+) -> Result<Vec<&'a str>, Error> {
+    let values: Vec<&str> = value.split(';').collect();
+    if values.len() != expected {
+        return Err(Error::RecipientCountMismatch {
+            parameter,
+            expected,
+            found: values.len(),
+        });
+    }
+    Ok(values)
+}
+
+fn parse_payment_id(value: &str) -> Result<Hash, Error> {
+    match hex::decode(value) {
+        Ok(bytes) if bytes.len() == 32 => Ok(Hash::from_slice(&bytes)),
+        _ => Err(Error::InvalidPaymentId(value.to_string())),
+    }
+}
+
+/// Decode the `%XX` escapes of a value
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let invalid = || Error::InvalidEncoding(value.to_string());
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [
+                iter.next().ok_or_else(invalid)?,
+                iter.next().ok_or_else(invalid)?,
+            ];
+            let decoded = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(invalid)?;
+            bytes.push(decoded);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Escape every byte of a value but the unreserved characters of RFC 3986
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, PaymentUri, Recipient};
+    use crate::cryptonote::hash::Hash;
+    use crate::network::Network;
+    use crate::util::address::{self, Address};
+    use crate::util::amount::Amount;
+
+    const STANDARD: &str = "4ADT1BtbxqEWeMKp9GgPr2NeyJXXtNxvoDawpyA4WpzFcGcoHUvXeijE66DNfohE9r1bQYaBiQjEtKE7CtkTdLwiDznFzra";
+    const INTEGRATED: &str = "4Byr22j9M2878Mtyb3fEPcBNwBZf5EXqn1Yi6VzR46618SFBrYysab2Cs1474CVDbsh94AJq7vuV3Z2DRq4zLcY3LHzo1Nbv3d8J6VhvCV";
+    const SUBADDRESS: &str = "8AW7SotwFrqfAKnibspuuhfowW4g3asvpQvdrTmPcpNr2GmXPtBBSxUPZQATAt8Vw2hiX9GDyxB4tMNgHjwt8qYsCeFDVvn";
+
+    #[test]
+    fn parse_and_display() {
+        let uri = format!("monero:{}", STANDARD);
+        let payment = PaymentUri::parse(&uri, Network::Mainnet).unwrap();
+        assert_eq!(
+            PaymentUri::new(Recipient::new(STANDARD.parse().unwrap())),
+            payment
+        );
+        assert_eq!(uri, payment.to_string());
+
+        let uri = format!(
+            "monero:{};{}?tx_amount=0.25;3&tx_payment_id={}&recipient_name=Alice;Bob%20%26%20Co&tx_description=Two%20payments&label=x",
+            STANDARD,
+            SUBADDRESS,
+            "42".repeat(32)
+        );
+        let payment = PaymentUri::parse(&uri, Network::Mainnet).unwrap();
+        assert_eq!(2, payment.recipients.len());
+        assert_eq!(
+            Some(Amount::from_pico(250_000_000_000)),
+            payment.recipients[0].amount
+        );
+        assert_eq!(Some(Amount::ONE_XMR * 3), payment.recipients[1].amount);
+        assert_eq!(Some("Bob & Co"), payment.recipients[1].name.as_deref());
+        assert_eq!(Some(Hash::from_slice(&[0x42; 32])), payment.payment_id);
+        assert_eq!(Some("Two payments"), payment.description.as_deref());
+        assert_eq!(
+            vec![("label".to_string(), "x".to_string())],
+            payment.unknown_parameters
+        );
+        assert_eq!(uri, payment.to_string());
+    }
+
+    #[test]
+    fn invalid_uris() {
+        let parse = |uri: String| PaymentUri::parse(&uri, Network::Mainnet);
+
+        assert_eq!(
+            Err(Error::InvalidScheme),
+            parse(format!("bitcoin:{}", STANDARD))
+        );
+        assert_eq!(
+            Err(Error::Address(address::Error::WrongNetwork {
+                expected: Network::Stagenet,
+                found: Network::Mainnet,
+            })),
+            PaymentUri::parse(&format!("monero:{}", STANDARD), Network::Stagenet)
+        );
+        assert_eq!(
+            Err(Error::InvalidParameter("tx_amount".to_string())),
+            parse(format!("monero:{}?tx_amount", STANDARD))
+        );
+        assert_eq!(
+            Err(Error::DuplicateParameter("tx_amount".to_string())),
+            parse(format!("monero:{}?tx_amount=1&tx_amount=2", STANDARD))
+        );
+        assert!(matches!(
+            parse(format!("monero:{}?tx_amount=1.0.0", STANDARD)),
+            Err(Error::Amount(_))
+        ));
+        assert_eq!(
+            Err(Error::RecipientCountMismatch {
+                parameter: "tx_amount",
+                expected: 2,
+                found: 1,
+            }),
+            parse(format!("monero:{};{}?tx_amount=1", STANDARD, SUBADDRESS))
+        );
+        assert_eq!(
+            Err(Error::InvalidPaymentId("1234".to_string())),
+            parse(format!("monero:{}?tx_payment_id=1234", STANDARD))
+        );
+        assert_eq!(
+            Err(Error::PaymentIdWithIntegratedAddress),
+            parse(format!(
+                "monero:{}?tx_payment_id={}",
+                INTEGRATED,
+                "00".repeat(32)
+            ))
+        );
+        assert_eq!(
+            Err(Error::UnknownRequiredParameter("req-fee".to_string())),
+            parse(format!("monero:{}?req-fee=1", STANDARD))
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding("%zz".to_string())),
+            parse(format!("monero:{}?tx_description=%zz", STANDARD))
+        );
+    }
+
+    #[test]
+    fn display_partial_amounts() {
+        let mut payment = PaymentUri::new(Recipient {
+            address: STANDARD.parse::<Address>().unwrap(),
+            amount: Some(Amount::ONE_XMR),
+            name: None,
+        });
+        payment
+            .recipients
+            .push(Recipient::new(SUBADDRESS.parse().unwrap()));
+        assert_eq!(
+            format!("monero:{};{}?tx_amount=1;0", STANDARD, SUBADDRESS),
+            payment.to_string()
+        );
+    }
+}